@@ -0,0 +1,60 @@
+-- Create picture_status enum
+CREATE TYPE picture_status AS ENUM ('pending', 'processing', 'ready', 'failed');
+
+-- Create user_pictures table
+CREATE TABLE user_pictures (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    is_profile_picture BOOLEAN NOT NULL DEFAULT FALSE,
+    original_path VARCHAR(255) NOT NULL,
+    status picture_status NOT NULL DEFAULT 'pending',
+    width INTEGER,
+    height INTEGER,
+    processing_error TEXT,
+    processed_at TIMESTAMP WITH TIME ZONE,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+
+    CONSTRAINT user_pictures_original_path_not_empty CHECK (original_path != ''),
+    CONSTRAINT user_pictures_dimensions_positive CHECK (
+        (width IS NULL AND height IS NULL) OR (width > 0 AND height > 0)
+    )
+);
+
+-- Create picture_variants table
+CREATE TABLE picture_variants (
+    picture_id UUID NOT NULL REFERENCES user_pictures(id) ON DELETE CASCADE,
+    variant VARCHAR(20) NOT NULL,
+    format VARCHAR(10) NOT NULL,
+    path VARCHAR(255) NOT NULL,
+    width INTEGER NOT NULL,
+    height INTEGER NOT NULL,
+    byte_size INTEGER NOT NULL,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+
+    PRIMARY KEY (picture_id, variant, format),
+    CONSTRAINT picture_variants_variant_valid CHECK (variant IN ('thumbnail', 'card', 'full')),
+    CONSTRAINT picture_variants_format_valid CHECK (format IN ('webp', 'jpeg')),
+    CONSTRAINT picture_variants_dimensions_positive CHECK (width > 0 AND height > 0)
+);
+
+-- Create indexes for performance
+CREATE INDEX idx_user_pictures_user_id ON user_pictures(user_id);
+CREATE INDEX idx_user_pictures_status ON user_pictures(status) WHERE status IN ('pending', 'processing');
+CREATE UNIQUE INDEX idx_user_pictures_one_profile_picture ON user_pictures(user_id) WHERE is_profile_picture;
+
+-- Create trigger for updated_at
+CREATE TRIGGER update_user_pictures_updated_at
+    BEFORE UPDATE ON user_pictures
+    FOR EACH ROW
+    EXECUTE FUNCTION update_updated_at_column();
+
+-- Add comments for documentation
+COMMENT ON TABLE user_pictures IS 'Pictures uploaded by users (up to 5 per user)';
+COMMENT ON COLUMN user_pictures.is_profile_picture IS 'Whether this is the designated profile picture';
+COMMENT ON COLUMN user_pictures.original_path IS 'Path of the original relative to the media directory (metadata-free once processed)';
+COMMENT ON COLUMN user_pictures.status IS 'Processing pipeline status';
+COMMENT ON COLUMN user_pictures.processing_error IS 'Error message of the last failed processing attempt';
+COMMENT ON TABLE picture_variants IS 'Resized, metadata-free renditions of a picture';
+COMMENT ON COLUMN picture_variants.variant IS 'Rendition size (thumbnail, card, full)';
+COMMENT ON COLUMN picture_variants.format IS 'Encoding of the rendition (webp, jpeg)';
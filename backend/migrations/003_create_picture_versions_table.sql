@@ -0,0 +1,16 @@
+-- Create picture_versions table
+CREATE TABLE picture_versions (
+    picture_id UUID NOT NULL REFERENCES user_pictures(id) ON DELETE CASCADE,
+    version INTEGER NOT NULL,
+    edits JSONB NOT NULL DEFAULT '[]',
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+
+    PRIMARY KEY (picture_id, version),
+    CONSTRAINT picture_versions_version_positive CHECK (version > 0),
+    CONSTRAINT picture_versions_edits_is_array CHECK (jsonb_typeof(edits) = 'array')
+);
+
+-- Add comments for documentation
+COMMENT ON TABLE picture_versions IS 'Edited versions of a picture; version 0 is the unedited original';
+COMMENT ON COLUMN picture_versions.version IS 'Increasing version number, the highest one is displayed';
+COMMENT ON COLUMN picture_versions.edits IS 'Full edit list applied to the original, so any version can be re-rendered';
@@ -0,0 +1,30 @@
+-- Create moderation enums
+CREATE TYPE moderation_status AS ENUM ('open', 'reviewing', 'actioned', 'dismissed');
+CREATE TYPE moderation_item_kind AS ENUM ('duplicate_picture');
+
+-- Create moderation_queue table
+CREATE TABLE moderation_queue (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    subject_user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    kind moderation_item_kind NOT NULL,
+    status moderation_status NOT NULL DEFAULT 'open',
+    details JSONB NOT NULL DEFAULT '{}',
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW()
+);
+
+-- Create indexes for performance
+CREATE INDEX idx_moderation_queue_open ON moderation_queue(created_at) WHERE status IN ('open', 'reviewing');
+CREATE INDEX idx_moderation_queue_subject ON moderation_queue(subject_user_id);
+
+-- Create trigger for updated_at
+CREATE TRIGGER update_moderation_queue_updated_at
+    BEFORE UPDATE ON moderation_queue
+    FOR EACH ROW
+    EXECUTE FUNCTION update_updated_at_column();
+
+-- Add comments for documentation
+COMMENT ON TABLE moderation_queue IS 'Items awaiting review by moderators';
+COMMENT ON COLUMN moderation_queue.subject_user_id IS 'Account the item is about';
+COMMENT ON COLUMN moderation_queue.kind IS 'What raised the item';
+COMMENT ON COLUMN moderation_queue.details IS 'Kind-specific evidence, e.g. the matching pictures';
@@ -0,0 +1,31 @@
+-- Create picture_hashes table
+CREATE TABLE picture_hashes (
+    picture_id UUID PRIMARY KEY REFERENCES user_pictures(id) ON DELETE CASCADE,
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    dhash BIGINT NOT NULL,
+    bands INTEGER[] NOT NULL,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW()
+);
+
+-- Create picture_duplicates table
+CREATE TABLE picture_duplicates (
+    picture_id UUID NOT NULL REFERENCES user_pictures(id) ON DELETE CASCADE,
+    matched_picture_id UUID NOT NULL REFERENCES user_pictures(id) ON DELETE CASCADE,
+    distance SMALLINT NOT NULL,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+
+    PRIMARY KEY (picture_id, matched_picture_id),
+    CONSTRAINT picture_duplicates_distinct CHECK (picture_id != matched_picture_id),
+    CONSTRAINT picture_duplicates_distance_range CHECK (distance BETWEEN 0 AND 64)
+);
+
+-- Near-duplicate candidates share at least one exact band, which this index finds
+CREATE INDEX idx_picture_hashes_bands ON picture_hashes USING GIN (bands);
+CREATE INDEX idx_picture_hashes_user_id ON picture_hashes(user_id);
+CREATE INDEX idx_picture_duplicates_matched ON picture_duplicates(matched_picture_id);
+
+-- Add comments for documentation
+COMMENT ON TABLE picture_hashes IS 'Perceptual hashes of processed pictures for duplicate detection';
+COMMENT ON COLUMN picture_hashes.dhash IS '64-bit difference hash of the original picture';
+COMMENT ON COLUMN picture_hashes.bands IS 'The hash split into 8-bit bands, tagged with their position (position * 256 + value)';
+COMMENT ON TABLE picture_duplicates IS 'Pictures found to be near-duplicates of another account''s picture';
@@ -0,0 +1,29 @@
+-- Create location_source enum, ordered from least to most trusted
+CREATE TYPE location_source AS ENUM ('ip', 'gps', 'manual');
+
+-- Create user_locations table
+CREATE TABLE user_locations (
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    source location_source NOT NULL,
+    latitude DOUBLE PRECISION NOT NULL,
+    longitude DOUBLE PRECISION NOT NULL,
+    accuracy_m DOUBLE PRECISION,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+
+    PRIMARY KEY (user_id, source),
+    CONSTRAINT user_locations_latitude_range CHECK (latitude BETWEEN -90 AND 90),
+    CONSTRAINT user_locations_longitude_range CHECK (longitude BETWEEN -180 AND 180),
+    CONSTRAINT user_locations_accuracy_positive CHECK (accuracy_m IS NULL OR accuracy_m >= 0)
+);
+
+-- Create trigger for updated_at
+CREATE TRIGGER update_user_locations_updated_at
+    BEFORE UPDATE ON user_locations
+    FOR EACH ROW
+    EXECUTE FUNCTION update_updated_at_column();
+
+-- Add comments for documentation
+COMMENT ON TABLE user_locations IS 'Latest known location of each user per source; manual beats gps beats ip';
+COMMENT ON COLUMN user_locations.source IS 'gps: browser geolocation, ip: GeoIP fallback, manual: user override';
+COMMENT ON COLUMN user_locations.accuracy_m IS 'Reported accuracy radius in meters, if known';
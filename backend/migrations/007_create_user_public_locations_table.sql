@@ -0,0 +1,26 @@
+-- Create user_public_locations table
+CREATE TABLE user_public_locations (
+    user_id UUID PRIMARY KEY REFERENCES users(id) ON DELETE CASCADE,
+    latitude DOUBLE PRECISION NOT NULL,
+    longitude DOUBLE PRECISION NOT NULL,
+    neighborhood VARCHAR(200),
+    city VARCHAR(200),
+    country_code CHAR(2),
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+
+    CONSTRAINT user_public_locations_latitude_range CHECK (latitude BETWEEN -90 AND 90),
+    CONSTRAINT user_public_locations_longitude_range CHECK (longitude BETWEEN -180 AND 180)
+);
+
+-- Create trigger for updated_at
+CREATE TRIGGER update_user_public_locations_updated_at
+    BEFORE UPDATE ON user_public_locations
+    FOR EACH ROW
+    EXECUTE FUNCTION update_updated_at_column();
+
+-- Add comments for documentation
+COMMENT ON TABLE user_public_locations IS 'Neighborhood-level location shown to other users, derived from the effective user_locations row';
+COMMENT ON COLUMN user_public_locations.latitude IS 'Centre of the neighborhood or grid cell, never the precise position';
+COMMENT ON COLUMN user_public_locations.neighborhood IS 'Reverse-geocoded neighborhood name, if the gazetteer has one nearby';
+COMMENT ON COLUMN user_public_locations.city IS 'Reverse-geocoded city name, if the gazetteer has one nearby';
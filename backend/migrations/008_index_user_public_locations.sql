@@ -0,0 +1,5 @@
+-- Spatial index for proximity queries: bounding boxes are matched against it
+-- before exact great-circle distances are computed
+CREATE INDEX idx_user_public_locations_point ON user_public_locations USING GIST (point(longitude, latitude));
+
+COMMENT ON INDEX idx_user_public_locations_point IS 'Bounding box prefilter for nearby-user queries';
@@ -0,0 +1,32 @@
+-- Create profile enums
+CREATE TYPE gender AS ENUM ('male', 'female', 'other');
+CREATE TYPE sexual_preference AS ENUM ('heterosexual', 'homosexual', 'bisexual');
+
+-- Create user_profiles table
+CREATE TABLE user_profiles (
+    user_id UUID PRIMARY KEY REFERENCES users(id) ON DELETE CASCADE,
+    gender gender,
+    sexual_preference sexual_preference,
+    biography TEXT,
+    birth_date DATE,
+    fame_rating DOUBLE PRECISION NOT NULL DEFAULT 0,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+
+    CONSTRAINT user_profiles_fame_rating_range CHECK (fame_rating BETWEEN 0 AND 100),
+    CONSTRAINT user_profiles_biography_length CHECK (LENGTH(biography) <= 2000)
+);
+
+-- Create indexes for performance
+CREATE INDEX idx_user_profiles_gender ON user_profiles(gender);
+
+-- Create trigger for updated_at
+CREATE TRIGGER update_user_profiles_updated_at
+    BEFORE UPDATE ON user_profiles
+    FOR EACH ROW
+    EXECUTE FUNCTION update_updated_at_column();
+
+-- Add comments for documentation
+COMMENT ON TABLE user_profiles IS 'Mutable dating profile data, kept apart from the core users table';
+COMMENT ON COLUMN user_profiles.sexual_preference IS 'NULL means bisexual';
+COMMENT ON COLUMN user_profiles.fame_rating IS 'Popularity score from 0 to 100';
@@ -0,0 +1,25 @@
+-- Create tags table
+CREATE TABLE tags (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    name VARCHAR(50) NOT NULL UNIQUE,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+
+    CONSTRAINT tags_name_format CHECK (name ~ '^[a-z0-9_]{1,50}$')
+);
+
+-- Create user_tags table
+CREATE TABLE user_tags (
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    tag_id UUID NOT NULL REFERENCES tags(id) ON DELETE CASCADE,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+
+    PRIMARY KEY (user_id, tag_id)
+);
+
+-- Create indexes for performance
+CREATE INDEX idx_user_tags_tag_id ON user_tags(tag_id);
+
+-- Add comments for documentation
+COMMENT ON TABLE tags IS 'Interest tags, shared between users (e.g. vegan, geek)';
+COMMENT ON COLUMN tags.name IS 'Lowercase tag name without the leading #';
+COMMENT ON TABLE user_tags IS 'Interests of each user';
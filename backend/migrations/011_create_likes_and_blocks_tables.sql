@@ -0,0 +1,27 @@
+-- Create likes table
+CREATE TABLE likes (
+    liker_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    liked_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+
+    PRIMARY KEY (liker_id, liked_id),
+    CONSTRAINT likes_not_self CHECK (liker_id != liked_id)
+);
+
+-- Create blocks table
+CREATE TABLE blocks (
+    blocker_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    blocked_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+
+    PRIMARY KEY (blocker_id, blocked_id),
+    CONSTRAINT blocks_not_self CHECK (blocker_id != blocked_id)
+);
+
+-- Create indexes for performance
+CREATE INDEX idx_likes_liked_id ON likes(liked_id);
+CREATE INDEX idx_blocks_blocked_id ON blocks(blocked_id);
+
+-- Add comments for documentation
+COMMENT ON TABLE likes IS 'One-way likes; two opposite likes make a connection';
+COMMENT ON TABLE blocks IS 'Blocked users never see each other in browse or search';
@@ -0,0 +1,37 @@
+-- Create fame enums
+CREATE TYPE fame_event_kind AS ENUM ('like', 'view', 'connection', 'report');
+CREATE TYPE fame_trigger AS ENUM ('event_recorded', 'event_withdrawn', 'profile_updated', 'scheduled');
+
+-- Create fame_events table
+CREATE TABLE fame_events (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    actor_id UUID REFERENCES users(id) ON DELETE CASCADE,
+    kind fame_event_kind NOT NULL,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW()
+);
+
+-- Create fame_history table
+CREATE TABLE fame_history (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    rating DOUBLE PRECISION NOT NULL,
+    breakdown JSONB NOT NULL,
+    trigger fame_trigger NOT NULL,
+    event_kind fame_event_kind,
+    computed_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+
+    CONSTRAINT fame_history_rating_range CHECK (rating BETWEEN 0 AND 100)
+);
+
+-- Create indexes for performance
+CREATE INDEX idx_fame_events_user_id ON fame_events(user_id, kind);
+CREATE INDEX idx_fame_events_actor_id ON fame_events(actor_id, user_id, kind);
+CREATE INDEX idx_fame_history_user_id ON fame_history(user_id, computed_at DESC);
+
+-- Add comments for documentation
+COMMENT ON TABLE fame_events IS 'Interactions that count towards the fame rating of user_id';
+COMMENT ON COLUMN fame_events.actor_id IS 'User who caused the event; withdrawing a like removes it';
+COMMENT ON TABLE fame_history IS 'Every stored fame rating with the components it was computed from';
+COMMENT ON COLUMN fame_history.breakdown IS 'Decayed inputs and weighted components, to explain the rating';
+COMMENT ON COLUMN fame_history.event_kind IS 'Kind of the recorded or withdrawn event, NULL for other triggers';
@@ -0,0 +1,35 @@
+-- Create notification_kind enum
+CREATE TYPE notification_kind AS ENUM ('profile_viewed');
+
+-- Create profile_visits table
+CREATE TABLE profile_visits (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    visitor_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    visited_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    visited_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+
+    CONSTRAINT profile_visits_not_self CHECK (visitor_id != visited_id)
+);
+
+-- Create notifications table
+CREATE TABLE notifications (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    actor_id UUID REFERENCES users(id) ON DELETE CASCADE,
+    kind notification_kind NOT NULL,
+    read_at TIMESTAMP WITH TIME ZONE,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW()
+);
+
+-- Create indexes for performance
+CREATE INDEX idx_profile_visits_visited_id ON profile_visits(visited_id, visited_at DESC, id DESC);
+CREATE INDEX idx_profile_visits_visitor_id ON profile_visits(visitor_id, visited_id, visited_at DESC);
+DROP INDEX idx_likes_liked_id;
+CREATE INDEX idx_likes_liked_id ON likes(liked_id, created_at DESC, liker_id DESC);
+CREATE INDEX idx_notifications_user_id ON notifications(user_id, created_at DESC, id DESC);
+CREATE INDEX idx_notifications_unread ON notifications(user_id) WHERE read_at IS NULL;
+
+-- Add comments for documentation
+COMMENT ON TABLE profile_visits IS 'Profile views; repeated views by the same visitor within an hour are stored once';
+COMMENT ON TABLE notifications IS 'Events shown to user_id, caused by actor_id when another user is involved';
+COMMENT ON COLUMN notifications.read_at IS 'NULL while unread';
@@ -0,0 +1,9 @@
+-- Create user_presence table
+CREATE TABLE user_presence (
+    user_id UUID PRIMARY KEY REFERENCES users(id) ON DELETE CASCADE,
+    last_seen_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW()
+);
+
+-- Add comments for documentation
+COMMENT ON TABLE user_presence IS 'When each user was last active, kept apart from the core users table';
+COMMENT ON COLUMN user_presence.last_seen_at IS 'Last authenticated activity; users never seen have no row';
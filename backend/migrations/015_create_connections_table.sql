@@ -0,0 +1,27 @@
+-- Add notification kinds for likes and connections
+ALTER TYPE notification_kind ADD VALUE 'liked';
+ALTER TYPE notification_kind ADD VALUE 'connected';
+ALTER TYPE notification_kind ADD VALUE 'disconnected';
+
+-- Create connections table
+CREATE TABLE connections (
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    other_user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+
+    PRIMARY KEY (user_id, other_user_id),
+    CONSTRAINT connections_ordered CHECK (user_id < other_user_id)
+);
+
+-- Connect users who already like each other
+INSERT INTO connections (user_id, other_user_id, created_at)
+SELECT l.liker_id, l.liked_id, GREATEST(l.created_at, back.created_at)
+FROM likes l
+JOIN likes back ON back.liker_id = l.liked_id AND back.liked_id = l.liker_id
+WHERE l.liker_id < l.liked_id;
+
+-- Create indexes for performance
+CREATE INDEX idx_connections_other_user_id ON connections(other_user_id);
+
+-- Add comments for documentation
+COMMENT ON TABLE connections IS 'Pairs of users who like each other, stored once with the smaller id first';
@@ -0,0 +1,13 @@
+-- Create the block check shared by every query that lists or links users
+CREATE FUNCTION is_blocked_between(user_id UUID, other_id UUID) RETURNS BOOLEAN
+LANGUAGE SQL STABLE
+AS $$
+    SELECT EXISTS (
+        SELECT 1 FROM blocks
+        WHERE (blocker_id = user_id AND blocked_id = other_id)
+        OR (blocker_id = other_id AND blocked_id = user_id)
+    )
+$$;
+
+-- Add comments for documentation
+COMMENT ON FUNCTION is_blocked_between(UUID, UUID) IS 'Whether either user blocked the other; inlined by the planner like the subquery it contains';
@@ -0,0 +1,26 @@
+-- Create report enums
+CREATE TYPE report_reason AS ENUM ('fake_account', 'spam', 'harassment', 'inappropriate_content', 'underage', 'other');
+ALTER TYPE moderation_item_kind ADD VALUE 'reported_account';
+
+-- Create reports table
+CREATE TABLE reports (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    reporter_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    reported_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    reason report_reason NOT NULL,
+    details TEXT,
+    moderation_item_id UUID REFERENCES moderation_queue(id) ON DELETE SET NULL,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+
+    CONSTRAINT reports_not_self CHECK (reporter_id != reported_id),
+    CONSTRAINT reports_once_per_reporter UNIQUE (reporter_id, reported_id),
+    CONSTRAINT reports_details_length CHECK (char_length(details) <= 1000)
+);
+
+-- Create indexes for performance
+CREATE INDEX idx_reports_reported_id ON reports(reported_id);
+CREATE INDEX idx_reports_moderation_item_id ON reports(moderation_item_id);
+
+-- Add comments for documentation
+COMMENT ON TABLE reports IS 'Users reporting other users, at most once per pair';
+COMMENT ON COLUMN reports.moderation_item_id IS 'The reported_account queue item reviewing this report';
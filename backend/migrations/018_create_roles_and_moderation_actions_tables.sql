@@ -0,0 +1,36 @@
+-- Create role and moderation action enums
+CREATE TYPE user_role AS ENUM ('user', 'moderator', 'admin');
+CREATE TYPE moderation_action_kind AS ENUM ('suspend', 'ban', 'reinstate', 'restore', 'change_role', 'review');
+
+-- Create user_roles table
+CREATE TABLE user_roles (
+    user_id UUID PRIMARY KEY REFERENCES users(id) ON DELETE CASCADE,
+    role user_role NOT NULL,
+    granted_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    granted_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW()
+);
+
+-- Create moderation_actions table
+CREATE TABLE moderation_actions (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    actor_id UUID REFERENCES users(id) ON DELETE SET NULL,
+    subject_user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    kind moderation_action_kind NOT NULL,
+    reason TEXT,
+    expires_at TIMESTAMP WITH TIME ZONE,
+    moderation_item_id UUID REFERENCES moderation_queue(id) ON DELETE SET NULL,
+    details JSONB NOT NULL DEFAULT '{}',
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW()
+);
+
+-- Create indexes for performance
+CREATE INDEX idx_moderation_actions_created_at ON moderation_actions(created_at DESC, id DESC);
+CREATE INDEX idx_moderation_actions_subject ON moderation_actions(subject_user_id, created_at DESC, id DESC);
+CREATE INDEX idx_moderation_actions_actor ON moderation_actions(actor_id, created_at DESC, id DESC);
+CREATE INDEX idx_moderation_queue_created_at ON moderation_queue(created_at DESC, id DESC);
+
+-- Add comments for documentation
+COMMENT ON TABLE user_roles IS 'Users with more than the default user role';
+COMMENT ON TABLE moderation_actions IS 'Audit log of every moderation action, written with the change it records';
+COMMENT ON COLUMN moderation_actions.actor_id IS 'Moderator or admin who acted; NULL for automatic actions';
+COMMENT ON COLUMN moderation_actions.details IS 'Action-specific data, e.g. the new role or review status';
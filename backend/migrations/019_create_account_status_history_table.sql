@@ -0,0 +1,33 @@
+-- Create account_status_history table
+CREATE TABLE account_status_history (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    status account_status NOT NULL,
+    previous_status account_status NOT NULL,
+    changed_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    reason TEXT,
+    expires_at TIMESTAMP WITH TIME ZONE,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+
+    CONSTRAINT only_suspensions_expire CHECK (expires_at IS NULL OR status = 'suspended')
+);
+
+-- Create indexes for performance
+CREATE INDEX idx_account_status_history_user ON account_status_history(user_id, created_at DESC, id DESC);
+CREATE INDEX idx_users_suspended ON users(id) WHERE account_status = 'suspended';
+
+-- Start the history of accounts that are not active from their last moderation action
+INSERT INTO account_status_history (user_id, status, previous_status, changed_by, reason, expires_at, created_at)
+SELECT DISTINCT ON (a.subject_user_id)
+       a.subject_user_id, u.account_status, 'active', a.actor_id, a.reason,
+       CASE WHEN u.account_status = 'suspended' THEN a.expires_at END, a.created_at
+FROM moderation_actions a
+JOIN users u ON u.id = a.subject_user_id
+WHERE u.account_status <> 'active'
+AND a.kind IN ('suspend', 'ban')
+ORDER BY a.subject_user_id, a.created_at DESC, a.id DESC;
+
+-- Add comments for documentation
+COMMENT ON TABLE account_status_history IS 'Every change of an account status, with who made it and why';
+COMMENT ON COLUMN account_status_history.changed_by IS 'Moderator who changed the status; NULL for automatic changes';
+COMMENT ON COLUMN account_status_history.expires_at IS 'When a suspension is lifted automatically; NULL until a moderator lifts it';
@@ -0,0 +1,35 @@
+-- Create conversations table
+CREATE TABLE conversations (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    other_user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    user_last_read_at TIMESTAMP WITH TIME ZONE,
+    other_user_last_read_at TIMESTAMP WITH TIME ZONE,
+    last_message_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+
+    CONSTRAINT one_conversation_per_pair UNIQUE (user_id, other_user_id),
+    CONSTRAINT ordered_pair CHECK (user_id < other_user_id)
+);
+
+-- Create messages table
+CREATE TABLE messages (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    conversation_id UUID NOT NULL REFERENCES conversations(id) ON DELETE CASCADE,
+    sender_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    body TEXT NOT NULL,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+
+    CONSTRAINT body_length CHECK (char_length(body) BETWEEN 1 AND 2000)
+);
+
+-- Create indexes for performance
+CREATE INDEX idx_conversations_user ON conversations(user_id, last_message_at DESC, id DESC);
+CREATE INDEX idx_conversations_other_user ON conversations(other_user_id, last_message_at DESC, id DESC);
+CREATE INDEX idx_messages_conversation ON messages(conversation_id, created_at DESC, id DESC);
+
+-- Add comments for documentation
+COMMENT ON TABLE conversations IS 'One-to-one chat between two users, stored once per pair with the lower id first';
+COMMENT ON COLUMN conversations.user_last_read_at IS 'Messages to user_id up to this time have been read';
+COMMENT ON COLUMN conversations.other_user_last_read_at IS 'Messages to other_user_id up to this time have been read';
+COMMENT ON TABLE messages IS 'Chat messages, sanitized before they are stored';
@@ -0,0 +1,13 @@
+-- Reports filed by the server have no reporter
+ALTER TABLE reports ALTER COLUMN reporter_id DROP NOT NULL;
+ALTER TABLE reports ADD COLUMN matched_user_id UUID REFERENCES users(id) ON DELETE CASCADE;
+ALTER TABLE reports ADD CONSTRAINT reports_automatic_have_match
+    CHECK ((reporter_id IS NULL) = (matched_user_id IS NOT NULL));
+
+-- One automatic report per account and account its picture matched
+CREATE UNIQUE INDEX idx_reports_automatic_once ON reports(reported_id, matched_user_id)
+    WHERE reporter_id IS NULL;
+
+-- Add comments for documentation
+COMMENT ON COLUMN reports.reporter_id IS 'User who filed the report, NULL for automatic reports';
+COMMENT ON COLUMN reports.matched_user_id IS 'For automatic reports, the other account with a near-duplicate picture';
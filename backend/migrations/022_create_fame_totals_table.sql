@@ -0,0 +1,22 @@
+-- Create fame_totals table
+CREATE TABLE fame_totals (
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    kind fame_event_kind NOT NULL,
+    total DOUBLE PRECISION NOT NULL DEFAULT 0,
+    as_of TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+
+    PRIMARY KEY (user_id, kind),
+    CONSTRAINT fame_totals_total_positive CHECK (total >= 0)
+);
+
+-- Start from the events recorded so far, with the 30 day half-life of the fame service
+INSERT INTO fame_totals (user_id, kind, total, as_of)
+SELECT user_id, kind,
+       SUM(POWER(0.5, GREATEST(EXTRACT(EPOCH FROM NOW() - created_at), 0) / 86400 / 30)::float8),
+       NOW()
+FROM fame_events
+GROUP BY user_id, kind;
+
+-- Add comments for documentation
+COMMENT ON TABLE fame_totals IS 'Running decayed count of the fame events of each kind per user';
+COMMENT ON COLUMN fame_totals.total IS 'Decayed count as of as_of; it keeps halving every half-life from there';
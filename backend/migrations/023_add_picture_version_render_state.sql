@@ -0,0 +1,9 @@
+-- Track which picture versions were rendered, so a picture stays shown while a new one renders
+ALTER TABLE picture_versions ADD COLUMN rendered_at TIMESTAMP WITH TIME ZONE;
+
+-- Versions saved so far were rendered, or replaced by a later one that was
+UPDATE picture_versions SET rendered_at = created_at;
+
+CREATE INDEX idx_picture_versions_unrendered ON picture_versions(picture_id) WHERE rendered_at IS NULL;
+
+COMMENT ON COLUMN picture_versions.rendered_at IS 'When the renditions of this version replaced the previous ones; NULL while rendering';
@@ -0,0 +1,13 @@
+-- Track the queue item behind each status change, so dismissing reports only lifts the suspension they caused
+ALTER TABLE account_status_history ADD COLUMN moderation_item_id UUID REFERENCES moderation_queue(id) ON DELETE SET NULL;
+
+-- Changes so far were recorded in the same transaction as the action that made them
+UPDATE account_status_history h
+SET moderation_item_id = a.moderation_item_id
+FROM moderation_actions a
+WHERE a.subject_user_id = h.user_id
+AND a.created_at = h.created_at
+AND a.actor_id IS NOT DISTINCT FROM h.changed_by
+AND a.moderation_item_id IS NOT NULL;
+
+COMMENT ON COLUMN account_status_history.moderation_item_id IS 'The queue item the change was made for, if any';
@@ -46,7 +46,10 @@ psql "$POSTGRES_URL" -q -c "DROP DATABASE IF EXISTS $DB_NAME;"
 echo "Creating fresh database '$DB_NAME'..."
 psql "$POSTGRES_URL" -q -c "CREATE DATABASE $DB_NAME OWNER $DB_USER;"
 
-echo "Running setup database migration..."
-psql "$DATABASE_URL" -q -f ../migrations/001_create_users_table.sql
+echo "Running database migrations..."
+for migration in ../migrations/*.sql; do
+    echo "Applying $(basename "$migration")..."
+    psql "$DATABASE_URL" -q -f "$migration"
+done
 
 echo "✅ Database nuked and recreated successfully."
\ No newline at end of file
@@ -12,6 +12,9 @@ if [ -z "$DATABASE_URL" ]; then
 fi
 echo "Setting up Matcha Backend Database..."
 
-psql "$DATABASE_URL" -q -f ../migrations/001_create_users_table.sql
+for migration in ../migrations/*.sql; do
+    echo "Applying $(basename "$migration")..."
+    psql "$DATABASE_URL" -q -f "$migration"
+done
 
 echo "✅ Database setup complete."
@@ -0,0 +1,619 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::{IntoResponse, Json, Response},
+    Extension,
+};
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use serde_json::json;
+use uuid::Uuid;
+
+use crate::api::database_error;
+use crate::api::pagination::{time_cursor, time_page_params, PageQuery};
+use crate::database::moderation_repository::StatusChange;
+use crate::database::{ModerationRepository, UserRepository};
+use crate::enums::{
+    AccountStatus, ModerationActionKind, ModerationItemKind, ModerationStatus, Role,
+};
+use crate::middleware::auth::AuthUser;
+use crate::models::moderation::{validate_reason, validate_suspension_end};
+use crate::models::{AdminUserView, NewModerationAction, Page, User};
+use crate::validation::core::ValidationError;
+use crate::AppState;
+
+const USERS_CURSOR_SCOPE: &str = "admin_users";
+const QUEUE_CURSOR_SCOPE: &str = "moderation_queue";
+const AUDIT_CURSOR_SCOPE: &str = "moderation_actions";
+const STATUS_HISTORY_CURSOR_SCOPE: &str = "account_status_history";
+
+#[derive(Debug, Deserialize)]
+pub struct UserFilter {
+    /// Start of a username or email.
+    pub q: Option<String>,
+    pub status: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct QueueFilter {
+    pub status: Option<String>,
+    pub kind: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AuditFilter {
+    pub subject_id: Option<Uuid>,
+    pub actor_id: Option<Uuid>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SuspendRequest {
+    pub reason: String,
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ReasonRequest {
+    pub reason: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ReviewRequest {
+    pub status: ModerationStatus,
+    pub reason: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RoleRequest {
+    pub role: Role,
+    pub reason: String,
+}
+
+/// Accounts by username or email prefix, deleted ones included.
+pub async fn search_users(
+    State(state): State<AppState>,
+    Query(filter): Query<UserFilter>,
+    Query(query): Query<PageQuery>,
+) -> impl IntoResponse {
+    let (limit, after) = match time_page_params(&state.cursor_signer, USERS_CURSOR_SCOPE, &query) {
+        Ok(params) => params,
+        Err(response) => return response,
+    };
+    let status = match filter.status.as_deref().map(str::parse::<AccountStatus>) {
+        None => None,
+        Some(Ok(status)) => Some(status),
+        Some(Err(_)) => return invalid_filter("status"),
+    };
+    let search = filter.q.as_deref().map(str::trim).filter(|q| !q.is_empty());
+
+    match UserRepository::new(state.db)
+        .search_accounts(search, status, after, limit + 1)
+        .await
+    {
+        Ok(accounts) => {
+            let page = Page::from_overfetched(accounts, limit as usize, |(user, _)| {
+                time_cursor(&state.cursor_signer, USERS_CURSOR_SCOPE, user.created_at, user.id)
+            })
+            .map(|(user, role)| AdminUserView::new(&user, role));
+
+            (StatusCode::OK, Json(page)).into_response()
+        }
+        Err(_) => database_error(),
+    }
+}
+
+pub async fn get_user(
+    State(state): State<AppState>,
+    Path(user_id): Path<Uuid>,
+) -> impl IntoResponse {
+    match UserRepository::new(state.db).find_account(user_id).await {
+        Ok(Some((user, role))) => {
+            (StatusCode::OK, Json(AdminUserView::new(&user, role))).into_response()
+        }
+        Ok(None) => user_not_found(),
+        Err(_) => database_error(),
+    }
+}
+
+/// Status changes of an account, newest first.
+pub async fn get_status_history(
+    State(state): State<AppState>,
+    Path(user_id): Path<Uuid>,
+    Query(query): Query<PageQuery>,
+) -> impl IntoResponse {
+    let (limit, after) =
+        match time_page_params(&state.cursor_signer, STATUS_HISTORY_CURSOR_SCOPE, &query) {
+            Ok(params) => params,
+            Err(response) => return response,
+        };
+
+    match UserRepository::new(state.db)
+        .find_status_history(user_id, after, limit + 1)
+        .await
+    {
+        Ok(changes) => {
+            let page = Page::from_overfetched(changes, limit as usize, |change| {
+                time_cursor(
+                    &state.cursor_signer,
+                    STATUS_HISTORY_CURSOR_SCOPE,
+                    change.created_at,
+                    change.id,
+                )
+            });
+
+            (StatusCode::OK, Json(page)).into_response()
+        }
+        Err(_) => database_error(),
+    }
+}
+
+pub async fn suspend_user(
+    State(state): State<AppState>,
+    Extension(auth_user): Extension<AuthUser>,
+    Extension(role): Extension<Role>,
+    Path(user_id): Path<Uuid>,
+    Json(data): Json<SuspendRequest>,
+) -> impl IntoResponse {
+    let (reason, expires_at) = match validate_reason(&data.reason)
+        .and_then(|reason| Ok((reason, validate_suspension_end(data.expires_at, Utc::now())?)))
+    {
+        Ok(valid) => valid,
+        Err(e) => return validation_error(e),
+    };
+    let (user, subject_role) = match load_subject(&state, &auth_user, role, user_id).await {
+        Ok(subject) => subject,
+        Err(response) => return response,
+    };
+    if user.account_status == AccountStatus::Banned && role < Role::Admin {
+        return only_admins_change_bans();
+    }
+
+    let action =
+        NewModerationAction::new(Some(auth_user.user.id), user_id, ModerationActionKind::Suspend)
+            .reason(Some(reason))
+            .expires_at(expires_at);
+    let from: &[AccountStatus] = if role < Role::Admin {
+        &[AccountStatus::Active, AccountStatus::Suspended]
+    } else {
+        &[
+            AccountStatus::Active,
+            AccountStatus::Suspended,
+            AccountStatus::Banned,
+        ]
+    };
+    change_account_status(&state, user, subject_role, action, from, AccountStatus::Suspended).await
+}
+
+pub async fn ban_user(
+    State(state): State<AppState>,
+    Extension(auth_user): Extension<AuthUser>,
+    Extension(role): Extension<Role>,
+    Path(user_id): Path<Uuid>,
+    Json(data): Json<ReasonRequest>,
+) -> impl IntoResponse {
+    let reason = match validate_reason(&data.reason) {
+        Ok(reason) => reason,
+        Err(e) => return validation_error(e),
+    };
+    let (user, subject_role) = match load_subject(&state, &auth_user, role, user_id).await {
+        Ok(subject) => subject,
+        Err(response) => return response,
+    };
+
+    let action =
+        NewModerationAction::new(Some(auth_user.user.id), user_id, ModerationActionKind::Ban)
+            .reason(Some(reason));
+    let from = [
+        AccountStatus::Active,
+        AccountStatus::Suspended,
+        AccountStatus::Banned,
+    ];
+    change_account_status(&state, user, subject_role, action, &from, AccountStatus::Banned).await
+}
+
+/// Lifts a suspension, or for admins a ban.
+pub async fn reinstate_user(
+    State(state): State<AppState>,
+    Extension(auth_user): Extension<AuthUser>,
+    Extension(role): Extension<Role>,
+    Path(user_id): Path<Uuid>,
+    Json(data): Json<ReasonRequest>,
+) -> impl IntoResponse {
+    let reason = match validate_reason(&data.reason) {
+        Ok(reason) => reason,
+        Err(e) => return validation_error(e),
+    };
+    let (user, subject_role) = match load_subject(&state, &auth_user, role, user_id).await {
+        Ok(subject) => subject,
+        Err(response) => return response,
+    };
+    match user.account_status {
+        AccountStatus::Active => {
+            return (
+                StatusCode::CONFLICT,
+                Json(json!({
+                    "error": "Account is already active"
+                })),
+            )
+                .into_response();
+        }
+        AccountStatus::Banned if role < Role::Admin => return only_admins_change_bans(),
+        _ => {}
+    }
+
+    let action =
+        NewModerationAction::new(Some(auth_user.user.id), user_id, ModerationActionKind::Reinstate)
+            .reason(Some(reason));
+    let from: &[AccountStatus] = if role < Role::Admin {
+        &[AccountStatus::Suspended]
+    } else {
+        &[AccountStatus::Suspended, AccountStatus::Banned]
+    };
+    change_account_status(&state, user, subject_role, action, from, AccountStatus::Active).await
+}
+
+/// Undoes the deletion of an account.
+pub async fn restore_user(
+    State(state): State<AppState>,
+    Extension(auth_user): Extension<AuthUser>,
+    Extension(role): Extension<Role>,
+    Path(user_id): Path<Uuid>,
+    Json(data): Json<ReasonRequest>,
+) -> impl IntoResponse {
+    let reason = match validate_reason(&data.reason) {
+        Ok(reason) => reason,
+        Err(e) => return validation_error(e),
+    };
+    let (mut user, subject_role) = match load_subject(&state, &auth_user, role, user_id).await {
+        Ok(subject) => subject,
+        Err(response) => return response,
+    };
+
+    let action =
+        NewModerationAction::new(Some(auth_user.user.id), user_id, ModerationActionKind::Restore)
+            .reason(Some(reason));
+    match ModerationRepository::new(state.db)
+        .restore_account(&action)
+        .await
+    {
+        Ok(true) => {
+            user.deleted_at = None;
+            (StatusCode::OK, Json(AdminUserView::new(&user, subject_role))).into_response()
+        }
+        Ok(false) => (
+            StatusCode::CONFLICT,
+            Json(json!({
+                "error": "Account is not deleted"
+            })),
+        )
+            .into_response(),
+        Err(_) => database_error(),
+    }
+}
+
+pub async fn set_role(
+    State(state): State<AppState>,
+    Extension(auth_user): Extension<AuthUser>,
+    Extension(role): Extension<Role>,
+    Path(user_id): Path<Uuid>,
+    Json(data): Json<RoleRequest>,
+) -> impl IntoResponse {
+    let reason = match validate_reason(&data.reason) {
+        Ok(reason) => reason,
+        Err(e) => return validation_error(e),
+    };
+    let (user, previous_role) = match load_subject(&state, &auth_user, role, user_id).await {
+        Ok(subject) => subject,
+        Err(response) => return response,
+    };
+
+    let action = NewModerationAction::new(
+        Some(auth_user.user.id),
+        user_id,
+        ModerationActionKind::ChangeRole,
+    )
+    .reason(Some(reason))
+    .details(json!({
+        "role": data.role,
+        "previous_role": previous_role
+    }));
+    match ModerationRepository::new(state.db)
+        .change_role(&action, data.role)
+        .await
+    {
+        Ok(()) => (StatusCode::OK, Json(AdminUserView::new(&user, data.role))).into_response(),
+        Err(_) => database_error(),
+    }
+}
+
+/// Queue items, newest first, with how many reports each one reviews.
+pub async fn get_queue(
+    State(state): State<AppState>,
+    Query(filter): Query<QueueFilter>,
+    Query(query): Query<PageQuery>,
+) -> impl IntoResponse {
+    let (limit, after) = match time_page_params(&state.cursor_signer, QUEUE_CURSOR_SCOPE, &query) {
+        Ok(params) => params,
+        Err(response) => return response,
+    };
+    let status = match filter.status.as_deref().map(str::parse::<ModerationStatus>) {
+        None => None,
+        Some(Ok(status)) => Some(status),
+        Some(Err(_)) => return invalid_filter("status"),
+    };
+    let kind = match filter.kind.as_deref().map(str::parse::<ModerationItemKind>) {
+        None => None,
+        Some(Ok(kind)) => Some(kind),
+        Some(Err(_)) => return invalid_filter("kind"),
+    };
+
+    match ModerationRepository::new(state.db)
+        .find_items(status, kind, after, limit + 1)
+        .await
+    {
+        Ok(items) => {
+            let page = Page::from_overfetched(items, limit as usize, |item| {
+                time_cursor(&state.cursor_signer, QUEUE_CURSOR_SCOPE, item.created_at, item.id)
+            });
+
+            (StatusCode::OK, Json(page)).into_response()
+        }
+        Err(_) => database_error(),
+    }
+}
+
+/// A queue item with its reports.
+pub async fn get_queue_item(
+    State(state): State<AppState>,
+    Path(item_id): Path<Uuid>,
+) -> impl IntoResponse {
+    let moderation_repo = ModerationRepository::new(state.db);
+    let item = match moderation_repo.find_item(item_id).await {
+        Ok(Some(item)) => item,
+        Ok(None) => return item_not_found(),
+        Err(_) => return database_error(),
+    };
+
+    match moderation_repo.find_reports(item_id).await {
+        Ok(reports) => (
+            StatusCode::OK,
+            Json(json!({
+                "item": item,
+                "reports": reports
+            })),
+        )
+            .into_response(),
+        Err(_) => database_error(),
+    }
+}
+
+/// Moves a queue item on. Dismissing reports that suspended their subject automatically
+/// reinstates the subject.
+pub async fn review_queue_item(
+    State(state): State<AppState>,
+    Extension(auth_user): Extension<AuthUser>,
+    Path(item_id): Path<Uuid>,
+    Json(data): Json<ReviewRequest>,
+) -> impl IntoResponse {
+    let reason = match data.reason.as_deref().map(validate_reason).transpose() {
+        Ok(reason) => reason,
+        Err(e) => return validation_error(e),
+    };
+
+    let moderation_repo = ModerationRepository::new(state.db.clone());
+    let item = match moderation_repo.find_item(item_id).await {
+        Ok(Some(item)) => item,
+        Ok(None) => return item_not_found(),
+        Err(_) => return database_error(),
+    };
+    if item.subject_user_id == auth_user.user.id {
+        return cannot_moderate_yourself();
+    }
+
+    let action = NewModerationAction::new(
+        Some(auth_user.user.id),
+        item.subject_user_id,
+        ModerationActionKind::Review,
+    )
+    .reason(reason)
+    .moderation_item(item_id)
+    .details(json!({
+        "status": data.status,
+        "previous_status": item.status
+    }));
+    match moderation_repo.review_item(&action, data.status).await {
+        Ok(true) => {}
+        Ok(false) => return item_not_found(),
+        Err(_) => return database_error(),
+    }
+
+    if data.status == ModerationStatus::Dismissed && item.details.get("auto_suspended_at").is_some()
+    {
+        lift_automatic_suspension(&state, auth_user.user.id, item.subject_user_id, item_id).await;
+    }
+
+    match moderation_repo.find_item(item_id).await {
+        Ok(Some(item)) => (StatusCode::OK, Json(item)).into_response(),
+        Ok(None) => item_not_found(),
+        Err(_) => database_error(),
+    }
+}
+
+/// Every moderation action, newest first, optionally about one user or by one moderator.
+pub async fn get_audit_log(
+    State(state): State<AppState>,
+    Query(filter): Query<AuditFilter>,
+    Query(query): Query<PageQuery>,
+) -> impl IntoResponse {
+    let (limit, after) = match time_page_params(&state.cursor_signer, AUDIT_CURSOR_SCOPE, &query) {
+        Ok(params) => params,
+        Err(response) => return response,
+    };
+
+    match ModerationRepository::new(state.db)
+        .find_actions(filter.subject_id, filter.actor_id, after, limit + 1)
+        .await
+    {
+        Ok(actions) => {
+            let page = Page::from_overfetched(actions, limit as usize, |action| {
+                time_cursor(&state.cursor_signer, AUDIT_CURSOR_SCOPE, action.created_at, action.id)
+            });
+
+            (StatusCode::OK, Json(page)).into_response()
+        }
+        Err(_) => database_error(),
+    }
+}
+
+/// Loads the user an action is about. Moderators cannot act on themselves or on anyone
+/// whose role is not below their own.
+async fn load_subject(
+    state: &AppState,
+    auth_user: &AuthUser,
+    role: Role,
+    user_id: Uuid,
+) -> Result<(User, Role), Response> {
+    if user_id == auth_user.user.id {
+        return Err(cannot_moderate_yourself());
+    }
+
+    match UserRepository::new(state.db.clone())
+        .find_account(user_id)
+        .await
+    {
+        Ok(Some((_, subject_role))) if subject_role >= role => Err((
+            StatusCode::FORBIDDEN,
+            Json(json!({
+                "error": format!("You cannot moderate a user with the {} role", subject_role)
+            })),
+        )
+            .into_response()),
+        Ok(Some(subject)) => Ok(subject),
+        Ok(None) => Err(user_not_found()),
+        Err(_) => Err(database_error()),
+    }
+}
+
+/// Moves the account from one of the `from` statuses the moderator checked to `status`,
+/// closing the user's realtime connections unless it is reinstated.
+async fn change_account_status(
+    state: &AppState,
+    mut user: User,
+    subject_role: Role,
+    action: NewModerationAction,
+    from: &[AccountStatus],
+    status: AccountStatus,
+) -> Response {
+    match ModerationRepository::new(state.db.clone())
+        .change_account_status(&action, from, status.clone())
+        .await
+    {
+        Ok(StatusChange::Changed) => {
+            if status != AccountStatus::Active {
+                state.hub.disconnect_user(user.id);
+            }
+            user.account_status = status;
+            (
+                StatusCode::OK,
+                Json(json!({
+                    "user": AdminUserView::new(&user, subject_role),
+                    "reason": action.reason,
+                    "expires_at": action.expires_at
+                })),
+            )
+                .into_response()
+        }
+        // Deleted accounts keep their status until restored
+        Ok(StatusChange::NotFound) => user_not_found(),
+        Ok(StatusChange::Conflict) => (
+            StatusCode::CONFLICT,
+            Json(json!({
+                "error": "The account status changed meanwhile, reload it and try again"
+            })),
+        )
+            .into_response(),
+        Err(_) => database_error(),
+    }
+}
+
+/// Reinstates the subject of dismissed reports, unless the suspension they caused was replaced
+/// since, for example by a moderator suspending or banning the user themselves.
+async fn lift_automatic_suspension(
+    state: &AppState,
+    moderator_id: Uuid,
+    user_id: Uuid,
+    item_id: Uuid,
+) {
+    let action =
+        NewModerationAction::new(Some(moderator_id), user_id, ModerationActionKind::Reinstate)
+            .reason(Some("Reports dismissed".to_string()))
+            .moderation_item(item_id);
+    if let Err(e) = ModerationRepository::new(state.db.clone())
+        .lift_automatic_suspension(&action, item_id)
+        .await
+    {
+        tracing::error!("Failed to reinstate dismissed user {}: {:#}", user_id, e);
+    }
+}
+
+fn validation_error(e: ValidationError) -> Response {
+    (
+        StatusCode::BAD_REQUEST,
+        Json(json!({
+            "error": e.message,
+            "field": e.field
+        })),
+    )
+        .into_response()
+}
+
+fn invalid_filter(field: &str) -> Response {
+    (
+        StatusCode::BAD_REQUEST,
+        Json(json!({
+            "error": format!("Invalid {}", field),
+            "field": field
+        })),
+    )
+        .into_response()
+}
+
+fn cannot_moderate_yourself() -> Response {
+    (
+        StatusCode::FORBIDDEN,
+        Json(json!({
+            "error": "You cannot moderate yourself"
+        })),
+    )
+        .into_response()
+}
+
+fn only_admins_change_bans() -> Response {
+    (
+        StatusCode::FORBIDDEN,
+        Json(json!({
+            "error": "Only admins can change banned accounts"
+        })),
+    )
+        .into_response()
+}
+
+fn user_not_found() -> Response {
+    (
+        StatusCode::NOT_FOUND,
+        Json(json!({
+            "error": "User not found"
+        })),
+    )
+        .into_response()
+}
+
+fn item_not_found() -> Response {
+    (
+        StatusCode::NOT_FOUND,
+        Json(json!({
+            "error": "Moderation item not found"
+        })),
+    )
+        .into_response()
+}
@@ -13,8 +13,11 @@ use sqlx::PgPool;
 use std::time;
 use uuid::Uuid;
 
+use crate::api::database_error;
 use crate::database::user_repository::UserRepository;
-use crate::models::User;
+use crate::database::ModerationRepository;
+use crate::enums::AccountStatus;
+use crate::models::{AccountStatusChange, SelfUserView};
 
 #[derive(Debug, Deserialize)]
 pub struct RegisterRequest {
@@ -33,7 +36,7 @@ pub struct LoginRequest {
 
 #[derive(Debug, Serialize)]
 pub struct AuthResponse {
-    pub user: User,
+    pub user: SelfUserView,
     pub message: String,
 }
 
@@ -142,12 +145,7 @@ pub async fn register(
         jar,
         Json(json!({
             "message": "User registered successfully. Please check your email for verification.",
-            "user": {
-                "id": user.id,
-                "email": user.email,
-                "username": user.username,
-                "email_verified": user.is_email_verified()
-            }
+            "user": SelfUserView::from(&user)
         })),
     )
         .into_response()
@@ -158,10 +156,10 @@ pub async fn login(
     jar: CookieJar,
     Form(data): Form<LoginRequest>,
 ) -> impl IntoResponse {
-    let user_repo = UserRepository::new(state.db);
+    let user_repo = UserRepository::new(state.db.clone());
 
     // Find user by email
-    let user = match user_repo.find_by_email(&data.email).await {
+    let mut user = match user_repo.find_by_email(&data.email).await {
         Ok(Some(user)) => user,
         Ok(None) => {
             return (
@@ -173,13 +171,7 @@ pub async fn login(
                 .into_response();
         }
         Err(_) => {
-            return (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(json!({
-                    "error": "Database error"
-                })),
-            )
-                .into_response();
+            return database_error();
         }
     };
 
@@ -210,6 +202,44 @@ pub async fn login(
             .into_response();
     }
 
+    // Suspensions that ended are lifted here too, so nobody waits for the scheduler
+    if user.account_status == AccountStatus::Suspended && user.deleted_at.is_none() {
+        let change = match user_repo.find_latest_status_change(user.id).await {
+            Ok(change) => change,
+            Err(_) => {
+                return database_error();
+            }
+        };
+        match change {
+            Some(change) if change.has_expired(Utc::now()) => {
+                match ModerationRepository::new(state.db)
+                    .lift_expired_suspension(user.id)
+                    .await
+                {
+                    Ok(true) => user.account_status = AccountStatus::Active,
+                    // Someone changed the status meanwhile, for example extending the suspension
+                    Ok(false) => match user_repo.find_by_id(user.id).await {
+                        Ok(Some(current)) if current.account_status == AccountStatus::Suspended => {
+                            return match user_repo.find_latest_status_change(user.id).await {
+                                Ok(change) => account_suspended(change.as_ref()),
+                                Err(_) => database_error(),
+                            };
+                        }
+                        Ok(Some(current)) => user = current,
+                        Ok(None) => {}
+                        Err(_) => {
+                            return database_error();
+                        }
+                    },
+                    Err(_) => {
+                        return database_error();
+                    }
+                }
+            }
+            change => return account_suspended(change.as_ref()),
+        }
+    }
+
     // Check if user is active
     if !user.is_active() {
         return (
@@ -250,12 +280,27 @@ pub async fn login(
         jar,
         Json(json!({
             "message": "Login successful",
-            "user": {
-                "id": user.id,
-                "email": user.email,
-                "username": user.username,
-                "email_verified": user.is_email_verified()
-            }
+            "user": SelfUserView::from(&user)
+        })),
+    )
+        .into_response()
+}
+
+/// Tells a suspended user why and until when, as far as the status history knows.
+fn account_suspended(change: Option<&AccountStatusChange>) -> axum::response::Response {
+    let reason = change.and_then(|change| change.reason.as_deref());
+    let suspended_until = change.and_then(|change| change.expires_at);
+    let error = match suspended_until {
+        Some(until) => format!("Account is suspended until {}", until.format("%Y-%m-%d %H:%M UTC")),
+        None => "Account is suspended".to_string(),
+    };
+
+    (
+        StatusCode::FORBIDDEN,
+        Json(json!({
+            "error": error,
+            "reason": reason,
+            "suspended_until": suspended_until
         })),
     )
         .into_response()
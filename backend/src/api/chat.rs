@@ -1,23 +1,151 @@
-use axum::response::Json;
-use serde_json::{json, Value};
-
-pub async fn get_conversations() -> Json<Value> {
-    Json(json!({
-        "message": "Get conversations endpoint - to be implemented",
-        "status": "not_implemented"
-    }))
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::{IntoResponse, Json, Response},
+    Extension,
+};
+use serde::Deserialize;
+use serde_json::json;
+use uuid::Uuid;
+
+use crate::api::database_error;
+use crate::api::pagination::{time_cursor, time_page_params, PageQuery};
+use crate::database::ChatRepository;
+use crate::middleware::auth::AuthUser;
+use crate::models::Page;
+use crate::services::chat::{self, ChatError};
+use crate::AppState;
+
+const CONVERSATIONS_CURSOR_SCOPE: &str = "conversations";
+const MESSAGES_CURSOR_SCOPE: &str = "messages";
+
+#[derive(Debug, Deserialize)]
+pub struct SendMessageRequest {
+    pub body: String,
 }
 
-pub async fn get_messages() -> Json<Value> {
-    Json(json!({
-        "message": "Get messages endpoint - to be implemented",
-        "status": "not_implemented"
-    }))
+/// Conversations of the current user, most recently active first.
+pub async fn get_conversations(
+    State(state): State<AppState>,
+    Extension(auth_user): Extension<AuthUser>,
+    Query(query): Query<PageQuery>,
+) -> impl IntoResponse {
+    let (limit, after) =
+        match time_page_params(&state.cursor_signer, CONVERSATIONS_CURSOR_SCOPE, &query) {
+            Ok(params) => params,
+            Err(response) => return response,
+        };
+
+    match ChatRepository::new(state.db)
+        .find_conversations(auth_user.user.id, after, limit + 1)
+        .await
+    {
+        Ok(conversations) => {
+            let page = Page::from_overfetched(conversations, limit as usize, |conversation| {
+                time_cursor(
+                    &state.cursor_signer,
+                    CONVERSATIONS_CURSOR_SCOPE,
+                    conversation.last_message_at,
+                    conversation.id,
+                )
+            });
+
+            (StatusCode::OK, Json(page)).into_response()
+        }
+        Err(_) => database_error(),
+    }
 }
 
-pub async fn send_message() -> Json<Value> {
-    Json(json!({
-        "message": "Send message endpoint - to be implemented",
-        "status": "not_implemented"
-    }))
+/// Messages with another user, newest first. Reading the first page marks them as read.
+pub async fn get_messages(
+    State(state): State<AppState>,
+    Extension(auth_user): Extension<AuthUser>,
+    Path(other_id): Path<Uuid>,
+    Query(query): Query<PageQuery>,
+) -> impl IntoResponse {
+    // A cursor from one conversation would skip arbitrarily into another
+    let scope = format!("{}:{}", MESSAGES_CURSOR_SCOPE, other_id);
+    let (limit, after) = match time_page_params(&state.cursor_signer, &scope, &query) {
+        Ok(params) => params,
+        Err(response) => return response,
+    };
+    // Past conversations stay readable after an unlike, but not after a block
+    if let Err(e) = chat::check_other_user(&state.db, auth_user.user.id, other_id, false).await {
+        return chat_error(e);
+    }
+
+    let chat_repo = ChatRepository::new(state.db.clone());
+    let messages = match chat_repo
+        .find_messages(auth_user.user.id, other_id, after, limit + 1)
+        .await
+    {
+        Ok(messages) => messages,
+        Err(_) => return database_error(),
+    };
+
+    if after.is_none() {
+        if let Some(newest) = messages.first() {
+            if let Err(e) = chat_repo
+                .mark_read(auth_user.user.id, other_id, newest.created_at)
+                .await
+            {
+                tracing::error!("Failed to mark messages as read: {:#}", e);
+            }
+        }
+    }
+
+    let page = Page::from_overfetched(messages, limit as usize, |message| {
+        time_cursor(&state.cursor_signer, &scope, message.created_at, message.id)
+    });
+    (StatusCode::OK, Json(page)).into_response()
+}
+
+/// Sends a message to a user the current user is connected with.
+pub async fn send_message(
+    State(state): State<AppState>,
+    Extension(auth_user): Extension<AuthUser>,
+    Path(recipient_id): Path<Uuid>,
+    Json(data): Json<SendMessageRequest>,
+) -> impl IntoResponse {
+    match chat::send_message(
+        &state.db,
+        &state.hub,
+        auth_user.user.id,
+        recipient_id,
+        &data.body,
+        None,
+    )
+    .await
+    {
+        Ok(message) => (StatusCode::CREATED, Json(message)).into_response(),
+        Err(e) => chat_error(e),
+    }
+}
+
+fn chat_error(e: ChatError) -> Response {
+    let status = match &e {
+        ChatError::Invalid(invalid) => {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(json!({
+                    "error": e.to_string(),
+                    "field": invalid.field
+                })),
+            )
+                .into_response();
+        }
+        ChatError::ToYourself => StatusCode::BAD_REQUEST,
+        ChatError::NotActive => StatusCode::FORBIDDEN,
+        ChatError::UserNotFound => StatusCode::NOT_FOUND,
+        ChatError::NotConnected => StatusCode::FORBIDDEN,
+        ChatError::Database(_) => return database_error(),
+    };
+
+    (
+        status,
+        Json(json!({
+            "error": e.to_string()
+        })),
+    )
+        .into_response()
 }
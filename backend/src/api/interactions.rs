@@ -1,30 +1,416 @@
-use axum::response::Json;
-use serde_json::{json, Value};
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::{IntoResponse, Json, Response},
+    Extension,
+};
+use serde::Deserialize;
+use serde_json::json;
+use uuid::Uuid;
 
-pub async fn like_user() -> Json<Value> {
-    Json(json!({
-        "message": "Like user endpoint - to be implemented",
-        "status": "not_implemented"
-    }))
+use crate::api::database_error;
+use crate::database::like_repository::{LikeOutcome, UnlikeOutcome};
+use crate::database::{
+    BlockRepository, LikeRepository, PictureRepository, ReportRepository, UserRepository,
+};
+use crate::enums::{FameEventKind, NotificationKind, ReportReason};
+use crate::middleware::auth::AuthUser;
+use crate::models::report::validate_report_details;
+use crate::services::fame::FameService;
+use crate::services::{moderation, notifications};
+use crate::AppState;
+
+#[derive(Debug, Deserialize)]
+pub struct ReportRequest {
+    pub reason: ReportReason,
+    pub details: Option<String>,
+}
+
+pub async fn like_user(
+    State(state): State<AppState>,
+    Extension(auth_user): Extension<AuthUser>,
+    Path(liked_id): Path<Uuid>,
+) -> impl IntoResponse {
+    let liker_id = auth_user.user.id;
+    if let Err(response) = check_target(&state, liker_id, liked_id, "like", true).await {
+        return response;
+    }
+
+    match PictureRepository::new(state.db.clone())
+        .has_profile_picture(liker_id)
+        .await
+    {
+        Ok(true) => {}
+        Ok(false) => {
+            return (
+                StatusCode::FORBIDDEN,
+                Json(json!({
+                    "error": "You need a profile picture to like other users"
+                })),
+            )
+                .into_response();
+        }
+        Err(_) => return database_error(),
+    }
+
+    let outcome = match LikeRepository::new(state.db.clone())
+        .like(liker_id, liked_id)
+        .await
+    {
+        Ok(LikeOutcome::Blocked) => return user_not_found(),
+        Ok(outcome) => outcome,
+        Err(_) => return database_error(),
+    };
+
+    // The like is stored, so side effects failing must not fail the request
+    let fame = FameService::new(state.db.clone(), state.fame.clone());
+    match outcome {
+        LikeOutcome::Blocked | LikeOutcome::AlreadyLiked => {}
+        LikeOutcome::Liked => {
+            notifications::notify(
+                &state.db,
+                &state.hub,
+                liked_id,
+                liker_id,
+                NotificationKind::Liked,
+            )
+            .await;
+            log_fame_error(
+                fame.record(liked_id, Some(liker_id), FameEventKind::Like)
+                    .await,
+            );
+        }
+        LikeOutcome::Connected => {
+            notifications::notify(
+                &state.db,
+                &state.hub,
+                liked_id,
+                liker_id,
+                NotificationKind::Connected,
+            )
+            .await;
+            notifications::notify(
+                &state.db,
+                &state.hub,
+                liker_id,
+                liked_id,
+                NotificationKind::Connected,
+            )
+            .await;
+            log_fame_error(
+                fame.record(liked_id, Some(liker_id), FameEventKind::Like)
+                    .await,
+            );
+            log_fame_error(
+                fame.record(liked_id, Some(liker_id), FameEventKind::Connection)
+                    .await,
+            );
+            log_fame_error(
+                fame.record(liker_id, Some(liked_id), FameEventKind::Connection)
+                    .await,
+            );
+        }
+    }
+
+    connection_response(&state, liker_id, liked_id).await
+}
+
+pub async fn unlike_user(
+    State(state): State<AppState>,
+    Extension(auth_user): Extension<AuthUser>,
+    Path(liked_id): Path<Uuid>,
+) -> impl IntoResponse {
+    let liker_id = auth_user.user.id;
+    // A like given before the other user was suspended can still be taken back
+    if let Err(response) = check_target(&state, liker_id, liked_id, "unlike", false).await {
+        return response;
+    }
+
+    let outcome = match LikeRepository::new(state.db.clone())
+        .unlike(liker_id, liked_id)
+        .await
+    {
+        Ok(outcome) => outcome,
+        Err(_) => return database_error(),
+    };
+
+    let fame = FameService::new(state.db.clone(), state.fame.clone());
+    match outcome {
+        UnlikeOutcome::NotLiked => {}
+        UnlikeOutcome::Unliked => {
+            log_fame_error(fame.withdraw(liked_id, liker_id, FameEventKind::Like).await);
+        }
+        UnlikeOutcome::Disconnected => {
+            notifications::notify(
+                &state.db,
+                &state.hub,
+                liked_id,
+                liker_id,
+                NotificationKind::Disconnected,
+            )
+            .await;
+            log_fame_error(fame.withdraw(liked_id, liker_id, FameEventKind::Like).await);
+            log_fame_error(
+                fame.withdraw(liked_id, liker_id, FameEventKind::Connection)
+                    .await,
+            );
+            log_fame_error(
+                fame.withdraw(liker_id, liked_id, FameEventKind::Connection)
+                    .await,
+            );
+        }
+    }
+
+    connection_response(&state, liker_id, liked_id).await
+}
+
+/// Blocks a user in both directions: they disappear from each other's lists and any likes
+/// or connection between them are removed.
+pub async fn block_user(
+    State(state): State<AppState>,
+    Extension(auth_user): Extension<AuthUser>,
+    Path(blocked_id): Path<Uuid>,
+) -> impl IntoResponse {
+    let blocker_id = auth_user.user.id;
+    if blocked_id == blocker_id {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(json!({
+                "error": "You cannot block yourself"
+            })),
+        )
+            .into_response();
+    }
+
+    // Suspended users can be blocked too, and blocking someone who already blocked you works
+    match UserRepository::new(state.db.clone())
+        .find_by_id(blocked_id)
+        .await
+    {
+        Ok(Some(user)) if user.deleted_at.is_none() => {}
+        Ok(_) => {
+            return (
+                StatusCode::NOT_FOUND,
+                Json(json!({
+                    "error": "User not found"
+                })),
+            )
+                .into_response();
+        }
+        Err(_) => return database_error(),
+    }
+
+    let outcome = match BlockRepository::new(state.db.clone())
+        .block(blocker_id, blocked_id)
+        .await
+    {
+        Ok(outcome) => outcome,
+        Err(_) => return database_error(),
+    };
+
+    // Neither side sees the other come online anymore, on sockets already open either
+    state.hub.unwatch(blocker_id, blocked_id);
+    state.hub.unwatch(blocked_id, blocker_id);
+
+    let fame = FameService::new(state.db.clone(), state.fame.clone());
+    for (liker_id, liked_id) in outcome.removed_likes {
+        log_fame_error(fame.withdraw(liked_id, liker_id, FameEventKind::Like).await);
+    }
+    if outcome.disconnected {
+        log_fame_error(
+            fame.withdraw(blocked_id, blocker_id, FameEventKind::Connection)
+                .await,
+        );
+        log_fame_error(
+            fame.withdraw(blocker_id, blocked_id, FameEventKind::Connection)
+                .await,
+        );
+    }
+
+    (
+        StatusCode::OK,
+        Json(json!({
+            "user_id": blocked_id,
+            "blocked": true
+        })),
+    )
+        .into_response()
+}
+
+pub async fn unblock_user(
+    State(state): State<AppState>,
+    Extension(auth_user): Extension<AuthUser>,
+    Path(blocked_id): Path<Uuid>,
+) -> impl IntoResponse {
+    match BlockRepository::new(state.db)
+        .unblock(auth_user.user.id, blocked_id)
+        .await
+    {
+        Ok(_) => (
+            StatusCode::OK,
+            Json(json!({
+                "user_id": blocked_id,
+                "blocked": false
+            })),
+        )
+            .into_response(),
+        Err(_) => database_error(),
+    }
+}
+
+/// Reports a user to moderators, suspending them once enough reports await review.
+pub async fn report_user(
+    State(state): State<AppState>,
+    Extension(auth_user): Extension<AuthUser>,
+    Path(reported_id): Path<Uuid>,
+    Json(data): Json<ReportRequest>,
+) -> impl IntoResponse {
+    let reporter_id = auth_user.user.id;
+    if reported_id == reporter_id {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(json!({
+                "error": "You cannot report yourself"
+            })),
+        )
+            .into_response();
+    }
+
+    let details = match validate_report_details(data.reason, data.details.as_deref()) {
+        Ok(details) => details,
+        Err(e) => {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(json!({
+                    "error": e.message,
+                    "field": e.field
+                })),
+            )
+                .into_response();
+        }
+    };
+
+    // Users who blocked the reporter can still be reported
+    match UserRepository::new(state.db.clone())
+        .find_by_id(reported_id)
+        .await
+    {
+        Ok(Some(user)) if user.deleted_at.is_none() => {}
+        Ok(_) => {
+            return (
+                StatusCode::NOT_FOUND,
+                Json(json!({
+                    "error": "User not found"
+                })),
+            )
+                .into_response();
+        }
+        Err(_) => return database_error(),
+    };
+
+    let report = match ReportRepository::new(state.db.clone())
+        .create(reporter_id, reported_id, data.reason, details.as_deref())
+        .await
+    {
+        Ok(Some(report)) => report,
+        Ok(None) => {
+            return (
+                StatusCode::CONFLICT,
+                Json(json!({
+                    "error": "You already reported this user"
+                })),
+            )
+                .into_response();
+        }
+        Err(_) => return database_error(),
+    };
+
+    // The report is stored, so side effects failing must not fail the request
+    log_fame_error(
+        FameService::new(state.db.clone(), state.fame.clone())
+            .record(reported_id, Some(reporter_id), FameEventKind::Report)
+            .await,
+    );
+
+    moderation::suspend_if_reported_enough(&state.db, &state.hub, reported_id, &report).await;
+
+    (
+        StatusCode::CREATED,
+        Json(json!({
+            "id": report.id,
+            "user_id": reported_id,
+            "reason": data.reason
+        })),
+    )
+        .into_response()
+}
+
+/// Rejects acting on yourself, and on users who are gone or blocked in either direction.
+/// Suspended and banned users count as gone when `active_only` is set.
+async fn check_target(
+    state: &AppState,
+    user_id: Uuid,
+    target_id: Uuid,
+    action: &str,
+    active_only: bool,
+) -> Result<(), Response> {
+    if target_id == user_id {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(json!({
+                "error": format!("You cannot {} yourself", action)
+            })),
+        )
+            .into_response());
+    }
+
+    let target = UserRepository::new(state.db.clone())
+        .find_by_id(target_id)
+        .await;
+    let blocked = BlockRepository::new(state.db.clone())
+        .is_blocked_between(user_id, target_id)
+        .await;
+    match (target, blocked) {
+        (Ok(Some(user)), Ok(false))
+            if user.deleted_at.is_none() && (user.is_active() || !active_only) =>
+        {
+            Ok(())
+        }
+        (Ok(_), Ok(_)) => Err(user_not_found()),
+        _ => Err(database_error()),
+    }
 }
 
-pub async fn unlike_user() -> Json<Value> {
-    Json(json!({
-        "message": "Unlike user endpoint - to be implemented",
-        "status": "not_implemented"
-    }))
+fn user_not_found() -> Response {
+    (
+        StatusCode::NOT_FOUND,
+        Json(json!({
+            "error": "User not found"
+        })),
+    )
+        .into_response()
 }
 
-pub async fn block_user() -> Json<Value> {
-    Json(json!({
-        "message": "Block user endpoint - to be implemented",
-        "status": "not_implemented"
-    }))
+/// Where the two users stand after a like or unlike.
+async fn connection_response(state: &AppState, user_id: Uuid, other_id: Uuid) -> Response {
+    match LikeRepository::new(state.db.clone())
+        .find_status(user_id, other_id)
+        .await
+    {
+        Ok(connection) => (
+            StatusCode::OK,
+            Json(json!({
+                "user_id": other_id,
+                "connection": connection
+            })),
+        )
+            .into_response(),
+        Err(_) => database_error(),
+    }
 }
 
-pub async fn report_user() -> Json<Value> {
-    Json(json!({
-        "message": "Report user endpoint - to be implemented",
-        "status": "not_implemented"
-    }))
+fn log_fame_error(result: anyhow::Result<()>) {
+    if let Err(e) = result {
+        tracing::error!("Failed to update fame events: {:#}", e);
+    }
 }
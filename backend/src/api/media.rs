@@ -0,0 +1,65 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::{header, StatusCode},
+    response::{IntoResponse, Json},
+};
+use chrono::Utc;
+use serde::Deserialize;
+use serde_json::json;
+
+use crate::services::storage::{content_type_for, is_valid_key};
+
+#[derive(Debug, Deserialize)]
+pub struct SignedMediaQuery {
+    pub expires: i64,
+    pub signature: String,
+}
+
+pub async fn serve_media(
+    State(state): State<crate::AppState>,
+    Path(key): Path<String>,
+    Query(query): Query<SignedMediaQuery>,
+) -> impl IntoResponse {
+    // Raw uploads still carry their EXIF metadata and are never served, signed or not
+    if !is_valid_key(&key)
+        || !key.starts_with("pictures/")
+        || !state
+            .media_signer
+            .verify(&key, query.expires, &query.signature)
+    {
+        return (
+            StatusCode::FORBIDDEN,
+            Json(json!({
+                "error": "Invalid or expired media link"
+            })),
+        )
+            .into_response();
+    }
+
+    let data = match state.storage.get(&key).await {
+        Ok(data) => data,
+        Err(_) => {
+            return (
+                StatusCode::NOT_FOUND,
+                Json(json!({
+                    "error": "Media not found"
+                })),
+            )
+                .into_response();
+        }
+    };
+
+    // Caches must not keep the file around longer than the link itself is valid
+    let max_age = (query.expires - Utc::now().timestamp()).max(0);
+
+    (
+        StatusCode::OK,
+        [
+            (header::CONTENT_TYPE, content_type_for(&key).to_string()),
+            (header::CACHE_CONTROL, format!("private, max-age={}", max_age)),
+            (header::X_CONTENT_TYPE_OPTIONS, "nosniff".to_string()),
+        ],
+        data,
+    )
+        .into_response()
+}
@@ -1,5 +1,25 @@
+use axum::{
+    http::StatusCode,
+    response::{IntoResponse, Json, Response},
+};
+use serde_json::json;
+
+pub mod admin;
 pub mod auth;
 pub mod chat;
 pub mod interactions;
+pub mod media;
 pub mod notifications;
+pub mod pagination;
 pub mod users;
+
+/// The response to a failed query, which does not tell clients what went wrong.
+pub(crate) fn database_error() -> Response {
+    (
+        StatusCode::INTERNAL_SERVER_ERROR,
+        Json(json!({
+            "error": "Database error"
+        })),
+    )
+        .into_response()
+}
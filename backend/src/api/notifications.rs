@@ -1,23 +1,299 @@
-use axum::response::Json;
-use serde_json::{json, Value};
+use axum::{
+    extract::{Path, Query, State},
+    http::{HeaderMap, StatusCode},
+    response::{
+        sse::{Event, KeepAlive, Sse},
+        IntoResponse, Json, Response,
+    },
+    Extension,
+};
+use futures_util::stream;
+use serde_json::json;
+use sqlx::PgPool;
+use std::cmp::Reverse;
+use std::collections::VecDeque;
+use std::convert::Infallible;
+use std::time::Duration;
+use uuid::Uuid;
 
-pub async fn get_notifications() -> Json<Value> {
-    Json(json!({
-        "message": "Get notifications endpoint - to be implemented",
-        "status": "not_implemented"
-    }))
+use crate::api::database_error;
+use crate::api::pagination::{time_cursor, time_page_params, PageQuery, TimePosition};
+use crate::database::{ChatRepository, NotificationRepository};
+use crate::middleware::auth::AuthUser;
+use crate::models::Page;
+use crate::services::hub::{ConnectionId, Outbox, Push};
+use crate::websocket::protocol::ServerMessage;
+use crate::AppState;
+
+const NOTIFICATIONS_CURSOR_SCOPE: &str = "notifications";
+
+/// How often a quiet event stream gets a comment, so proxies do not close it.
+const KEEP_ALIVE_INTERVAL: Duration = Duration::from_secs(15);
+
+/// Most missed events replayed when a stream resumes; the client is told when older ones
+/// were left out, and finds them through the REST endpoints.
+const REPLAY_LIMIT: i64 = 100;
+
+/// Newest notifications first, with the number still unread.
+pub async fn get_notifications(
+    State(state): State<crate::AppState>,
+    Extension(auth_user): Extension<AuthUser>,
+    Query(query): Query<PageQuery>,
+) -> impl IntoResponse {
+    let (limit, after) =
+        match time_page_params(&state.cursor_signer, NOTIFICATIONS_CURSOR_SCOPE, &query) {
+            Ok(params) => params,
+            Err(response) => return response,
+        };
+
+    let notification_repo = NotificationRepository::new(state.db);
+    let notifications = notification_repo
+        .find_for_user(auth_user.user.id, after, limit + 1)
+        .await;
+    let unread = notification_repo.count_unread(auth_user.user.id).await;
+
+    match (notifications, unread) {
+        (Ok(notifications), Ok(unread)) => {
+            let page = Page::from_overfetched(notifications, limit as usize, |notification| {
+                time_cursor(
+                    &state.cursor_signer,
+                    NOTIFICATIONS_CURSOR_SCOPE,
+                    notification.created_at,
+                    notification.id,
+                )
+            });
+
+            (
+                StatusCode::OK,
+                Json(json!({
+                    "items": page.items,
+                    "next_cursor": page.next_cursor,
+                    "unread": unread
+                })),
+            )
+                .into_response()
+        }
+        _ => database_error(),
+    }
+}
+
+pub async fn mark_as_read(
+    State(state): State<crate::AppState>,
+    Extension(auth_user): Extension<AuthUser>,
+) -> impl IntoResponse {
+    match NotificationRepository::new(state.db)
+        .mark_all_read(auth_user.user.id)
+        .await
+    {
+        Ok(marked) => (
+            StatusCode::OK,
+            Json(json!({
+                "marked": marked
+            })),
+        )
+            .into_response(),
+        Err(_) => database_error(),
+    }
+}
+
+pub async fn mark_single_as_read(
+    State(state): State<crate::AppState>,
+    Extension(auth_user): Extension<AuthUser>,
+    Path(id): Path<Uuid>,
+) -> impl IntoResponse {
+    match NotificationRepository::new(state.db)
+        .mark_read(auth_user.user.id, id)
+        .await
+    {
+        Ok(true) => (
+            StatusCode::OK,
+            Json(json!({
+                "id": id,
+                "read": true
+            })),
+        )
+            .into_response(),
+        Ok(false) => (
+            StatusCode::NOT_FOUND,
+            Json(json!({
+                "error": "Notification not found"
+            })),
+        )
+            .into_response(),
+        Err(_) => database_error(),
+    }
 }
 
-pub async fn mark_as_read() -> Json<Value> {
-    Json(json!({
-        "message": "Mark as read endpoint - to be implemented",
-        "status": "not_implemented"
-    }))
+/// Server-sent events with the notifications and chat messages pushed over the WebSocket,
+/// for clients that cannot open one. Notification and chat message events carry the id of
+/// what they are about, so a reconnecting client sending `Last-Event-ID` first gets the
+/// events it missed.
+pub async fn stream_events(
+    State(state): State<AppState>,
+    Extension(auth_user): Extension<AuthUser>,
+    headers: HeaderMap,
+) -> Response {
+    let user_id = auth_user.user.id;
+    // Registering before the replay means nothing created meanwhile is missed
+    let (connection, outbox) = StreamConnection::open(&state, user_id);
+
+    let (missed, truncated) = match headers.get("last-event-id") {
+        Some(value) => {
+            let since_id = value
+                .to_str()
+                .ok()
+                .and_then(|value| Uuid::parse_str(value.trim()).ok());
+            match find_missed(&state.db, user_id, since_id).await {
+                Ok(missed) => missed,
+                Err(_) => return database_error(),
+            }
+        }
+        None => (Vec::new(), false),
+    };
+
+    // Events pushed while the replay was loaded may be in it already
+    let replayed_until = missed.last().and_then(event_position);
+    let mut replay: VecDeque<Event> = missed.iter().filter_map(sse_event).collect();
+    if truncated {
+        replay.push_front(Event::default().event("replay_truncated").data("{}"));
+    }
+
+    let events = stream::unfold(
+        (replay, outbox, connection),
+        move |(mut replay, mut outbox, connection)| async move {
+            if let Some(event) = replay.pop_front() {
+                return Some((Ok::<_, Infallible>(event), (replay, outbox, connection)));
+            }
+            loop {
+                // The hub closes the outbox of streams too slow to keep up, and of suspended users
+                let push = outbox.recv().await?;
+                // Streams carry no presence, so there is nothing to unwatch
+                let Push::Message(message) = &*push else {
+                    continue;
+                };
+                let replayed = event_position(message)
+                    .zip(replayed_until)
+                    .is_some_and(|(position, until)| position <= until);
+                if replayed {
+                    continue;
+                }
+                if let Some(event) = sse_event(message) {
+                    return Some((Ok(event), (replay, outbox, connection)));
+                }
+            }
+        },
+    );
+
+    (
+        // Keeps nginx-style proxies from buffering the stream
+        [("x-accel-buffering", "no")],
+        Sse::new(events).keep_alive(
+            KeepAlive::new()
+                .interval(KEEP_ALIVE_INTERVAL)
+                .text("keep-alive"),
+        ),
+    )
+        .into_response()
+}
+
+/// The notifications and chat messages created after the event `since_id`, oldest first, and
+/// whether some were left out: only the newest `REPLAY_LIMIT` are replayed, and nothing is
+/// known to be missed after an id that is not one of the user's events.
+async fn find_missed(
+    db: &PgPool,
+    user_id: Uuid,
+    since_id: Option<Uuid>,
+) -> anyhow::Result<(Vec<ServerMessage>, bool)> {
+    let notification_repo = NotificationRepository::new(db.clone());
+    let chat_repo = ChatRepository::new(db.clone());
+
+    let since = match since_id {
+        Some(since_id) => match notification_repo.find_position(user_id, since_id).await? {
+            Some(position) => Some(position),
+            None => chat_repo.find_message_position(user_id, since_id).await?,
+        },
+        None => None,
+    };
+    let Some(since) = since else {
+        return Ok((Vec::new(), true));
+    };
+
+    // Each kind is fetched one past the limit, so the newest of both show whether more were missed
+    let notifications = notification_repo
+        .find_latest_after(user_id, since, REPLAY_LIMIT + 1)
+        .await?;
+    let messages = chat_repo
+        .find_latest_after(user_id, since, REPLAY_LIMIT + 1)
+        .await?;
+    let missed = notifications
+        .into_iter()
+        .map(|notification| ServerMessage::Notification { notification })
+        .chain(
+            messages
+                .into_iter()
+                .map(|message| ServerMessage::ChatMessage { message }),
+        )
+        .collect();
+
+    Ok(newest_in_order(missed, REPLAY_LIMIT as usize))
+}
+
+/// The newest `limit` events, oldest first, and whether there were more.
+fn newest_in_order(mut events: Vec<ServerMessage>, limit: usize) -> (Vec<ServerMessage>, bool) {
+    events.sort_by_key(|event| Reverse(event_position(event)));
+    let truncated = events.len() > limit;
+    events.truncate(limit);
+    events.reverse();
+    (events, truncated)
+}
+
+/// Where a replayable event falls in the order notifications and messages are created in.
+fn event_position(message: &ServerMessage) -> Option<TimePosition> {
+    match message {
+        ServerMessage::Notification { notification } => {
+            Some((notification.created_at, notification.id))
+        }
+        ServerMessage::ChatMessage { message } => Some((message.created_at, message.id)),
+        _ => None,
+    }
+}
+
+/// A message as the event named after its type, with the other fields as JSON data.
+/// Replayable events carry their id.
+fn sse_event(message: &ServerMessage) -> Option<Event> {
+    let (name, data) = message.event_parts()?;
+    let event = Event::default().event(name).data(data.to_string());
+    match event_position(message) {
+        Some((_, id)) => Some(event.id(id.to_string())),
+        None => Some(event),
+    }
+}
+
+/// The hub registration of an event stream, which counts as an open socket for presence
+/// until the client goes away and the stream is dropped.
+struct StreamConnection {
+    state: AppState,
+    user_id: Uuid,
+    id: ConnectionId,
+}
+
+impl StreamConnection {
+    fn open(state: &AppState, user_id: Uuid) -> (Self, Outbox) {
+        let (id, outbox) = state.hub.register(user_id);
+        state.presence.connect(user_id);
+        let connection = Self {
+            state: state.clone(),
+            user_id,
+            id,
+        };
+
+        (connection, outbox)
+    }
 }
 
-pub async fn mark_single_as_read() -> Json<Value> {
-    Json(json!({
-        "message": "Mark single as read endpoint - to be implemented",
-        "status": "not_implemented"
-    }))
+impl Drop for StreamConnection {
+    fn drop(&mut self) {
+        self.state.hub.unregister(self.user_id, self.id);
+        self.state.presence.disconnect(self.user_id);
+    }
 }
@@ -0,0 +1,89 @@
+use axum::{
+    http::StatusCode,
+    response::{IntoResponse, Json, Response},
+};
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use serde_json::json;
+use uuid::Uuid;
+
+use crate::services::cursor::{Cursor, CursorSigner, SortValue};
+
+const DEFAULT_PAGE_SIZE: i64 = 20;
+const MAX_PAGE_SIZE: i64 = 100;
+
+/// Timestamp and id of the last item of a page ordered newest first.
+pub type TimePosition = (DateTime<Utc>, Uuid);
+
+/// Query string of lists without filters of their own.
+#[derive(Debug, Deserialize)]
+pub struct PageQuery {
+    pub limit: Option<i64>,
+    pub cursor: Option<String>,
+}
+
+/// Validates the page size and decodes the cursor of a paginated list request.
+#[allow(clippy::result_large_err)]
+pub fn page_params(
+    signer: &CursorSigner,
+    scope: &str,
+    limit: Option<i64>,
+    cursor: Option<&str>,
+) -> Result<(i64, Option<Cursor>), Response> {
+    let limit = limit.unwrap_or(DEFAULT_PAGE_SIZE);
+    if !(1..=MAX_PAGE_SIZE).contains(&limit) {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(json!({
+                "error": format!("Limit must be between 1 and {}", MAX_PAGE_SIZE),
+                "field": "limit"
+            })),
+        )
+            .into_response());
+    }
+
+    match cursor {
+        None => Ok((limit, None)),
+        Some(token) => match signer.decode(token, scope) {
+            Some(cursor) => Ok((limit, Some(cursor))),
+            None => Err(invalid_cursor()),
+        },
+    }
+}
+
+/// Like `page_params`, for lists ordered newest first by a timestamp and then by id.
+#[allow(clippy::result_large_err)]
+pub fn time_page_params(
+    signer: &CursorSigner,
+    scope: &str,
+    query: &PageQuery,
+) -> Result<(i64, Option<TimePosition>), Response> {
+    let (limit, after) = page_params(signer, scope, query.limit, query.cursor.as_deref())?;
+    match after {
+        None => Ok((limit, None)),
+        Some(cursor) => match cursor.values.as_slice() {
+            [SortValue::Time(time)] => Ok((limit, Some((*time, cursor.id)))),
+            _ => Err(invalid_cursor()),
+        },
+    }
+}
+
+/// Cursor continuing a list read with `time_page_params` after the item at `(time, id)`.
+pub fn time_cursor(signer: &CursorSigner, scope: &str, time: DateTime<Utc>, id: Uuid) -> String {
+    signer.encode(&Cursor {
+        scope: scope.to_string(),
+        values: vec![SortValue::Time(time)],
+        id,
+    })
+}
+
+pub fn invalid_cursor() -> Response {
+    (
+        StatusCode::BAD_REQUEST,
+        Json(json!({
+            "error": "Invalid cursor",
+            "field": "cursor"
+        })),
+    )
+        .into_response()
+}
@@ -1,5 +1,73 @@
-use axum::response::Json;
+use axum::{
+    extract::{ConnectInfo, Multipart, Path, Query, State},
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Json, Response},
+    Extension,
+};
+use serde::Deserialize;
 use serde_json::{json, Value};
+use std::net::SocketAddr;
+use uuid::Uuid;
+
+use crate::api::database_error;
+use crate::api::pagination::{
+    invalid_cursor, page_params, time_cursor, time_page_params, PageQuery,
+};
+use crate::database::{
+    BlockRepository, FameRepository, LikeRepository, LocationRepository, PictureRepository,
+    PresenceRepository, ProfileRepository, UserRepository, VisitRepository,
+};
+use crate::enums::{FameEventKind, LocationSource, NotificationKind, PictureStatus, SearchSort};
+use crate::middleware::auth::AuthUser;
+use crate::models::location::validate_accuracy;
+use crate::models::{
+    Coordinates, Page, Picture, PictureVariant, Profile, PublicLocation, PublicProfileView,
+    SearchCriteria, SearchQuery, SearchResult, UserLocation,
+};
+use crate::services::cursor::{Cursor, SortValue};
+use crate::services::fame::FameService;
+use crate::services::image_editing::{self, Edit};
+use crate::services::matching::Orientation;
+use crate::services::storage::{self, SharedStorage};
+use crate::services::{browse, image_processing, jobs, notifications};
+
+const MAX_PICTURES_PER_USER: i64 = 5;
+
+const MAX_BROWSE_RADIUS_KM: f64 = 20_000.0;
+const MAX_BROWSE_CANDIDATES: i64 = 500;
+const BROWSE_CURSOR_SCOPE: &str = "browse";
+const FAME_HISTORY_CURSOR_SCOPE: &str = "fame_history";
+const VISITS_CURSOR_SCOPE: &str = "visits";
+const LIKES_CURSOR_SCOPE: &str = "likes";
+const BLOCKS_CURSOR_SCOPE: &str = "blocks";
+const VISIT_WINDOW_MINUTES: i64 = 60;
+
+#[derive(Debug, Deserialize)]
+pub struct EditPictureRequest {
+    pub edits: Vec<Edit>,
+}
+
+/// Coordinates reported by the browser's geolocation API.
+#[derive(Debug, Deserialize)]
+pub struct UpdateLocationRequest {
+    pub latitude: f64,
+    pub longitude: f64,
+    pub accuracy: Option<f64>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ManualLocationRequest {
+    pub latitude: f64,
+    pub longitude: f64,
+}
+
+/// Without a radius, browsing suggests the nearest users however far away they are.
+#[derive(Debug, Deserialize)]
+pub struct BrowseQuery {
+    pub radius_km: Option<f64>,
+    pub limit: Option<i64>,
+    pub cursor: Option<String>,
+}
 
 pub async fn get_profile() -> Json<Value> {
     Json(json!({
@@ -15,11 +83,393 @@ pub async fn update_profile() -> Json<Value> {
     }))
 }
 
-pub async fn upload_pictures() -> Json<Value> {
-    Json(json!({
-        "message": "Upload pictures endpoint - to be implemented",
-        "status": "not_implemented"
-    }))
+/// The current fame rating and, newest first, how it was computed over time.
+pub async fn get_fame(
+    State(state): State<crate::AppState>,
+    Extension(auth_user): Extension<AuthUser>,
+    Query(query): Query<PageQuery>,
+) -> impl IntoResponse {
+    let (limit, after) =
+        match time_page_params(&state.cursor_signer, FAME_HISTORY_CURSOR_SCOPE, &query) {
+            Ok(params) => params,
+            Err(response) => return response,
+        };
+
+    let fame_repo = FameRepository::new(state.db);
+    let rating = fame_repo.find_rating(auth_user.user.id).await;
+    let history = fame_repo
+        .find_history(auth_user.user.id, after, limit + 1)
+        .await;
+
+    match (rating, history) {
+        (Ok(rating), Ok(history)) => {
+            let page = Page::from_overfetched(history, limit as usize, |entry| {
+                time_cursor(
+                    &state.cursor_signer,
+                    FAME_HISTORY_CURSOR_SCOPE,
+                    entry.computed_at,
+                    entry.id,
+                )
+            });
+
+            (
+                StatusCode::OK,
+                Json(json!({
+                    "rating": rating.unwrap_or(0.0),
+                    "history": page
+                })),
+            )
+                .into_response()
+        }
+        _ => database_error(),
+    }
+}
+
+pub async fn get_pictures(
+    State(state): State<crate::AppState>,
+    Extension(auth_user): Extension<AuthUser>,
+) -> impl IntoResponse {
+    let picture_repo = PictureRepository::new(state.db);
+    let user_id = auth_user.user.id;
+
+    let (pictures, variants) = match tokio::try_join!(
+        picture_repo.find_by_user(user_id),
+        picture_repo.find_variants_by_user(user_id)
+    ) {
+        Ok(result) => result,
+        Err(_) => {
+            return database_error();
+        }
+    };
+
+    (
+        StatusCode::OK,
+        Json(json!({
+            "pictures": pictures_with_urls(&state.storage, &pictures, &variants)
+        })),
+    )
+        .into_response()
+}
+
+pub async fn upload_pictures(
+    State(state): State<crate::AppState>,
+    Extension(auth_user): Extension<AuthUser>,
+    mut multipart: Multipart,
+) -> impl IntoResponse {
+    let picture_repo = PictureRepository::new(state.db);
+    let user_id = auth_user.user.id;
+
+    let mut picture_count = match picture_repo.count_for_user(user_id).await {
+        Ok(count) => count,
+        Err(_) => {
+            return database_error();
+        }
+    };
+
+    let mut pictures = Vec::new();
+
+    loop {
+        let field = match multipart.next_field().await {
+            Ok(Some(field)) => field,
+            Ok(None) => break,
+            Err(_) => {
+                return (
+                    StatusCode::BAD_REQUEST,
+                    Json(json!({
+                        "error": "Invalid multipart body"
+                    })),
+                )
+                    .into_response();
+            }
+        };
+
+        if field.file_name().is_none() {
+            continue;
+        }
+
+        // Saves reading files that cannot be kept; the insert is what enforces the limit
+        if picture_count >= MAX_PICTURES_PER_USER {
+            return too_many_pictures(&pictures);
+        }
+
+        let data = match field.bytes().await {
+            Ok(data) => data,
+            Err(_) => {
+                return (
+                    StatusCode::BAD_REQUEST,
+                    Json(json!({
+                        "error": "Failed to read uploaded file"
+                    })),
+                )
+                    .into_response();
+            }
+        };
+
+        if !image_processing::is_supported_upload(&data) {
+            return (
+                StatusCode::UNSUPPORTED_MEDIA_TYPE,
+                Json(json!({
+                    "error": "Pictures must be JPEG, PNG or WebP images",
+                    "pictures": pictures
+                })),
+            )
+                .into_response();
+        }
+
+        let upload_path =
+            match jobs::store_upload(&state.storage, Uuid::new_v4(), data.to_vec()).await {
+                Ok(path) => path,
+                Err(_) => {
+                    return (
+                        StatusCode::INTERNAL_SERVER_ERROR,
+                        Json(json!({
+                            "error": "Failed to store picture"
+                        })),
+                    )
+                        .into_response();
+                }
+            };
+
+        let picture = match picture_repo
+            .create_picture(user_id, &upload_path, MAX_PICTURES_PER_USER)
+            .await
+        {
+            Ok(Some(picture)) => picture,
+            result => {
+                if let Err(e) = state.storage.delete(&upload_path).await {
+                    tracing::error!("Failed to delete upload {}: {:#}", upload_path, e);
+                }
+                return match result {
+                    // Another upload took the last place meanwhile
+                    Ok(_) => too_many_pictures(&pictures),
+                    Err(_) => (
+                        StatusCode::INTERNAL_SERVER_ERROR,
+                        Json(json!({
+                            "error": "Failed to save picture"
+                        })),
+                    )
+                        .into_response(),
+                };
+            }
+        };
+
+        if let Err(e) = state
+            .jobs
+            .enqueue(jobs::Job::ProcessPicture {
+                picture_id: picture.id,
+            })
+            .await
+        {
+            tracing::error!("Failed to queue picture {}: {:#}", picture.id, e);
+        }
+
+        picture_count += 1;
+        pictures.push(picture);
+    }
+
+    if pictures.is_empty() {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(json!({
+                "error": "No pictures were uploaded"
+            })),
+        )
+            .into_response();
+    }
+
+    // Processing happens in the background, clients poll the pictures until they are ready
+    (
+        StatusCode::ACCEPTED,
+        Json(json!({
+            "message": "Pictures uploaded, processing started",
+            "pictures": pictures
+        })),
+    )
+        .into_response()
+}
+
+fn too_many_pictures(uploaded: &[Picture]) -> Response {
+    (
+        StatusCode::BAD_REQUEST,
+        Json(json!({
+            "error": format!("A profile can have at most {} pictures", MAX_PICTURES_PER_USER),
+            "pictures": uploaded
+        })),
+    )
+        .into_response()
+}
+
+pub async fn edit_picture(
+    State(state): State<crate::AppState>,
+    Extension(auth_user): Extension<AuthUser>,
+    Path(picture_id): Path<Uuid>,
+    Json(data): Json<EditPictureRequest>,
+) -> impl IntoResponse {
+    queue_picture_version(state, auth_user, picture_id, Some(data.edits)).await
+}
+
+pub async fn revert_picture(
+    State(state): State<crate::AppState>,
+    Extension(auth_user): Extension<AuthUser>,
+    Path(picture_id): Path<Uuid>,
+) -> impl IntoResponse {
+    queue_picture_version(state, auth_user, picture_id, None).await
+}
+
+/// Records a new version of the picture and queues its rendering.
+///
+/// New edits are appended to the current version's list, which is always applied to the
+/// untouched original; `None` starts over from the original.
+async fn queue_picture_version(
+    state: crate::AppState,
+    auth_user: AuthUser,
+    picture_id: Uuid,
+    new_edits: Option<Vec<Edit>>,
+) -> axum::response::Response {
+    let picture_repo = PictureRepository::new(state.db);
+
+    let picture = match picture_repo.find_by_id(picture_id).await {
+        Ok(Some(picture)) if picture.user_id == auth_user.user.id => picture,
+        Ok(_) => {
+            return (
+                StatusCode::NOT_FOUND,
+                Json(json!({
+                    "error": "Picture not found"
+                })),
+            )
+                .into_response();
+        }
+        Err(_) => {
+            return database_error();
+        }
+    };
+
+    let (width, height) = match (picture.is_ready(), picture.width, picture.height) {
+        (true, Some(width), Some(height)) => (width as u32, height as u32),
+        _ => {
+            return (
+                StatusCode::CONFLICT,
+                Json(json!({
+                    "error": "Picture is still being processed"
+                })),
+            )
+                .into_response();
+        }
+    };
+
+    let edits = match new_edits {
+        Some(new_edits) => {
+            let current = match picture_repo.find_current_version(picture_id).await {
+                Ok(current) => current,
+                Err(_) => {
+                    return database_error();
+                }
+            };
+
+            let mut edits: Vec<Edit> = current
+                .and_then(|version| serde_json::from_value(version.edits).ok())
+                .unwrap_or_default();
+            edits.extend(new_edits);
+            edits
+        }
+        None => Vec::new(),
+    };
+
+    if let Err(e) = image_editing::validate_edits(&edits, width, height) {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(json!({
+                "error": e.message,
+                "field": e.field
+            })),
+        )
+            .into_response();
+    }
+
+    let version = match picture_repo.create_version(picture_id, &json!(edits)).await {
+        Ok(version) => version,
+        Err(_) => {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({
+                    "error": "Failed to save picture version"
+                })),
+            )
+                .into_response();
+        }
+    };
+
+    // The picture stays ready and shown as it was until the new version is rendered
+    if let Err(e) = state
+        .jobs
+        .enqueue(jobs::Job::RenderPictureVersion { picture_id })
+        .await
+    {
+        tracing::error!("Failed to queue rendering of picture {}: {:#}", picture_id, e);
+        if let Err(e) = picture_repo
+            .delete_version(picture_id, version.version)
+            .await
+        {
+            tracing::error!("Failed to drop version of picture {}: {:#}", picture_id, e);
+        }
+        return (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": "Failed to start rendering"
+            })),
+        )
+            .into_response();
+    }
+
+    (
+        StatusCode::ACCEPTED,
+        Json(json!({
+            "message": "Picture version saved, rendering started",
+            "version": version.version,
+            "edits": version.edits
+        })),
+    )
+        .into_response()
+}
+
+/// Attaches short-lived signed URLs for every rendition, grouped by variant and format.
+fn pictures_with_urls(
+    storage: &SharedStorage,
+    pictures: &[Picture],
+    variants: &[PictureVariant],
+) -> Vec<Value> {
+    let expires_at = storage::signed_url_expiry();
+
+    pictures
+        .iter()
+        .map(|picture| {
+            let mut urls = serde_json::Map::new();
+            for variant in variants.iter().filter(|v| v.picture_id == picture.id) {
+                let Ok(url) = storage.signed_url(&variant.path, expires_at) else {
+                    continue;
+                };
+                let formats = urls
+                    .entry(variant.variant.clone())
+                    .or_insert_with(|| json!({}));
+                formats[&variant.format] = json!({
+                    "url": url,
+                    "width": variant.width,
+                    "height": variant.height
+                });
+            }
+
+            json!({
+                "id": picture.id,
+                "is_profile_picture": picture.is_profile_picture,
+                "status": picture.status,
+                "width": picture.width,
+                "height": picture.height,
+                "created_at": picture.created_at,
+                "variants": urls,
+                "urls_expire_at": expires_at
+            })
+        })
+        .collect()
 }
 
 pub async fn delete_picture() -> Json<Value> {
@@ -29,30 +479,783 @@ pub async fn delete_picture() -> Json<Value> {
     }))
 }
 
-pub async fn browse_users() -> Json<Value> {
-    Json(json!({
-        "message": "Browse users endpoint - to be implemented",
-        "status": "not_implemented"
-    }))
+pub async fn get_location(
+    State(state): State<crate::AppState>,
+    Extension(auth_user): Extension<AuthUser>,
+) -> impl IntoResponse {
+    let location_repo = LocationRepository::new(state.db);
+    let user_id = auth_user.user.id;
+
+    let location = location_repo.find_effective(user_id).await;
+    let public = location_repo.find_public(user_id).await;
+    match (location, public) {
+        (Ok(location), Ok(public)) => location_response("Location retrieved", location, public),
+        _ => database_error(),
+    }
 }
 
-pub async fn search_users() -> Json<Value> {
-    Json(json!({
-        "message": "Search users endpoint - to be implemented",
-        "status": "not_implemented"
-    }))
+pub async fn update_location(
+    State(state): State<crate::AppState>,
+    Extension(auth_user): Extension<AuthUser>,
+    Json(data): Json<UpdateLocationRequest>,
+) -> impl IntoResponse {
+    let accuracy = match validate_accuracy(data.accuracy) {
+        Ok(accuracy) => accuracy,
+        Err(e) => {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(json!({
+                    "error": e.message,
+                    "field": e.field
+                })),
+            )
+                .into_response();
+        }
+    };
+
+    save_location(state, auth_user, LocationSource::Gps, data.latitude, data.longitude, accuracy)
+        .await
 }
 
-pub async fn get_user_profile() -> Json<Value> {
-    Json(json!({
-        "message": "Get user profile endpoint - to be implemented",
-        "status": "not_implemented"
-    }))
+pub async fn set_manual_location(
+    State(state): State<crate::AppState>,
+    Extension(auth_user): Extension<AuthUser>,
+    Json(data): Json<ManualLocationRequest>,
+) -> impl IntoResponse {
+    save_location(state, auth_user, LocationSource::Manual, data.latitude, data.longitude, None)
+        .await
 }
 
-pub async fn record_visit() -> Json<Value> {
-    Json(json!({
-        "message": "Record visit endpoint - to be implemented",
-        "status": "not_implemented"
-    }))
+/// Drops the manual override, so the GPS or IP location applies again.
+pub async fn clear_manual_location(
+    State(state): State<crate::AppState>,
+    Extension(auth_user): Extension<AuthUser>,
+) -> impl IntoResponse {
+    let location_repo = LocationRepository::new(state.db.clone());
+
+    match location_repo
+        .delete(auth_user.user.id, LocationSource::Manual)
+        .await
+    {
+        Ok(true) => location_changed_response(state, auth_user, "Manual location removed").await,
+        Ok(false) => (
+            StatusCode::NOT_FOUND,
+            Json(json!({
+                "error": "No manual location set"
+            })),
+        )
+            .into_response(),
+        Err(_) => database_error(),
+    }
+}
+
+/// Fallback for users who deny GPS: locates the client IP in the local GeoIP database.
+pub async fn locate_by_ip(
+    State(state): State<crate::AppState>,
+    Extension(auth_user): Extension<AuthUser>,
+    ConnectInfo(peer): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+) -> impl IntoResponse {
+    if !state.geoip.is_enabled() {
+        return (
+            StatusCode::SERVICE_UNAVAILABLE,
+            Json(json!({
+                "error": "IP-based location is not available, please set your location manually"
+            })),
+        )
+            .into_response();
+    }
+
+    let ip = crate::utils::client_ip(&headers, peer, &state.trusted_proxies);
+    let located = match state.geoip.locate(ip) {
+        Ok(Some(located)) => located,
+        Ok(None) => {
+            return (
+                StatusCode::UNPROCESSABLE_ENTITY,
+                Json(json!({
+                    "error": "Could not determine your location, please set it manually"
+                })),
+            )
+                .into_response();
+        }
+        Err(e) => {
+            tracing::error!("GeoIP lookup of {} failed: {:#}", ip, e);
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({
+                    "error": "Failed to determine location"
+                })),
+            )
+                .into_response();
+        }
+    };
+
+    let location_repo = LocationRepository::new(state.db.clone());
+    if location_repo
+        .upsert(auth_user.user.id, LocationSource::Ip, located.coordinates, located.accuracy_m)
+        .await
+        .is_err()
+    {
+        return (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": "Failed to save location"
+            })),
+        )
+            .into_response();
+    }
+
+    location_changed_response(state, auth_user, "Location updated").await
+}
+
+async fn save_location(
+    state: crate::AppState,
+    auth_user: AuthUser,
+    source: LocationSource,
+    latitude: f64,
+    longitude: f64,
+    accuracy_m: Option<f64>,
+) -> axum::response::Response {
+    let coordinates = match Coordinates::new(latitude, longitude) {
+        Ok(coordinates) => coordinates,
+        Err(e) => {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(json!({
+                    "error": e.message,
+                    "field": e.field
+                })),
+            )
+                .into_response();
+        }
+    };
+
+    let location_repo = LocationRepository::new(state.db.clone());
+    if location_repo
+        .upsert(auth_user.user.id, source, coordinates, accuracy_m)
+        .await
+        .is_err()
+    {
+        return (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": "Failed to save location"
+            })),
+        )
+            .into_response();
+    }
+
+    location_changed_response(state, auth_user, "Location updated").await
+}
+
+/// Re-derives the public location from the one that applies after a change, which is not
+/// necessarily the one just saved: a manual override keeps shadowing new GPS and IP locations.
+async fn location_changed_response(
+    state: crate::AppState,
+    auth_user: AuthUser,
+    message: &str,
+) -> axum::response::Response {
+    let location_repo = LocationRepository::new(state.db.clone());
+    let user_id = auth_user.user.id;
+
+    let refreshed = async {
+        let location = location_repo.find_effective(user_id).await?;
+        let public = match &location {
+            Some(location) => {
+                let public = state.geo.public_location(location.coordinates());
+                location_repo.upsert_public(user_id, &public).await?;
+                Some(public)
+            }
+            None => {
+                location_repo.delete_public(user_id).await?;
+                None
+            }
+        };
+        anyhow::Ok((location, public))
+    }
+    .await;
+
+    match refreshed {
+        Ok((location, public)) => location_response(message, location, public),
+        Err(e) => {
+            tracing::error!("Failed to refresh location of {}: {:#}", user_id, e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({
+                    "error": "Failed to update location"
+                })),
+            )
+                .into_response()
+        }
+    }
+}
+
+fn location_response(
+    message: &str,
+    location: Option<UserLocation>,
+    public: Option<PublicLocation>,
+) -> axum::response::Response {
+    let public = public.as_ref().map(public_location_json);
+
+    (
+        StatusCode::OK,
+        Json(json!({
+            "message": message,
+            "location": location,
+            "public_location": public
+        })),
+    )
+        .into_response()
+}
+
+fn public_location_json(public: &PublicLocation) -> Value {
+    json!({
+        "display_name": public.display_name(),
+        "latitude": public.latitude,
+        "longitude": public.longitude,
+        "neighborhood": public.neighborhood,
+        "city": public.city,
+        "country_code": public.country_code
+    })
+}
+
+pub async fn browse_users(
+    State(state): State<crate::AppState>,
+    Extension(auth_user): Extension<AuthUser>,
+    Query(query): Query<BrowseQuery>,
+) -> impl IntoResponse {
+    let (limit, after) = match page_params(
+        &state.cursor_signer,
+        BROWSE_CURSOR_SCOPE,
+        query.limit,
+        query.cursor.as_deref(),
+    ) {
+        Ok(params) => params,
+        Err(response) => return response,
+    };
+    let after = match after {
+        None => None,
+        Some(cursor) => match cursor.values.as_slice() {
+            [SortValue::Number(score), SortValue::Number(distance_km)] => {
+                Some((*score, *distance_km, cursor.id))
+            }
+            _ => return invalid_cursor(),
+        },
+    };
+    if let Some(radius_km) = query.radius_km {
+        if !(radius_km > 0.0 && radius_km <= MAX_BROWSE_RADIUS_KM) {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(json!({
+                    "error": format!("Radius must be between 0 and {} km", MAX_BROWSE_RADIUS_KM),
+                    "field": "radius_km"
+                })),
+            )
+                .into_response();
+        }
+    }
+
+    let location_repo = LocationRepository::new(state.db.clone());
+    let origin = match location_repo.find_effective(auth_user.user.id).await {
+        Ok(Some(location)) => location.coordinates(),
+        Ok(None) => {
+            return (
+                StatusCode::CONFLICT,
+                Json(json!({
+                    "error": "Set your location before browsing"
+                })),
+            )
+                .into_response();
+        }
+        Err(_) => {
+            return database_error();
+        }
+    };
+
+    let orientation = match ProfileRepository::new(state.db.clone())
+        .find_by_user_id(auth_user.user.id)
+        .await
+    {
+        Ok(Some(Profile {
+            gender: Some(gender),
+            sexual_preference,
+            ..
+        })) => Orientation::new(gender, sexual_preference),
+        Ok(_) => {
+            return (
+                StatusCode::CONFLICT,
+                Json(json!({
+                    "error": "Set your gender before browsing"
+                })),
+            )
+                .into_response();
+        }
+        Err(_) => {
+            return database_error();
+        }
+    };
+
+    // Ranking needs the whole neighbourhood at once, so only the closest candidates are ranked
+    let user_repo = UserRepository::new(state.db);
+    let candidates = match query.radius_km {
+        Some(radius_km) => {
+            user_repo
+                .find_browse_candidates(
+                    auth_user.user.id,
+                    &orientation,
+                    origin,
+                    radius_km,
+                    MAX_BROWSE_CANDIDATES,
+                )
+                .await
+        }
+        None => {
+            user_repo
+                .find_nearest_candidates(
+                    auth_user.user.id,
+                    &orientation,
+                    origin,
+                    MAX_BROWSE_CANDIDATES,
+                )
+                .await
+        }
+    };
+    let viewer_tags = user_repo.count_tags(auth_user.user.id).await;
+
+    match (candidates, viewer_tags) {
+        (Ok(candidates), Ok(viewer_tags)) => {
+            let ranked = browse::rank(candidates, &state.browse_weights, viewer_tags, after);
+            let page = Page::from_overfetched(ranked, limit as usize, |ranked| {
+                let (score, distance_km, id) = ranked.position();
+                state.cursor_signer.encode(&Cursor {
+                    scope: BROWSE_CURSOR_SCOPE.to_string(),
+                    values: vec![SortValue::Number(score), SortValue::Number(distance_km)],
+                    id,
+                })
+            })
+            .map(|ranked| {
+                let candidate = &ranked.candidate;
+                json!({
+                    "id": candidate.user.id,
+                    "username": candidate.user.username,
+                    "location": public_location_json(&candidate.location),
+                    // Public locations are only neighborhood-accurate, more digits would suggest otherwise
+                    "distance_km": (candidate.distance_km * 10.0).round() / 10.0,
+                    "shared_tags": candidate.shared_tags,
+                    "fame_rating": candidate.fame_rating,
+                    "score": (ranked.score * 1000.0).round() / 1000.0
+                })
+            });
+
+            (StatusCode::OK, Json(page)).into_response()
+        }
+        _ => database_error(),
+    }
+}
+
+pub async fn search_users(
+    State(state): State<crate::AppState>,
+    Extension(auth_user): Extension<AuthUser>,
+    Query(query): Query<SearchQuery>,
+) -> impl IntoResponse {
+    let criteria = match SearchCriteria::from_query(&query) {
+        Ok(criteria) => criteria,
+        Err(e) => {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(json!({
+                    "error": e.message,
+                    "field": e.field
+                })),
+            )
+                .into_response();
+        }
+    };
+    // A named place replaces the user's own location as the centre of the search
+    let origin = match query.location.as_deref().map(str::trim) {
+        Some(name) if !name.is_empty() => {
+            let Some(gazetteer) = state.geo.gazetteer() else {
+                return (
+                    StatusCode::BAD_REQUEST,
+                    Json(json!({
+                        "error": "Searching by place name is not available",
+                        "field": "location"
+                    })),
+                )
+                    .into_response();
+            };
+            match gazetteer.find(name) {
+                Some(place) => Some(place.coordinates),
+                None => {
+                    return (
+                        StatusCode::BAD_REQUEST,
+                        Json(json!({
+                            "error": "Unknown location",
+                            "field": "location"
+                        })),
+                    )
+                        .into_response();
+                }
+            }
+        }
+        _ => match LocationRepository::new(state.db.clone())
+            .find_effective(auth_user.user.id)
+            .await
+        {
+            Ok(location) => location.map(|location| location.coordinates()),
+            Err(_) => {
+                return database_error();
+            }
+        },
+    };
+    if origin.is_none() && criteria.needs_origin() {
+        return (
+            StatusCode::CONFLICT,
+            Json(json!({
+                "error": "Set your location or name a place to search by distance"
+            })),
+        )
+            .into_response();
+    }
+
+    // Cursors only make sense for the search they were issued for
+    let scope = criteria.cursor_scope(origin);
+    let (limit, after) =
+        match page_params(&state.cursor_signer, &scope, query.limit, query.cursor.as_deref()) {
+            Ok(params) => params,
+            Err(response) => return response,
+        };
+    let after = match after {
+        None => None,
+        Some(cursor) => match cursor.values.as_slice() {
+            [value] => Some((value.clone(), cursor.id)),
+            _ => return invalid_cursor(),
+        },
+    };
+
+    let orientation = match ProfileRepository::new(state.db.clone())
+        .find_by_user_id(auth_user.user.id)
+        .await
+    {
+        Ok(Some(Profile {
+            gender: Some(gender),
+            sexual_preference,
+            ..
+        })) => Orientation::new(gender, sexual_preference),
+        Ok(_) => {
+            return (
+                StatusCode::CONFLICT,
+                Json(json!({
+                    "error": "Set your gender before searching"
+                })),
+            )
+                .into_response();
+        }
+        Err(_) => {
+            return database_error();
+        }
+    };
+
+    let user_repo = UserRepository::new(state.db);
+    match user_repo
+        .search(auth_user.user.id, &orientation, origin, &criteria, after, limit + 1)
+        .await
+    {
+        Ok(results) => {
+            let page = Page::from_overfetched(results, limit as usize, |result| {
+                state.cursor_signer.encode(&Cursor {
+                    scope: scope.clone(),
+                    values: vec![search_sort_value(result, criteria.sort)],
+                    id: result.user.id,
+                })
+            })
+            .map(|result| {
+                json!({
+                    "id": result.user.id,
+                    "username": result.user.username,
+                    "age": result.age,
+                    "location": result.location.as_ref().map(public_location_json),
+                    "distance_km": result.distance_km.map(|distance| (distance * 10.0).round() / 10.0),
+                    "fame_rating": result.fame_rating,
+                    "common_tags": result.common_tags
+                })
+            });
+
+            (StatusCode::OK, Json(page)).into_response()
+        }
+        Err(_) => database_error(),
+    }
+}
+
+fn search_sort_value(result: &SearchResult, sort: SearchSort) -> SortValue {
+    match sort {
+        SearchSort::Distance => result.distance_km.into(),
+        SearchSort::Age => result.age.map(f64::from).into(),
+        SearchSort::Fame => SortValue::Number(result.fame_rating),
+        SearchSort::CommonTags => SortValue::Number(result.common_tags as f64),
+    }
+}
+
+/// Another user's profile as the viewer may see it, with how the two are connected.
+pub async fn get_user_profile(
+    State(state): State<crate::AppState>,
+    Extension(auth_user): Extension<AuthUser>,
+    Path(user_id): Path<Uuid>,
+) -> impl IntoResponse {
+    let viewer_id = auth_user.user.id;
+
+    let user = UserRepository::new(state.db.clone())
+        .find_by_id(user_id)
+        .await;
+    let blocked = BlockRepository::new(state.db.clone())
+        .is_blocked_between(viewer_id, user_id)
+        .await;
+    let user = match (user, blocked) {
+        (Ok(Some(user)), Ok(false)) if user.is_active() => user,
+        (Ok(_), Ok(_)) => {
+            return (
+                StatusCode::NOT_FOUND,
+                Json(json!({
+                    "error": "User not found"
+                })),
+            )
+                .into_response();
+        }
+        _ => {
+            return database_error();
+        }
+    };
+
+    let profile_repo = ProfileRepository::new(state.db.clone());
+    let picture_repo = PictureRepository::new(state.db.clone());
+    let location_repo = LocationRepository::new(state.db.clone());
+    let presence_repo = PresenceRepository::new(state.db.clone());
+    let presence = state.presence.status(user_id);
+    let like_repo = LikeRepository::new(state.db);
+    let details = tokio::try_join!(
+        profile_repo.find_by_user_id(user_id),
+        profile_repo.find_tags(user_id),
+        picture_repo.find_by_user(user_id),
+        picture_repo.find_variants_by_user(user_id),
+        location_repo.find_public(user_id),
+        async {
+            match presence {
+                Some(status) => Ok(Some(status.last_seen_at)),
+                None => presence_repo.find_last_seen(user_id).await,
+            }
+        },
+        like_repo.find_status(viewer_id, user_id)
+    );
+    let (profile, tags, pictures, variants, location, last_seen_at, connection) = match details {
+        Ok(details) => details,
+        Err(_) => {
+            return database_error();
+        }
+    };
+
+    let now = chrono::Utc::now();
+    let pictures: Vec<Picture> = pictures
+        .into_iter()
+        .filter(|picture| picture.status == PictureStatus::Ready)
+        .collect();
+
+    let mut view = PublicProfileView::new(&user, profile.as_ref(), now.date_naive());
+    view.tags = tags;
+    view.pictures = pictures_with_urls(&state.storage, &pictures, &variants);
+    view.location = location.as_ref().map(public_location_json);
+    view.online = presence.is_some_and(|status| status.online);
+    view.last_seen_at = last_seen_at;
+    view.connection = connection;
+
+    (StatusCode::OK, Json(view)).into_response()
+}
+
+/// Records that the user looked at the profile of `id`, and tells them about it.
+///
+/// Repeated views within `VISIT_WINDOW_MINUTES` count once, so reloading a profile
+/// neither floods the visited user with notifications nor inflates their fame.
+pub async fn record_visit(
+    State(state): State<crate::AppState>,
+    Extension(auth_user): Extension<AuthUser>,
+    Path(visited_id): Path<Uuid>,
+) -> impl IntoResponse {
+    let visitor_id = auth_user.user.id;
+    if visited_id == visitor_id {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(json!({
+                "error": "You cannot visit your own profile"
+            })),
+        )
+            .into_response();
+    }
+
+    let visited = UserRepository::new(state.db.clone())
+        .find_by_id(visited_id)
+        .await;
+    let blocked = BlockRepository::new(state.db.clone())
+        .is_blocked_between(visitor_id, visited_id)
+        .await;
+    match (visited, blocked) {
+        (Ok(Some(user)), Ok(false)) if user.is_active() => {}
+        (Ok(_), Ok(_)) => {
+            return (
+                StatusCode::NOT_FOUND,
+                Json(json!({
+                    "error": "User not found"
+                })),
+            )
+                .into_response();
+        }
+        _ => {
+            return database_error();
+        }
+    }
+
+    let since = chrono::Utc::now() - chrono::Duration::minutes(VISIT_WINDOW_MINUTES);
+    let recorded = match VisitRepository::new(state.db.clone())
+        .record(visitor_id, visited_id, since)
+        .await
+    {
+        Ok(recorded) => recorded,
+        Err(_) => {
+            return database_error();
+        }
+    };
+
+    // The visit itself is stored, so side effects failing must not fail the request
+    if recorded {
+        notifications::notify(
+            &state.db,
+            &state.hub,
+            visited_id,
+            visitor_id,
+            NotificationKind::ProfileViewed,
+        )
+        .await;
+        if let Err(e) = FameService::new(state.db, state.fame)
+            .record(visited_id, Some(visitor_id), FameEventKind::View)
+            .await
+        {
+            tracing::error!("Failed to count visit of {} towards fame: {:#}", visited_id, e);
+        }
+    }
+
+    (
+        StatusCode::OK,
+        Json(json!({
+            "recorded": recorded
+        })),
+    )
+        .into_response()
+}
+
+/// Who looked at the user's profile, newest first.
+pub async fn get_visitors(
+    State(state): State<crate::AppState>,
+    Extension(auth_user): Extension<AuthUser>,
+    Query(query): Query<PageQuery>,
+) -> impl IntoResponse {
+    let (limit, after) = match time_page_params(&state.cursor_signer, VISITS_CURSOR_SCOPE, &query) {
+        Ok(params) => params,
+        Err(response) => return response,
+    };
+
+    match VisitRepository::new(state.db)
+        .find_visitors(auth_user.user.id, after, limit + 1)
+        .await
+    {
+        Ok(visits) => {
+            let page = Page::from_overfetched(visits, limit as usize, |visit| {
+                time_cursor(&state.cursor_signer, VISITS_CURSOR_SCOPE, visit.visited_at, visit.id)
+            })
+            .map(|visit| {
+                json!({
+                    "user": {
+                        "id": visit.visitor_id,
+                        "username": visit.visitor_username
+                    },
+                    "visited_at": visit.visited_at
+                })
+            });
+
+            (StatusCode::OK, Json(page)).into_response()
+        }
+        Err(_) => database_error(),
+    }
+}
+
+/// Who liked the user, newest first.
+pub async fn get_likers(
+    State(state): State<crate::AppState>,
+    Extension(auth_user): Extension<AuthUser>,
+    Query(query): Query<PageQuery>,
+) -> impl IntoResponse {
+    let (limit, after) = match time_page_params(&state.cursor_signer, LIKES_CURSOR_SCOPE, &query) {
+        Ok(params) => params,
+        Err(response) => return response,
+    };
+
+    match LikeRepository::new(state.db)
+        .find_likers(auth_user.user.id, after, limit + 1)
+        .await
+    {
+        Ok(likes) => {
+            let page = Page::from_overfetched(likes, limit as usize, |like| {
+                time_cursor(&state.cursor_signer, LIKES_CURSOR_SCOPE, like.liked_at, like.liker_id)
+            })
+            .map(|like| {
+                json!({
+                    "user": {
+                        "id": like.liker_id,
+                        "username": like.liker_username
+                    },
+                    "liked_at": like.liked_at
+                })
+            });
+
+            (StatusCode::OK, Json(page)).into_response()
+        }
+        Err(_) => database_error(),
+    }
+}
+
+/// Users the user blocked, most recent first.
+pub async fn get_blocked_users(
+    State(state): State<crate::AppState>,
+    Extension(auth_user): Extension<AuthUser>,
+    Query(query): Query<PageQuery>,
+) -> impl IntoResponse {
+    let (limit, after) = match time_page_params(&state.cursor_signer, BLOCKS_CURSOR_SCOPE, &query) {
+        Ok(params) => params,
+        Err(response) => return response,
+    };
+
+    match BlockRepository::new(state.db)
+        .find_blocked(auth_user.user.id, after, limit + 1)
+        .await
+    {
+        Ok(blocks) => {
+            let page = Page::from_overfetched(blocks, limit as usize, |block| {
+                time_cursor(
+                    &state.cursor_signer,
+                    BLOCKS_CURSOR_SCOPE,
+                    block.blocked_at,
+                    block.blocked_id,
+                )
+            })
+            .map(|block| {
+                json!({
+                    "user": {
+                        "id": block.blocked_id,
+                        "username": block.blocked_username
+                    },
+                    "blocked_at": block.blocked_at
+                })
+            });
+
+            (StatusCode::OK, Json(page)).into_response()
+        }
+        Err(_) => database_error(),
+    }
 }
@@ -1,6 +1,8 @@
-use crate::enums::Environment;
+use crate::enums::{Environment, StorageBackend};
+use crate::services::browse::BrowseWeights;
 use crate::validation::{self, Validator};
 use std::env;
+use std::net::IpAddr;
 use tracing::Level;
 
 #[derive(Debug, Clone)]
@@ -17,9 +19,26 @@ pub struct Config {
     pub from_email: String,
     pub ethereal_user: String,
     pub ethereal_pass: String,
+    pub public_url: String,
+    pub storage_backend: StorageBackend,
+    pub media_dir: String,
+    pub s3: Option<S3Config>,
+    pub geoip_database_path: Option<String>,
+    pub gazetteer_path: Option<String>,
+    pub trusted_proxies: Vec<IpAddr>,
+    pub browse_weights: BrowseWeights,
     pub log_level: Level,
 }
 
+#[derive(Debug, Clone)]
+pub struct S3Config {
+    pub endpoint: String,
+    pub region: String,
+    pub bucket: String,
+    pub access_key: String,
+    pub secret_key: String,
+}
+
 impl Config {
     pub fn load() -> anyhow::Result<Self> {
         dotenvy::dotenv().ok();
@@ -28,6 +47,10 @@ impl Config {
             env::var(name).map_err(|_| anyhow::anyhow!("'{}' env variable must be set", name))
         }
 
+        fn get_optional_env_var(name: &str) -> String {
+            env::var(name).unwrap_or_default()
+        }
+
         let environment = validation::enums::<Environment>()
             .validate(&get_env_var("ENVIRONMENT")?)?
             .ok_or_else(|| anyhow::anyhow!("ENVIRONMENT is required"))?;
@@ -38,6 +61,65 @@ impl Config {
             Environment::Test => Level::ERROR,
         };
 
+        let storage_backend = validation::enums::<StorageBackend>()
+            .validate(&get_env_var("STORAGE_BACKEND")?)?
+            .ok_or_else(|| anyhow::anyhow!("STORAGE_BACKEND is required"))?;
+
+        // S3 settings are only required when pictures are actually stored there
+        let s3 = match storage_backend {
+            StorageBackend::Local => None,
+            StorageBackend::S3 => Some(S3Config {
+                endpoint: validation::url()
+                    .validate(&get_optional_env_var("S3_ENDPOINT"))?
+                    .ok_or_else(|| anyhow::anyhow!("S3_ENDPOINT is required"))?,
+                region: validation::string()
+                    .validate(&get_optional_env_var("S3_REGION"))?
+                    .ok_or_else(|| anyhow::anyhow!("S3_REGION is required"))?,
+                bucket: validation::string()
+                    .min_length(3)
+                    .max_length(63)
+                    .validate(&get_optional_env_var("S3_BUCKET"))?
+                    .ok_or_else(|| anyhow::anyhow!("S3_BUCKET is required"))?,
+                access_key: validation::string()
+                    .validate(&get_optional_env_var("S3_ACCESS_KEY"))?
+                    .ok_or_else(|| anyhow::anyhow!("S3_ACCESS_KEY is required"))?,
+                secret_key: validation::string()
+                    .validate(&get_optional_env_var("S3_SECRET_KEY"))?
+                    .ok_or_else(|| anyhow::anyhow!("S3_SECRET_KEY is required"))?,
+            }),
+        };
+
+        // Each weight may be tuned on its own, unset ones keep their default
+        let default_weights = BrowseWeights::default();
+        let browse_weight = |name: &str, default: f64| -> anyhow::Result<f64> {
+            let weight: Option<f64> = validation::number()
+                .optional()
+                .min_value(0.0)
+                .max_value(100.0)
+                .validate(&get_optional_env_var(name))?;
+            Ok(weight.unwrap_or(default))
+        };
+        let browse_weights = BrowseWeights {
+            distance: browse_weight("BROWSE_WEIGHT_DISTANCE", default_weights.distance)?,
+            shared_tags: browse_weight("BROWSE_WEIGHT_SHARED_TAGS", default_weights.shared_tags)?,
+            fame: browse_weight("BROWSE_WEIGHT_FAME", default_weights.fame)?,
+        };
+        if browse_weights.distance + browse_weights.shared_tags + browse_weights.fame <= 0.0 {
+            anyhow::bail!("At least one BROWSE_WEIGHT_* must be greater than 0");
+        }
+
+        // Only these peers may report the client address in X-Forwarded-For
+        let trusted_proxies = get_optional_env_var("TRUSTED_PROXIES")
+            .split(',')
+            .map(str::trim)
+            .filter(|proxy| !proxy.is_empty())
+            .map(|proxy| {
+                proxy.parse::<IpAddr>().map_err(|_| {
+                    anyhow::anyhow!("TRUSTED_PROXIES contains an invalid address: {}", proxy)
+                })
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+
         let config = Config {
             environment,
             port: validation::number()
@@ -91,6 +173,32 @@ impl Config {
                 .validate(&get_env_var("ETHEREAL_PASS")?)?
                 .ok_or_else(|| anyhow::anyhow!("ETHEREAL_PASS is required"))?,
 
+            public_url: validation::url()
+                .validate(&get_env_var("PUBLIC_URL")?)?
+                .ok_or_else(|| anyhow::anyhow!("PUBLIC_URL is required"))?,
+
+            storage_backend,
+
+            media_dir: validation::string()
+                .validate(&get_env_var("MEDIA_DIR")?)?
+                .ok_or_else(|| anyhow::anyhow!("MEDIA_DIR is required"))?,
+
+            s3,
+
+            // Without a GeoIP database, users who deny GPS have to set their location manually
+            geoip_database_path: validation::string()
+                .optional()
+                .validate(&get_optional_env_var("GEOIP_DATABASE_PATH"))?,
+
+            // Without a gazetteer, public locations are still coarsened but remain unnamed
+            gazetteer_path: validation::string()
+                .optional()
+                .validate(&get_optional_env_var("GAZETTEER_PATH"))?,
+
+            trusted_proxies,
+
+            browse_weights,
+
             log_level,
         };
 
@@ -0,0 +1,189 @@
+use crate::database::like_repository::lock_pair;
+use crate::database::query_builder::Sql;
+use crate::models::BlockedUser;
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+/// What blocking a user changed.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct BlockOutcome {
+    /// False when the block already existed.
+    pub blocked: bool,
+    /// Likes between the two users, as `(liker_id, liked_id)`, removed with the block.
+    pub removed_likes: Vec<(Uuid, Uuid)>,
+    pub disconnected: bool,
+}
+
+#[derive(Debug)]
+pub struct BlockRepository {
+    pool: PgPool,
+}
+
+impl BlockRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// Blocks `blocked_id` and removes every like and connection between the two users.
+    pub async fn block(&self, blocker_id: Uuid, blocked_id: Uuid) -> Result<BlockOutcome> {
+        let mut tx = self.pool.begin().await?;
+        lock_pair(&mut tx, blocker_id, blocked_id).await?;
+
+        let blocked = sqlx::query!(
+            r#"
+            INSERT INTO blocks (blocker_id, blocked_id)
+            VALUES ($1, $2)
+            ON CONFLICT DO NOTHING
+            "#,
+            blocker_id,
+            blocked_id
+        )
+        .execute(&mut *tx)
+        .await?
+        .rows_affected()
+            > 0;
+
+        let removed_likes = sqlx::query!(
+            r#"
+            DELETE FROM likes
+            WHERE (liker_id = $1 AND liked_id = $2) OR (liker_id = $2 AND liked_id = $1)
+            RETURNING liker_id, liked_id
+            "#,
+            blocker_id,
+            blocked_id
+        )
+        .fetch_all(&mut *tx)
+        .await?
+        .into_iter()
+        .map(|row| (row.liker_id, row.liked_id))
+        .collect();
+
+        let disconnected = sqlx::query!(
+            r#"
+            DELETE FROM connections
+            WHERE user_id = LEAST($1::uuid, $2::uuid) AND other_user_id = GREATEST($1::uuid, $2::uuid)
+            "#,
+            blocker_id,
+            blocked_id
+        )
+        .execute(&mut *tx)
+        .await?
+        .rows_affected()
+            > 0;
+
+        tx.commit().await?;
+
+        Ok(BlockOutcome {
+            blocked,
+            removed_likes,
+            disconnected,
+        })
+    }
+
+    /// Lifts a block, returning whether there was one. Removed likes stay removed.
+    pub async fn unblock(&self, blocker_id: Uuid, blocked_id: Uuid) -> Result<bool> {
+        let result = sqlx::query!(
+            "DELETE FROM blocks WHERE blocker_id = $1 AND blocked_id = $2",
+            blocker_id,
+            blocked_id
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Users `blocker_id` blocked, newest first, continuing after `(created_at, blocked_id)`.
+    pub async fn find_blocked(
+        &self,
+        blocker_id: Uuid,
+        after: Option<(DateTime<Utc>, Uuid)>,
+        limit: i64,
+    ) -> Result<Vec<BlockedUser>> {
+        let (after_created_at, after_blocked_id) = after.unzip();
+        let rows = sqlx::query!(
+            r#"
+            SELECT b.blocked_id, u.username, b.created_at
+            FROM blocks b
+            JOIN users u ON u.id = b.blocked_id
+            WHERE b.blocker_id = $1
+            AND ($2::timestamptz IS NULL OR (b.created_at, b.blocked_id) < ($2, $3))
+            ORDER BY b.created_at DESC, b.blocked_id DESC
+            LIMIT $4
+            "#,
+            blocker_id,
+            after_created_at,
+            after_blocked_id,
+            limit
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| BlockedUser {
+                blocked_id: row.blocked_id,
+                blocked_username: row.username,
+                blocked_at: row.created_at,
+            })
+            .collect())
+    }
+
+    /// Whether either user blocked the other.
+    pub async fn is_blocked_between(&self, user_id: Uuid, other_id: Uuid) -> Result<bool> {
+        let blocked = sqlx::query_scalar!(
+            r#"
+            SELECT is_blocked_between($1, $2) as "blocked!"
+            "#,
+            user_id,
+            other_id
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(blocked)
+    }
+
+    /// The users among `other_ids` that neither blocked nor were blocked by `user_id`.
+    pub async fn filter_unblocked(&self, user_id: Uuid, other_ids: &[Uuid]) -> Result<Vec<Uuid>> {
+        let ids = sqlx::query_scalar!(
+            r#"
+            SELECT o.id as "id!"
+            FROM UNNEST($2::uuid[]) AS o(id)
+            WHERE NOT is_blocked_between($1, o.id)
+            "#,
+            user_id,
+            other_ids
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(ids)
+    }
+}
+
+/// Condition for built queries keeping only rows where the user in `column` has no block
+/// with `user_id`. Queries written out in full call `is_blocked_between` the same way.
+pub fn unblocked_condition(user_id: Uuid, column: &'static str) -> Sql {
+    Sql::new("NOT is_blocked_between(")
+        .bind(user_id)
+        .push(", ")
+        .push(column)
+        .push(")")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn binds_the_user_and_names_the_column() {
+        let sql = Sql::new("SELECT * FROM users u WHERE ")
+            .append(unblocked_condition(Uuid::nil(), "u.id"))
+            .build();
+
+        assert_eq!(sql.sql(), "SELECT * FROM users u WHERE NOT is_blocked_between($1, u.id)");
+    }
+}
@@ -0,0 +1,274 @@
+use crate::database::like_repository::lock_pair;
+use crate::models::chat::preview;
+use crate::models::{Conversation, Message, MessagePreview};
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+#[derive(Debug)]
+pub struct ChatRepository {
+    pool: PgPool,
+}
+
+impl ChatRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// Sends a message, starting the conversation with its first one. Returns `None` unless
+    /// the two users are connected.
+    pub async fn send(
+        &self,
+        sender_id: Uuid,
+        recipient_id: Uuid,
+        body: &str,
+    ) -> Result<Option<Message>> {
+        let mut tx = self.pool.begin().await?;
+        // Unlikes and blocks take the same lock, so the connection holds until the commit
+        lock_pair(&mut tx, sender_id, recipient_id).await?;
+
+        let connected = sqlx::query_scalar!(
+            r#"
+            SELECT EXISTS (
+                SELECT 1 FROM connections
+                WHERE user_id = LEAST($1::uuid, $2::uuid) AND other_user_id = GREATEST($1::uuid, $2::uuid)
+            ) as "connected!"
+            "#,
+            sender_id,
+            recipient_id
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+        if !connected {
+            return Ok(None);
+        }
+
+        // Timestamps are taken under the lock, so messages of a conversation are in order
+        let conversation_id = sqlx::query_scalar!(
+            r#"
+            INSERT INTO conversations (user_id, other_user_id, last_message_at)
+            VALUES (LEAST($1::uuid, $2::uuid), GREATEST($1::uuid, $2::uuid), clock_timestamp())
+            ON CONFLICT (user_id, other_user_id) DO UPDATE SET last_message_at = EXCLUDED.last_message_at
+            RETURNING id
+            "#,
+            sender_id,
+            recipient_id
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+
+        let message = sqlx::query_as!(
+            Message,
+            r#"
+            INSERT INTO messages (conversation_id, sender_id, body, created_at)
+            SELECT id, $2, $3, last_message_at
+            FROM conversations
+            WHERE id = $1
+            RETURNING id, conversation_id, sender_id, body, created_at
+            "#,
+            conversation_id,
+            sender_id,
+            body
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+
+        // Senders have read everything up to their own message
+        sqlx::query!(
+            r#"
+            UPDATE conversations
+            SET user_last_read_at = CASE WHEN user_id = $2 THEN $3 ELSE user_last_read_at END,
+                other_user_last_read_at = CASE WHEN other_user_id = $2 THEN $3 ELSE other_user_last_read_at END
+            WHERE id = $1
+            "#,
+            conversation_id,
+            sender_id,
+            message.created_at
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+
+        Ok(Some(message))
+    }
+
+    /// Messages between the two users, newest first and continuing after `(created_at, id)`.
+    pub async fn find_messages(
+        &self,
+        user_id: Uuid,
+        other_id: Uuid,
+        after: Option<(DateTime<Utc>, Uuid)>,
+        limit: i64,
+    ) -> Result<Vec<Message>> {
+        let (after_created_at, after_id) = after.unzip();
+        let messages = sqlx::query_as!(
+            Message,
+            r#"
+            SELECT m.id, m.conversation_id, m.sender_id, m.body, m.created_at
+            FROM messages m
+            JOIN conversations c ON c.id = m.conversation_id
+            WHERE c.user_id = LEAST($1::uuid, $2::uuid) AND c.other_user_id = GREATEST($1::uuid, $2::uuid)
+            AND ($3::timestamptz IS NULL OR (m.created_at, m.id) < ($3, $4))
+            ORDER BY m.created_at DESC, m.id DESC
+            LIMIT $5
+            "#,
+            user_id,
+            other_id,
+            after_created_at,
+            after_id,
+            limit
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(messages)
+    }
+
+    /// Where a message of one of the user's conversations falls in the order messages are
+    /// sent in.
+    pub async fn find_message_position(
+        &self,
+        user_id: Uuid,
+        id: Uuid,
+    ) -> Result<Option<(DateTime<Utc>, Uuid)>> {
+        let position = sqlx::query!(
+            r#"
+            SELECT m.created_at, m.id
+            FROM messages m
+            JOIN conversations c ON c.id = m.conversation_id
+            WHERE m.id = $2 AND $1 IN (c.user_id, c.other_user_id)
+            "#,
+            user_id,
+            id
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(position.map(|row| (row.created_at, row.id)))
+    }
+
+    /// The newest messages sent after `after` in any conversation of the user, newest first.
+    /// Conversations with users blocked in either direction are left out.
+    pub async fn find_latest_after(
+        &self,
+        user_id: Uuid,
+        after: (DateTime<Utc>, Uuid),
+        limit: i64,
+    ) -> Result<Vec<Message>> {
+        let messages = sqlx::query_as!(
+            Message,
+            r#"
+            SELECT m.id, m.conversation_id, m.sender_id, m.body, m.created_at
+            FROM messages m
+            JOIN conversations c ON c.id = m.conversation_id
+            WHERE $1 IN (c.user_id, c.other_user_id)
+            AND NOT is_blocked_between(c.user_id, c.other_user_id)
+            AND (m.created_at, m.id) > ($2, $3)
+            ORDER BY m.created_at DESC, m.id DESC
+            LIMIT $4
+            "#,
+            user_id,
+            after.0,
+            after.1,
+            limit
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(messages)
+    }
+
+    /// Marks messages from `other_id` up to `read_at` as read by `user_id`.
+    pub async fn mark_read(
+        &self,
+        user_id: Uuid,
+        other_id: Uuid,
+        read_at: DateTime<Utc>,
+    ) -> Result<()> {
+        sqlx::query!(
+            r#"
+            UPDATE conversations
+            SET user_last_read_at = CASE
+                    WHEN user_id = $1 THEN GREATEST(user_last_read_at, $3) ELSE user_last_read_at END,
+                other_user_last_read_at = CASE
+                    WHEN other_user_id = $1 THEN GREATEST(other_user_last_read_at, $3) ELSE other_user_last_read_at END
+            WHERE user_id = LEAST($1::uuid, $2::uuid) AND other_user_id = GREATEST($1::uuid, $2::uuid)
+            "#,
+            user_id,
+            other_id,
+            read_at
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Conversations of a user with the last message and how many messages they have not
+    /// read, most recently active first and continuing after `(last_message_at, id)`.
+    /// Conversations with deleted users or users blocked in either direction are left out.
+    pub async fn find_conversations(
+        &self,
+        user_id: Uuid,
+        after: Option<(DateTime<Utc>, Uuid)>,
+        limit: i64,
+    ) -> Result<Vec<Conversation>> {
+        let (after_last_message_at, after_id) = after.unzip();
+        let rows = sqlx::query!(
+            r#"
+            SELECT c.id, o.id as other_user_id, o.username as other_username, c.last_message_at,
+                   last.sender_id as last_sender_id, last.body as last_body,
+                   last.created_at as last_created_at,
+                   (
+                       SELECT COUNT(*)
+                       FROM messages m
+                       WHERE m.conversation_id = c.id
+                       AND m.sender_id <> $1
+                       AND m.created_at > COALESCE(
+                           CASE WHEN c.user_id = $1 THEN c.user_last_read_at ELSE c.other_user_last_read_at END,
+                           '-infinity'
+                       )
+                   ) as "unread_count!"
+            FROM conversations c
+            JOIN users o ON o.id = CASE WHEN c.user_id = $1 THEN c.other_user_id ELSE c.user_id END
+            JOIN LATERAL (
+                SELECT sender_id, body, created_at
+                FROM messages
+                WHERE conversation_id = c.id
+                ORDER BY created_at DESC, id DESC
+                LIMIT 1
+            ) last ON TRUE
+            WHERE (c.user_id = $1 OR c.other_user_id = $1)
+            AND o.deleted_at IS NULL
+            AND NOT is_blocked_between($1, o.id)
+            AND ($2::timestamptz IS NULL OR (c.last_message_at, c.id) < ($2, $3))
+            ORDER BY c.last_message_at DESC, c.id DESC
+            LIMIT $4
+            "#,
+            user_id,
+            after_last_message_at,
+            after_id,
+            limit
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| Conversation {
+                id: row.id,
+                other_user_id: row.other_user_id,
+                other_username: row.other_username,
+                last_message: MessagePreview {
+                    sender_id: row.last_sender_id,
+                    body: preview(&row.last_body),
+                    created_at: row.last_created_at,
+                },
+                unread_count: row.unread_count,
+                last_message_at: row.last_message_at,
+            })
+            .collect())
+    }
+}
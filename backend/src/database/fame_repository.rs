@@ -0,0 +1,312 @@
+use crate::enums::{FameEventKind, FameTrigger};
+use crate::services::fame::{decay, FameBreakdown, ProfileCompleteness, HALF_LIFE_DAYS};
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sqlx::{PgPool, Postgres, Transaction};
+use uuid::Uuid;
+
+/// A stored rating and how it came about.
+#[derive(Debug, Clone, Serialize)]
+pub struct FameHistoryEntry {
+    pub id: Uuid,
+    pub rating: f64,
+    pub breakdown: FameBreakdown,
+    pub trigger: FameTrigger,
+    pub event_kind: Option<FameEventKind>,
+    pub computed_at: DateTime<Utc>,
+}
+
+#[derive(Debug)]
+pub struct FameRepository {
+    pool: PgPool,
+}
+
+impl FameRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// Records an event and counts it into the running total of its kind.
+    pub async fn insert_event(
+        &self,
+        user_id: Uuid,
+        actor_id: Option<Uuid>,
+        kind: FameEventKind,
+    ) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+
+        sqlx::query!(
+            r#"
+            INSERT INTO fame_events (user_id, actor_id, kind)
+            VALUES ($1, $2, $3::text::fame_event_kind)
+            "#,
+            user_id,
+            actor_id,
+            kind.to_string()
+        )
+        .execute(&mut *tx)
+        .await?;
+        add_to_total(&mut tx, user_id, kind, 1.0).await?;
+
+        tx.commit().await?;
+
+        Ok(())
+    }
+
+    /// Removes the events of `kind` caused by `actor_id` and takes their current weight off the
+    /// running total, returning how many there were.
+    pub async fn delete_events(
+        &self,
+        user_id: Uuid,
+        actor_id: Uuid,
+        kind: FameEventKind,
+    ) -> Result<u64> {
+        let mut tx = self.pool.begin().await?;
+
+        let ages = sqlx::query_scalar!(
+            r#"
+            DELETE FROM fame_events
+            WHERE user_id = $1 AND actor_id = $2 AND kind = $3::text::fame_event_kind
+            RETURNING (EXTRACT(EPOCH FROM NOW() - created_at) / 86400)::float8 as "age_days!"
+            "#,
+            user_id,
+            actor_id,
+            kind.to_string()
+        )
+        .fetch_all(&mut *tx)
+        .await?;
+        if !ages.is_empty() {
+            let weight: f64 = ages.iter().map(|&age_days| decay(age_days)).sum();
+            add_to_total(&mut tx, user_id, kind, -weight).await?;
+        }
+
+        tx.commit().await?;
+
+        Ok(ages.len() as u64)
+    }
+
+    /// Running total of each kind of event counting towards the fame of `user_id`, decayed to now.
+    pub async fn find_totals(&self, user_id: Uuid) -> Result<Vec<(FameEventKind, f64)>> {
+        let rows = sqlx::query!(
+            r#"
+            SELECT kind::text as "kind!",
+                   (total * POWER(0.5, GREATEST(EXTRACT(EPOCH FROM NOW() - as_of), 0)
+                                       / 86400 / $2::float8))::float8 as "total!"
+            FROM fame_totals
+            WHERE user_id = $1
+            "#,
+            user_id,
+            HALF_LIFE_DAYS
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.into_iter()
+            .map(|row| {
+                let kind = row
+                    .kind
+                    .parse::<FameEventKind>()
+                    .map_err(|_| anyhow::anyhow!("Invalid fame event kind: {}", row.kind))?;
+                Ok((kind, row.total))
+            })
+            .collect()
+    }
+
+    pub async fn find_completeness(&self, user_id: Uuid) -> Result<ProfileCompleteness> {
+        let row = sqlx::query!(
+            r#"
+            SELECT
+                p.gender IS NOT NULL as "gender!",
+                p.sexual_preference IS NOT NULL as "sexual_preference!",
+                COALESCE(BTRIM(p.biography), '') <> '' as "biography!",
+                p.birth_date IS NOT NULL as "birth_date!",
+                EXISTS (
+                    SELECT 1 FROM user_pictures
+                    WHERE user_id = u.id AND status = 'ready'
+                ) as "pictures!",
+                EXISTS (
+                    SELECT 1 FROM user_pictures
+                    WHERE user_id = u.id AND status = 'ready' AND is_profile_picture
+                ) as "profile_picture!",
+                EXISTS (SELECT 1 FROM user_tags WHERE user_id = u.id) as "tags!"
+            FROM users u
+            LEFT JOIN user_profiles p ON p.user_id = u.id
+            WHERE u.id = $1
+            "#,
+            user_id
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(row
+            .map(|row| ProfileCompleteness {
+                gender: row.gender,
+                sexual_preference: row.sexual_preference,
+                biography: row.biography,
+                birth_date: row.birth_date,
+                pictures: row.pictures,
+                profile_picture: row.profile_picture,
+                tags: row.tags,
+            })
+            .unwrap_or_default())
+    }
+
+    /// The rating currently shown on the profile, if it has one.
+    pub async fn find_rating(&self, user_id: Uuid) -> Result<Option<f64>> {
+        let rating = sqlx::query_scalar!(
+            "SELECT fame_rating FROM user_profiles WHERE user_id = $1",
+            user_id
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(rating)
+    }
+
+    /// Shows `breakdown.rating` on the profile and appends it to the history.
+    pub async fn save(
+        &self,
+        user_id: Uuid,
+        breakdown: &FameBreakdown,
+        trigger: FameTrigger,
+        event_kind: Option<FameEventKind>,
+    ) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+
+        sqlx::query!(
+            r#"
+            INSERT INTO user_profiles (user_id, fame_rating)
+            VALUES ($1, $2)
+            ON CONFLICT (user_id) DO UPDATE SET fame_rating = EXCLUDED.fame_rating
+            "#,
+            user_id,
+            breakdown.rating
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        sqlx::query!(
+            r#"
+            INSERT INTO fame_history (user_id, rating, breakdown, trigger, event_kind)
+            VALUES ($1, $2, $3, $4::text::fame_trigger, $5::text::fame_event_kind)
+            "#,
+            user_id,
+            breakdown.rating,
+            serde_json::to_value(breakdown)?,
+            trigger.to_string(),
+            event_kind.map(|kind| kind.to_string())
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+
+        Ok(())
+    }
+
+    /// Newest entries first, continuing after `(computed_at, id)` of the last one seen.
+    pub async fn find_history(
+        &self,
+        user_id: Uuid,
+        after: Option<(DateTime<Utc>, Uuid)>,
+        limit: i64,
+    ) -> Result<Vec<FameHistoryEntry>> {
+        let (after_time, after_id) = after.unzip();
+        let rows = sqlx::query!(
+            r#"
+            SELECT id, rating, breakdown, trigger::text as "trigger!",
+                   event_kind::text as "event_kind", computed_at
+            FROM fame_history
+            WHERE user_id = $1
+              AND ($2::timestamptz IS NULL OR (computed_at, id) < ($2, $3))
+            ORDER BY computed_at DESC, id DESC
+            LIMIT $4
+            "#,
+            user_id,
+            after_time,
+            after_id,
+            limit
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.into_iter()
+            .map(|row| {
+                let trigger = row
+                    .trigger
+                    .parse::<FameTrigger>()
+                    .map_err(|_| anyhow::anyhow!("Invalid fame trigger: {}", row.trigger))?;
+                let event_kind = row
+                    .event_kind
+                    .map(|kind| {
+                        kind.parse::<FameEventKind>()
+                            .map_err(|_| anyhow::anyhow!("Invalid fame event kind: {}", kind))
+                    })
+                    .transpose()?;
+
+                Ok(FameHistoryEntry {
+                    id: row.id,
+                    rating: row.rating,
+                    breakdown: serde_json::from_value(row.breakdown)?,
+                    trigger,
+                    event_kind,
+                    computed_at: row.computed_at,
+                })
+            })
+            .collect()
+    }
+
+    /// Ids of active users in id order, for walking through all of them in batches.
+    pub async fn find_user_ids(&self, after: Option<Uuid>, limit: i64) -> Result<Vec<Uuid>> {
+        let ids = sqlx::query_scalar!(
+            r#"
+            SELECT id FROM users
+            WHERE ($1::uuid IS NULL OR id > $1)
+              AND account_status = 'active'
+              AND deleted_at IS NULL
+            ORDER BY id
+            LIMIT $2
+            "#,
+            after,
+            limit
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(ids)
+    }
+}
+
+/// Decays the running total of `kind` to now and adds `weight` to it. Rounding can leave a
+/// total a hair below zero after events are taken off, so it is clamped.
+async fn add_to_total(
+    tx: &mut Transaction<'_, Postgres>,
+    user_id: Uuid,
+    kind: FameEventKind,
+    weight: f64,
+) -> Result<()> {
+    sqlx::query!(
+        r#"
+        INSERT INTO fame_totals (user_id, kind, total, as_of)
+        VALUES ($1, $2::text::fame_event_kind, GREATEST($3::float8, 0), NOW())
+        ON CONFLICT (user_id, kind) DO UPDATE SET
+            total = GREATEST(
+                fame_totals.total
+                    * POWER(0.5, GREATEST(EXTRACT(EPOCH FROM NOW() - fame_totals.as_of), 0)
+                                 / 86400 / $4::float8)::float8
+                    + $3,
+                0
+            ),
+            as_of = NOW()
+        "#,
+        user_id,
+        kind.to_string(),
+        weight,
+        HALF_LIFE_DAYS
+    )
+    .execute(&mut **tx)
+    .await?;
+
+    Ok(())
+}
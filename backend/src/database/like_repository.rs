@@ -0,0 +1,230 @@
+use crate::models::{ConnectionStatus, ReceivedLike};
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use sqlx::{PgPool, Postgres, Transaction};
+use uuid::Uuid;
+
+/// What liking a user changed.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LikeOutcome {
+    /// One of the two users blocked the other.
+    Blocked,
+    AlreadyLiked,
+    Liked,
+    /// The like was returned, so the two users are now connected.
+    Connected,
+}
+
+/// What taking back a like changed.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum UnlikeOutcome {
+    NotLiked,
+    Unliked,
+    /// The two users liked each other, so their connection is gone too.
+    Disconnected,
+}
+
+#[derive(Debug)]
+pub struct LikeRepository {
+    pool: PgPool,
+}
+
+impl LikeRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// Likes `liked_id`, connecting the two users if the like is mutual.
+    pub async fn like(&self, liker_id: Uuid, liked_id: Uuid) -> Result<LikeOutcome> {
+        let mut tx = self.pool.begin().await?;
+        lock_pair(&mut tx, liker_id, liked_id).await?;
+
+        // Checked again under the lock, as a block may have landed since the handler looked
+        let blocked = sqlx::query_scalar!(
+            r#"SELECT is_blocked_between($1, $2) as "blocked!""#,
+            liker_id,
+            liked_id
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+        if blocked {
+            return Ok(LikeOutcome::Blocked);
+        }
+
+        let inserted = sqlx::query!(
+            r#"
+            INSERT INTO likes (liker_id, liked_id)
+            VALUES ($1, $2)
+            ON CONFLICT DO NOTHING
+            "#,
+            liker_id,
+            liked_id
+        )
+        .execute(&mut *tx)
+        .await?
+        .rows_affected()
+            > 0;
+        if !inserted {
+            return Ok(LikeOutcome::AlreadyLiked);
+        }
+
+        let connected = sqlx::query!(
+            r#"
+            INSERT INTO connections (user_id, other_user_id)
+            SELECT LEAST($1::uuid, $2::uuid), GREATEST($1::uuid, $2::uuid)
+            WHERE EXISTS (SELECT 1 FROM likes WHERE liker_id = $2 AND liked_id = $1)
+                AND NOT is_blocked_between($1, $2)
+            ON CONFLICT DO NOTHING
+            "#,
+            liker_id,
+            liked_id
+        )
+        .execute(&mut *tx)
+        .await?
+        .rows_affected()
+            > 0;
+
+        tx.commit().await?;
+
+        Ok(if connected {
+            LikeOutcome::Connected
+        } else {
+            LikeOutcome::Liked
+        })
+    }
+
+    /// Takes back a like, ending the connection between the two users if there was one.
+    pub async fn unlike(&self, liker_id: Uuid, liked_id: Uuid) -> Result<UnlikeOutcome> {
+        let mut tx = self.pool.begin().await?;
+        lock_pair(&mut tx, liker_id, liked_id).await?;
+
+        let deleted = sqlx::query!(
+            "DELETE FROM likes WHERE liker_id = $1 AND liked_id = $2",
+            liker_id,
+            liked_id
+        )
+        .execute(&mut *tx)
+        .await?
+        .rows_affected()
+            > 0;
+        if !deleted {
+            return Ok(UnlikeOutcome::NotLiked);
+        }
+
+        let disconnected = sqlx::query!(
+            r#"
+            DELETE FROM connections
+            WHERE user_id = LEAST($1::uuid, $2::uuid) AND other_user_id = GREATEST($1::uuid, $2::uuid)
+            "#,
+            liker_id,
+            liked_id
+        )
+        .execute(&mut *tx)
+        .await?
+        .rows_affected()
+            > 0;
+
+        tx.commit().await?;
+
+        Ok(if disconnected {
+            UnlikeOutcome::Disconnected
+        } else {
+            UnlikeOutcome::Unliked
+        })
+    }
+
+    /// Whether `viewer_id` likes `other_id` and the other way round.
+    pub async fn find_status(&self, viewer_id: Uuid, other_id: Uuid) -> Result<ConnectionStatus> {
+        let row = sqlx::query!(
+            r#"
+            SELECT
+                EXISTS (SELECT 1 FROM likes WHERE liker_id = $1 AND liked_id = $2) as "liked!",
+                EXISTS (SELECT 1 FROM likes WHERE liker_id = $2 AND liked_id = $1) as "liked_me!"
+            "#,
+            viewer_id,
+            other_id
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(ConnectionStatus::new(row.liked, row.liked_me))
+    }
+
+    /// Users who like `user_id` and are liked back.
+    pub async fn find_connected_ids(&self, user_id: Uuid) -> Result<Vec<Uuid>> {
+        let ids = sqlx::query_scalar!(
+            r#"
+            SELECT CASE WHEN user_id = $1 THEN other_user_id ELSE user_id END as "id!"
+            FROM connections
+            WHERE user_id = $1 OR other_user_id = $1
+            "#,
+            user_id
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(ids)
+    }
+
+    /// Likes `user_id` received, newest first, continuing after `(created_at, liker_id)`.
+    /// Likers who are blocked in either direction or no longer active are left out.
+    pub async fn find_likers(
+        &self,
+        user_id: Uuid,
+        after: Option<(DateTime<Utc>, Uuid)>,
+        limit: i64,
+    ) -> Result<Vec<ReceivedLike>> {
+        let (after_created_at, after_liker_id) = after.unzip();
+        let rows = sqlx::query!(
+            r#"
+            SELECT l.liker_id, u.username, l.created_at
+            FROM likes l
+            JOIN users u ON u.id = l.liker_id
+            WHERE l.liked_id = $1
+            AND u.account_status = 'active'
+            AND u.deleted_at IS NULL
+            AND NOT is_blocked_between($1, l.liker_id)
+            AND ($2::timestamptz IS NULL OR (l.created_at, l.liker_id) < ($2, $3))
+            ORDER BY l.created_at DESC, l.liker_id DESC
+            LIMIT $4
+            "#,
+            user_id,
+            after_created_at,
+            after_liker_id,
+            limit
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| ReceivedLike {
+                liker_id: row.liker_id,
+                liker_username: row.username,
+                liked_at: row.created_at,
+            })
+            .collect())
+    }
+}
+
+/// Serializes changes to the likes between two users, so two likes crossing each other
+/// still see each other and connect, and a block cannot race a like.
+pub(crate) async fn lock_pair(
+    tx: &mut Transaction<'_, Postgres>,
+    user_id: Uuid,
+    other_id: Uuid,
+) -> Result<()> {
+    sqlx::query!(
+        r#"
+        SELECT pg_advisory_xact_lock(
+            hashtextextended(LEAST($1::uuid, $2::uuid)::text || GREATEST($1::uuid, $2::uuid)::text, 0)
+        )
+        "#,
+        user_id,
+        other_id
+    )
+    .execute(&mut **tx)
+    .await?;
+
+    Ok(())
+}
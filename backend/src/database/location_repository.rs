@@ -0,0 +1,137 @@
+use crate::enums::LocationSource;
+use crate::models::{Coordinates, PublicLocation, UserLocation};
+use anyhow::Result;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+#[derive(Debug)]
+pub struct LocationRepository {
+    pool: PgPool,
+}
+
+impl LocationRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// Replaces the user's location from `source`, leaving the other sources untouched.
+    pub async fn upsert(
+        &self,
+        user_id: Uuid,
+        source: LocationSource,
+        coordinates: Coordinates,
+        accuracy_m: Option<f64>,
+    ) -> Result<()> {
+        sqlx::query!(
+            r#"
+            INSERT INTO user_locations (user_id, source, latitude, longitude, accuracy_m)
+            VALUES ($1, $2::text::location_source, $3, $4, $5)
+            ON CONFLICT (user_id, source) DO UPDATE
+            SET latitude = EXCLUDED.latitude, longitude = EXCLUDED.longitude,
+                accuracy_m = EXCLUDED.accuracy_m
+            "#,
+            user_id,
+            source.to_string(),
+            coordinates.latitude,
+            coordinates.longitude,
+            accuracy_m
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Returns `false` if the user had no location from `source`.
+    pub async fn delete(&self, user_id: Uuid, source: LocationSource) -> Result<bool> {
+        let result = sqlx::query!(
+            r#"
+            DELETE FROM user_locations
+            WHERE user_id = $1 AND source = $2::text::location_source
+            "#,
+            user_id,
+            source.to_string()
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// The location from the most trusted source the user has.
+    pub async fn find_effective(&self, user_id: Uuid) -> Result<Option<UserLocation>> {
+        let row = sqlx::query!(
+            r#"
+            SELECT latitude, longitude, accuracy_m, source::text as "source!", updated_at
+            FROM user_locations
+            WHERE user_id = $1
+            ORDER BY source DESC
+            LIMIT 1
+            "#,
+            user_id
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        match row {
+            Some(row) => {
+                let location = UserLocation::from_row(
+                    row.latitude,
+                    row.longitude,
+                    row.accuracy_m,
+                    row.source,
+                    row.updated_at,
+                )?;
+                Ok(Some(location))
+            }
+            None => Ok(None),
+        }
+    }
+
+    pub async fn find_public(&self, user_id: Uuid) -> Result<Option<PublicLocation>> {
+        let location = sqlx::query_as!(
+            PublicLocation,
+            r#"
+            SELECT latitude, longitude, neighborhood, city, country_code
+            FROM user_public_locations
+            WHERE user_id = $1
+            "#,
+            user_id
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(location)
+    }
+
+    pub async fn upsert_public(&self, user_id: Uuid, location: &PublicLocation) -> Result<()> {
+        sqlx::query!(
+            r#"
+            INSERT INTO user_public_locations (user_id, latitude, longitude, neighborhood, city, country_code)
+            VALUES ($1, $2, $3, $4, $5, $6)
+            ON CONFLICT (user_id) DO UPDATE
+            SET latitude = EXCLUDED.latitude, longitude = EXCLUDED.longitude,
+                neighborhood = EXCLUDED.neighborhood, city = EXCLUDED.city,
+                country_code = EXCLUDED.country_code
+            "#,
+            user_id,
+            location.latitude,
+            location.longitude,
+            location.neighborhood,
+            location.city,
+            location.country_code
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn delete_public(&self, user_id: Uuid) -> Result<()> {
+        sqlx::query!("DELETE FROM user_public_locations WHERE user_id = $1", user_id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+}
@@ -1,8 +1,37 @@
 use sqlx::PgPool;
 
+pub mod block_repository;
+pub mod chat_repository;
+pub mod fame_repository;
+pub mod like_repository;
+pub mod location_repository;
+pub mod moderation_repository;
+pub mod notification_repository;
+pub mod picture_hash_repository;
+pub mod picture_repository;
+pub mod presence_repository;
+pub mod profile_repository;
+pub mod query_builder;
+pub mod report_repository;
+pub mod role_repository;
 pub mod user_repository;
+pub mod visit_repository;
 
+pub use block_repository::BlockRepository;
+pub use chat_repository::ChatRepository;
+pub use fame_repository::FameRepository;
+pub use like_repository::LikeRepository;
+pub use location_repository::LocationRepository;
+pub use moderation_repository::ModerationRepository;
+pub use notification_repository::NotificationRepository;
+pub use picture_hash_repository::PictureHashRepository;
+pub use picture_repository::PictureRepository;
+pub use presence_repository::PresenceRepository;
+pub use profile_repository::ProfileRepository;
+pub use report_repository::ReportRepository;
+pub use role_repository::RoleRepository;
 pub use user_repository::UserRepository;
+pub use visit_repository::VisitRepository;
 
 pub async fn create_pool(database_url: &str) -> anyhow::Result<PgPool> {
     let pool = PgPool::connect(database_url).await?;
@@ -0,0 +1,563 @@
+use crate::enums::{
+    AccountStatus, ModerationActionKind, ModerationItemKind, ModerationStatus, ReportReason, Role,
+};
+use crate::models::{ModerationAction, ModerationItem, NewModerationAction, Report};
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use sqlx::{PgPool, Postgres, Transaction};
+use uuid::Uuid;
+
+/// Reason recorded when a suspension is lifted because it ended.
+const SUSPENSION_ENDED: &str = "Suspension ended";
+
+/// What changing an account status did.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum StatusChange {
+    Changed,
+    /// There is no such account, or it is deleted.
+    NotFound,
+    /// The account's status changed since the caller looked, to one the change does not apply to.
+    Conflict,
+}
+
+#[derive(Debug)]
+pub struct ModerationRepository {
+    pool: PgPool,
+}
+
+impl ModerationRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// Opens a queue item about `subject_user_id`, returning its id.
+    pub async fn enqueue(
+        &self,
+        subject_user_id: Uuid,
+        kind: ModerationItemKind,
+        details: &serde_json::Value,
+    ) -> Result<Uuid> {
+        let id = sqlx::query_scalar!(
+            r#"
+            INSERT INTO moderation_queue (subject_user_id, kind, details)
+            VALUES ($1, $2::text::moderation_item_kind, $3)
+            RETURNING id
+            "#,
+            subject_user_id,
+            kind.to_string(),
+            details
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(id)
+    }
+
+    /// Notes on the item that its subject was suspended without waiting for a moderator.
+    pub async fn mark_auto_suspended(&self, id: Uuid) -> Result<()> {
+        sqlx::query!(
+            r#"
+            UPDATE moderation_queue
+            SET details = details || jsonb_build_object('auto_suspended_at', NOW())
+            WHERE id = $1
+            "#,
+            id
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Newest first, continuing after `(created_at, id)`.
+    pub async fn find_items(
+        &self,
+        status: Option<ModerationStatus>,
+        kind: Option<ModerationItemKind>,
+        after: Option<(DateTime<Utc>, Uuid)>,
+        limit: i64,
+    ) -> Result<Vec<ModerationItem>> {
+        let (after_created_at, after_id) = after.unzip();
+        let rows = sqlx::query!(
+            r#"
+            SELECT q.id, q.subject_user_id, u.username, q.kind::text as "kind!",
+                   q.status::text as "status!", q.details, q.created_at, q.updated_at,
+                   (SELECT COUNT(*) FROM reports WHERE moderation_item_id = q.id) as "report_count!"
+            FROM moderation_queue q
+            JOIN users u ON u.id = q.subject_user_id
+            WHERE ($1::text IS NULL OR q.status = $1::text::moderation_status)
+            AND ($2::text IS NULL OR q.kind = $2::text::moderation_item_kind)
+            AND ($3::timestamptz IS NULL OR (q.created_at, q.id) < ($3, $4))
+            ORDER BY q.created_at DESC, q.id DESC
+            LIMIT $5
+            "#,
+            status.map(|status| status.to_string()),
+            kind.map(|kind| kind.to_string()),
+            after_created_at,
+            after_id,
+            limit
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.into_iter()
+            .map(|row| {
+                Ok(ModerationItem {
+                    id: row.id,
+                    subject_user_id: row.subject_user_id,
+                    subject_username: row.username,
+                    kind: parse_item_kind(&row.kind)?,
+                    status: parse_status(&row.status)?,
+                    details: row.details,
+                    report_count: row.report_count,
+                    created_at: row.created_at,
+                    updated_at: row.updated_at,
+                })
+            })
+            .collect()
+    }
+
+    pub async fn find_item(&self, id: Uuid) -> Result<Option<ModerationItem>> {
+        let row = sqlx::query!(
+            r#"
+            SELECT q.id, q.subject_user_id, u.username, q.kind::text as "kind!",
+                   q.status::text as "status!", q.details, q.created_at, q.updated_at,
+                   (SELECT COUNT(*) FROM reports WHERE moderation_item_id = q.id) as "report_count!"
+            FROM moderation_queue q
+            JOIN users u ON u.id = q.subject_user_id
+            WHERE q.id = $1
+            "#,
+            id
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        row.map(|row| {
+            Ok(ModerationItem {
+                id: row.id,
+                subject_user_id: row.subject_user_id,
+                subject_username: row.username,
+                kind: parse_item_kind(&row.kind)?,
+                status: parse_status(&row.status)?,
+                details: row.details,
+                report_count: row.report_count,
+                created_at: row.created_at,
+                updated_at: row.updated_at,
+            })
+        })
+        .transpose()
+    }
+
+    /// Reports reviewed by the item, oldest first.
+    pub async fn find_reports(&self, moderation_item_id: Uuid) -> Result<Vec<Report>> {
+        let rows = sqlx::query!(
+            r#"
+            SELECT r.id, r.reporter_id, u.username as "reporter_username?", r.matched_user_id,
+                   m.username as "matched_username?", r.reason::text as "reason!", r.details,
+                   r.created_at
+            FROM reports r
+            LEFT JOIN users u ON u.id = r.reporter_id
+            LEFT JOIN users m ON m.id = r.matched_user_id
+            WHERE r.moderation_item_id = $1
+            ORDER BY r.created_at, r.id
+            "#,
+            moderation_item_id
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.into_iter()
+            .map(|row| {
+                let reason = row
+                    .reason
+                    .parse::<ReportReason>()
+                    .map_err(|_| anyhow::anyhow!("Invalid report reason: {}", row.reason))?;
+                Ok(Report {
+                    id: row.id,
+                    reporter_id: row.reporter_id,
+                    reporter_username: row.reporter_username,
+                    matched_user_id: row.matched_user_id,
+                    matched_username: row.matched_username,
+                    reason,
+                    details: row.details,
+                    created_at: row.created_at,
+                })
+            })
+            .collect()
+    }
+
+    /// Sets the status of a queue item and records the review. Returns false if there is no
+    /// such item.
+    pub async fn review_item(
+        &self,
+        action: &NewModerationAction,
+        status: ModerationStatus,
+    ) -> Result<bool> {
+        let Some(item_id) = action.moderation_item_id else {
+            anyhow::bail!("A review needs a moderation item");
+        };
+        let mut tx = self.pool.begin().await?;
+
+        let updated = sqlx::query!(
+            r#"
+            UPDATE moderation_queue
+            SET status = $2::text::moderation_status
+            WHERE id = $1
+            "#,
+            item_id,
+            status.to_string()
+        )
+        .execute(&mut *tx)
+        .await?
+        .rows_affected()
+            > 0;
+        if !updated {
+            return Ok(false);
+        }
+
+        insert_action(&mut tx, action).await?;
+        tx.commit().await?;
+
+        Ok(true)
+    }
+
+    /// Sets the status of an account that is not deleted and whose status is one of `from`,
+    /// recording the change in its history and the audit log.
+    ///
+    /// Reports, moderators and ending suspensions all change statuses through this.
+    pub async fn change_account_status(
+        &self,
+        action: &NewModerationAction,
+        from: &[AccountStatus],
+        status: AccountStatus,
+    ) -> Result<StatusChange> {
+        let mut tx = self.pool.begin().await?;
+        let change = change_status(&mut tx, action, from, status).await?;
+        if change == StatusChange::Changed {
+            tx.commit().await?;
+        }
+
+        Ok(change)
+    }
+
+    /// Reinstates an account suspended automatically for the reports of `moderation_item_id`,
+    /// once a moderator dismissed them. Returns false if its status changed since, for example
+    /// because a moderator suspended it themselves.
+    pub async fn lift_automatic_suspension(
+        &self,
+        action: &NewModerationAction,
+        moderation_item_id: Uuid,
+    ) -> Result<bool> {
+        let mut tx = self.pool.begin().await?;
+
+        // Locked before the history is read, so a moderator changing the status meanwhile is seen
+        let suspended = sqlx::query_scalar!(
+            r#"
+            SELECT account_status = 'suspended' as "suspended!"
+            FROM users
+            WHERE id = $1 AND deleted_at IS NULL
+            FOR UPDATE
+            "#,
+            action.subject_user_id
+        )
+        .fetch_optional(&mut *tx)
+        .await?
+        .unwrap_or(false);
+        let automatic = suspended
+            && sqlx::query_scalar!(
+                r#"
+                SELECT changed_by IS NULL AND moderation_item_id IS NOT DISTINCT FROM $2
+                    as "automatic!"
+                FROM account_status_history
+                WHERE user_id = $1
+                ORDER BY created_at DESC, id DESC
+                LIMIT 1
+                "#,
+                action.subject_user_id,
+                moderation_item_id
+            )
+            .fetch_optional(&mut *tx)
+            .await?
+            .unwrap_or(false);
+        if !automatic {
+            return Ok(false);
+        }
+
+        change_status(&mut tx, action, &[AccountStatus::Suspended], AccountStatus::Active).await?;
+        tx.commit().await?;
+
+        Ok(true)
+    }
+
+    /// Suspended accounts whose suspension has ended.
+    pub async fn find_expired_suspensions(&self, limit: i64) -> Result<Vec<Uuid>> {
+        let ids = sqlx::query_scalar!(
+            r#"
+            SELECT u.id
+            FROM users u
+            JOIN LATERAL (
+                SELECT h.expires_at
+                FROM account_status_history h
+                WHERE h.user_id = u.id
+                ORDER BY h.created_at DESC, h.id DESC
+                LIMIT 1
+            ) latest ON TRUE
+            WHERE u.account_status = 'suspended'
+            AND u.deleted_at IS NULL
+            AND latest.expires_at <= NOW()
+            LIMIT $1
+            "#,
+            limit
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(ids)
+    }
+
+    /// Reinstates an account whose suspension has ended, recording it as done by nobody.
+    /// Returns false if the account is no longer suspended or its suspension was extended.
+    pub async fn lift_expired_suspension(&self, user_id: Uuid) -> Result<bool> {
+        let mut tx = self.pool.begin().await?;
+
+        // Locked before the history is read, so a moderator changing the status meanwhile is seen
+        let suspended = sqlx::query_scalar!(
+            r#"
+            SELECT account_status = 'suspended' as "suspended!"
+            FROM users
+            WHERE id = $1 AND deleted_at IS NULL
+            FOR UPDATE
+            "#,
+            user_id
+        )
+        .fetch_optional(&mut *tx)
+        .await?
+        .unwrap_or(false);
+        let expired = suspended
+            && sqlx::query_scalar!(
+                r#"
+                SELECT COALESCE(expires_at <= NOW(), false) as "expired!"
+                FROM account_status_history
+                WHERE user_id = $1
+                ORDER BY created_at DESC, id DESC
+                LIMIT 1
+                "#,
+                user_id
+            )
+            .fetch_optional(&mut *tx)
+            .await?
+            .unwrap_or(false);
+        if !expired {
+            return Ok(false);
+        }
+
+        let action = NewModerationAction::new(None, user_id, ModerationActionKind::Reinstate)
+            .reason(Some(SUSPENSION_ENDED.to_string()));
+        change_status(&mut tx, &action, &[AccountStatus::Suspended], AccountStatus::Active).await?;
+        tx.commit().await?;
+
+        Ok(true)
+    }
+
+    /// Undoes the soft deletion of an account. Returns false if it was not deleted.
+    pub async fn restore_account(&self, action: &NewModerationAction) -> Result<bool> {
+        let mut tx = self.pool.begin().await?;
+
+        let restored = sqlx::query!(
+            "UPDATE users SET deleted_at = NULL WHERE id = $1 AND deleted_at IS NOT NULL",
+            action.subject_user_id
+        )
+        .execute(&mut *tx)
+        .await?
+        .rows_affected()
+            > 0;
+        if !restored {
+            return Ok(false);
+        }
+
+        insert_action(&mut tx, action).await?;
+        tx.commit().await?;
+
+        Ok(true)
+    }
+
+    pub async fn change_role(&self, action: &NewModerationAction, role: Role) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+
+        sqlx::query!(
+            r#"
+            INSERT INTO user_roles (user_id, role, granted_by)
+            VALUES ($1, $2::text::user_role, $3)
+            ON CONFLICT (user_id) DO UPDATE
+            SET role = EXCLUDED.role, granted_by = EXCLUDED.granted_by, granted_at = NOW()
+            "#,
+            action.subject_user_id,
+            role.to_string(),
+            action.actor_id
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        insert_action(&mut tx, action).await?;
+        tx.commit().await?;
+
+        Ok(())
+    }
+
+    /// The audit log, newest first and continuing after `(created_at, id)`, optionally only
+    /// about one user or by one moderator.
+    pub async fn find_actions(
+        &self,
+        subject_user_id: Option<Uuid>,
+        actor_id: Option<Uuid>,
+        after: Option<(DateTime<Utc>, Uuid)>,
+        limit: i64,
+    ) -> Result<Vec<ModerationAction>> {
+        let (after_created_at, after_id) = after.unzip();
+        let rows = sqlx::query!(
+            r#"
+            SELECT a.id, a.kind::text as "kind!", a.actor_id, actor.username as "actor_username?",
+                   a.subject_user_id, subject.username as subject_username, a.reason,
+                   a.expires_at, a.moderation_item_id, a.details, a.created_at
+            FROM moderation_actions a
+            JOIN users subject ON subject.id = a.subject_user_id
+            LEFT JOIN users actor ON actor.id = a.actor_id
+            WHERE ($1::uuid IS NULL OR a.subject_user_id = $1)
+            AND ($2::uuid IS NULL OR a.actor_id = $2)
+            AND ($3::timestamptz IS NULL OR (a.created_at, a.id) < ($3, $4))
+            ORDER BY a.created_at DESC, a.id DESC
+            LIMIT $5
+            "#,
+            subject_user_id,
+            actor_id,
+            after_created_at,
+            after_id,
+            limit
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.into_iter()
+            .map(|row| {
+                let kind = row
+                    .kind
+                    .parse::<ModerationActionKind>()
+                    .map_err(|_| anyhow::anyhow!("Invalid moderation action: {}", row.kind))?;
+                Ok(ModerationAction {
+                    id: row.id,
+                    kind,
+                    actor_id: row.actor_id,
+                    actor_username: row.actor_username,
+                    subject_user_id: row.subject_user_id,
+                    subject_username: row.subject_username,
+                    reason: row.reason,
+                    expires_at: row.expires_at,
+                    moderation_item_id: row.moderation_item_id,
+                    details: row.details,
+                    created_at: row.created_at,
+                })
+            })
+            .collect()
+    }
+}
+
+/// Sets the status of the action's subject if it is not deleted and its status is one of
+/// `from`, appending the change to its history and the action to the audit log, in the
+/// caller's transaction.
+async fn change_status(
+    tx: &mut Transaction<'_, Postgres>,
+    action: &NewModerationAction,
+    from: &[AccountStatus],
+    status: AccountStatus,
+) -> Result<StatusChange> {
+    let user_id = action.subject_user_id;
+    let previous_status = sqlx::query_scalar!(
+        r#"
+        SELECT account_status::text as "account_status!"
+        FROM users
+        WHERE id = $1 AND deleted_at IS NULL
+        FOR UPDATE
+        "#,
+        user_id
+    )
+    .fetch_optional(&mut **tx)
+    .await?;
+    let Some(previous_status) = previous_status else {
+        return Ok(StatusChange::NotFound);
+    };
+    // Callers check the status before asking, but it may have changed before the lock was taken
+    if !from
+        .iter()
+        .any(|allowed| allowed.to_string() == previous_status)
+    {
+        return Ok(StatusChange::Conflict);
+    }
+
+    sqlx::query!(
+        "UPDATE users SET account_status = $2::text::account_status WHERE id = $1",
+        user_id,
+        status.to_string()
+    )
+    .execute(&mut **tx)
+    .await?;
+
+    let expires_at = action
+        .expires_at
+        .filter(|_| status == AccountStatus::Suspended);
+    sqlx::query!(
+        r#"
+        INSERT INTO account_status_history
+            (user_id, status, previous_status, changed_by, reason, expires_at, moderation_item_id)
+        VALUES ($1, $2::text::account_status, $3::text::account_status, $4, $5, $6, $7)
+        "#,
+        user_id,
+        status.to_string(),
+        previous_status,
+        action.actor_id,
+        action.reason,
+        expires_at,
+        action.moderation_item_id
+    )
+    .execute(&mut **tx)
+    .await?;
+
+    insert_action(tx, action).await?;
+
+    Ok(StatusChange::Changed)
+}
+
+/// Appends to the audit log in the transaction making the change it records.
+async fn insert_action(
+    tx: &mut Transaction<'_, Postgres>,
+    action: &NewModerationAction,
+) -> Result<()> {
+    sqlx::query!(
+        r#"
+        INSERT INTO moderation_actions
+            (actor_id, subject_user_id, kind, reason, expires_at, moderation_item_id, details)
+        VALUES ($1, $2, $3::text::moderation_action_kind, $4, $5, $6, $7)
+        "#,
+        action.actor_id,
+        action.subject_user_id,
+        action.kind.to_string(),
+        action.reason,
+        action.expires_at,
+        action.moderation_item_id,
+        action.details
+    )
+    .execute(&mut **tx)
+    .await?;
+
+    Ok(())
+}
+
+fn parse_item_kind(kind: &str) -> Result<ModerationItemKind> {
+    kind.parse()
+        .map_err(|_| anyhow::anyhow!("Invalid moderation item kind: {}", kind))
+}
+
+fn parse_status(status: &str) -> Result<ModerationStatus> {
+    status
+        .parse()
+        .map_err(|_| anyhow::anyhow!("Invalid moderation status: {}", status))
+}
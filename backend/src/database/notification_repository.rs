@@ -0,0 +1,207 @@
+use crate::enums::NotificationKind;
+use crate::models::Notification;
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+#[derive(Debug)]
+pub struct NotificationRepository {
+    pool: PgPool,
+}
+
+impl NotificationRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// Returns `None` without notifying when either user blocked the other.
+    pub async fn create(
+        &self,
+        user_id: Uuid,
+        actor_id: Option<Uuid>,
+        kind: NotificationKind,
+    ) -> Result<Option<Notification>> {
+        let row = sqlx::query!(
+            r#"
+            WITH created AS (
+                INSERT INTO notifications (user_id, actor_id, kind)
+                SELECT $1, $2, $3::text::notification_kind
+                WHERE NOT is_blocked_between($1, $2)
+                RETURNING id, kind, actor_id, read_at, created_at
+            )
+            SELECT c.id as "id!", c.kind::text as "kind!", c.actor_id,
+                   a.username as "actor_username?", c.read_at, c.created_at as "created_at!"
+            FROM created c
+            LEFT JOIN users a ON a.id = c.actor_id
+            "#,
+            user_id,
+            actor_id,
+            kind.to_string()
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        row.map(|row| {
+            Notification::from_row(
+                row.id,
+                row.kind,
+                row.actor_id,
+                row.actor_username,
+                row.read_at,
+                row.created_at,
+            )
+        })
+        .transpose()
+    }
+
+    /// Newest first, continuing after `(created_at, id)`. Notifications caused by users
+    /// blocked in either direction are left out.
+    pub async fn find_for_user(
+        &self,
+        user_id: Uuid,
+        after: Option<(DateTime<Utc>, Uuid)>,
+        limit: i64,
+    ) -> Result<Vec<Notification>> {
+        let (after_created_at, after_id) = after.unzip();
+        let rows = sqlx::query!(
+            r#"
+            SELECT n.id, n.kind::text as "kind!", n.actor_id, a.username as "actor_username?",
+                   n.read_at, n.created_at
+            FROM notifications n
+            LEFT JOIN users a ON a.id = n.actor_id
+            WHERE n.user_id = $1
+            AND NOT is_blocked_between($1, n.actor_id)
+            AND ($2::timestamptz IS NULL OR (n.created_at, n.id) < ($2, $3))
+            ORDER BY n.created_at DESC, n.id DESC
+            LIMIT $4
+            "#,
+            user_id,
+            after_created_at,
+            after_id,
+            limit
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.into_iter()
+            .map(|row| {
+                Notification::from_row(
+                    row.id,
+                    row.kind,
+                    row.actor_id,
+                    row.actor_username,
+                    row.read_at,
+                    row.created_at,
+                )
+            })
+            .collect()
+    }
+
+    /// Where a notification of the user falls in the order notifications are created in.
+    pub async fn find_position(
+        &self,
+        user_id: Uuid,
+        id: Uuid,
+    ) -> Result<Option<(DateTime<Utc>, Uuid)>> {
+        let position = sqlx::query!(
+            r#"
+            SELECT created_at, id
+            FROM notifications
+            WHERE id = $2 AND user_id = $1
+            "#,
+            user_id,
+            id
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(position.map(|row| (row.created_at, row.id)))
+    }
+
+    /// The newest notifications created after `after`, newest first.
+    pub async fn find_latest_after(
+        &self,
+        user_id: Uuid,
+        after: (DateTime<Utc>, Uuid),
+        limit: i64,
+    ) -> Result<Vec<Notification>> {
+        let rows = sqlx::query!(
+            r#"
+            SELECT n.id, n.kind::text as "kind!", n.actor_id, a.username as "actor_username?",
+                   n.read_at, n.created_at
+            FROM notifications n
+            LEFT JOIN users a ON a.id = n.actor_id
+            WHERE n.user_id = $1
+            AND NOT is_blocked_between($1, n.actor_id)
+            AND (n.created_at, n.id) > ($2, $3)
+            ORDER BY n.created_at DESC, n.id DESC
+            LIMIT $4
+            "#,
+            user_id,
+            after.0,
+            after.1,
+            limit
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.into_iter()
+            .map(|row| {
+                Notification::from_row(
+                    row.id,
+                    row.kind,
+                    row.actor_id,
+                    row.actor_username,
+                    row.read_at,
+                    row.created_at,
+                )
+            })
+            .collect()
+    }
+
+    pub async fn count_unread(&self, user_id: Uuid) -> Result<i64> {
+        let count = sqlx::query_scalar!(
+            r#"
+            SELECT COUNT(*) as "count!"
+            FROM notifications n
+            WHERE n.user_id = $1
+            AND n.read_at IS NULL
+            AND NOT is_blocked_between($1, n.actor_id)
+            "#,
+            user_id
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(count)
+    }
+
+    /// Returns how many notifications were unread.
+    pub async fn mark_all_read(&self, user_id: Uuid) -> Result<u64> {
+        let result = sqlx::query!(
+            "UPDATE notifications SET read_at = NOW() WHERE user_id = $1 AND read_at IS NULL",
+            user_id
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected())
+    }
+
+    /// Returns false when `user_id` has no such notification.
+    pub async fn mark_read(&self, user_id: Uuid, id: Uuid) -> Result<bool> {
+        let result = sqlx::query!(
+            r#"
+            UPDATE notifications SET read_at = COALESCE(read_at, NOW())
+            WHERE id = $1 AND user_id = $2
+            "#,
+            id,
+            user_id
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+}
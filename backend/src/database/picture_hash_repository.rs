@@ -0,0 +1,102 @@
+use anyhow::Result;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+#[derive(Debug)]
+pub struct PictureHashRepository {
+    pool: PgPool,
+}
+
+#[derive(Debug, Clone)]
+pub struct NearDuplicate {
+    pub picture_id: Uuid,
+    pub user_id: Uuid,
+    pub distance: i32,
+}
+
+impl PictureHashRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    pub async fn upsert_hash(
+        &self,
+        picture_id: Uuid,
+        user_id: Uuid,
+        dhash: u64,
+        bands: &[i32],
+    ) -> Result<()> {
+        sqlx::query!(
+            r#"
+            INSERT INTO picture_hashes (picture_id, user_id, dhash, bands)
+            VALUES ($1, $2, $3, $4)
+            ON CONFLICT (picture_id) DO UPDATE
+            SET dhash = EXCLUDED.dhash, bands = EXCLUDED.bands
+            "#,
+            picture_id,
+            user_id,
+            dhash as i64,
+            bands
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Other accounts' pictures whose hash is within `max_distance` bits, closest first.
+    ///
+    /// The band overlap narrows the search through the GIN index before distances are computed.
+    pub async fn find_near_duplicates(
+        &self,
+        user_id: Uuid,
+        dhash: u64,
+        bands: &[i32],
+        max_distance: u32,
+    ) -> Result<Vec<NearDuplicate>> {
+        let duplicates = sqlx::query_as!(
+            NearDuplicate,
+            r#"
+            SELECT picture_id, user_id, distance as "distance!"
+            FROM (
+                SELECT picture_id, user_id, bit_count((dhash # $2)::bit(64))::int AS distance
+                FROM picture_hashes
+                WHERE bands && $3 AND user_id != $1
+            ) candidates
+            WHERE distance <= $4
+            ORDER BY distance
+            "#,
+            user_id,
+            dhash as i64,
+            bands,
+            max_distance as i32
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(duplicates)
+    }
+
+    /// Records a match, returning `false` if it was already known.
+    pub async fn record_duplicate(
+        &self,
+        picture_id: Uuid,
+        matched_picture_id: Uuid,
+        distance: i32,
+    ) -> Result<bool> {
+        let result = sqlx::query!(
+            r#"
+            INSERT INTO picture_duplicates (picture_id, matched_picture_id, distance)
+            VALUES ($1, $2, $3)
+            ON CONFLICT DO NOTHING
+            "#,
+            picture_id,
+            matched_picture_id,
+            distance as i16
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+}
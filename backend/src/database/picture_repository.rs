@@ -0,0 +1,493 @@
+use crate::models::{Picture, PictureVariant, PictureVersion};
+use anyhow::Result;
+use sqlx::{PgPool, Postgres, Transaction};
+use uuid::Uuid;
+
+#[derive(Debug)]
+pub struct PictureRepository {
+    pool: PgPool,
+}
+
+pub struct NewPictureVariant {
+    pub variant: String,
+    pub format: String,
+    pub path: String,
+    pub width: i32,
+    pub height: i32,
+    pub byte_size: i32,
+}
+
+impl PictureRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// Adds a picture unless the user already has `max_pictures`, returning `None` then. The
+    /// first one becomes the profile picture. Uploads of the same user take turns, so
+    /// concurrent ones cannot go past the limit.
+    pub async fn create_picture(
+        &self,
+        user_id: Uuid,
+        original_path: &str,
+        max_pictures: i64,
+    ) -> Result<Option<Picture>> {
+        let mut tx = self.pool.begin().await?;
+        sqlx::query!(
+            "SELECT pg_advisory_xact_lock(hashtextextended('pictures:' || $1::uuid::text, 0))",
+            user_id
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        let count = sqlx::query_scalar!(
+            r#"
+            SELECT COUNT(*) FROM user_pictures
+            WHERE user_id = $1
+            "#,
+            user_id
+        )
+        .fetch_one(&mut *tx)
+        .await?
+        .unwrap_or(0);
+        if count >= max_pictures {
+            return Ok(None);
+        }
+
+        let row = sqlx::query!(
+            r#"
+            INSERT INTO user_pictures (user_id, original_path, is_profile_picture)
+            VALUES ($1, $2, $3)
+            RETURNING id, user_id, is_profile_picture, original_path, status::text as "status!", width, height, processed_at, created_at
+            "#,
+            user_id,
+            original_path,
+            count == 0
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+        tx.commit().await?;
+
+        Picture::from_row(
+            row.id,
+            row.user_id,
+            row.is_profile_picture,
+            row.original_path,
+            row.status,
+            row.width,
+            row.height,
+            row.processed_at,
+            row.created_at,
+        )
+        .map(Some)
+    }
+
+    pub async fn find_by_id(&self, id: Uuid) -> Result<Option<Picture>> {
+        let row = sqlx::query!(
+            r#"
+            SELECT id, user_id, is_profile_picture, original_path, status::text as "status!", width, height, processed_at, created_at
+            FROM user_pictures
+            WHERE id = $1
+            "#,
+            id
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        match row {
+            Some(row) => {
+                let picture = Picture::from_row(
+                    row.id,
+                    row.user_id,
+                    row.is_profile_picture,
+                    row.original_path,
+                    row.status,
+                    row.width,
+                    row.height,
+                    row.processed_at,
+                    row.created_at,
+                )?;
+                Ok(Some(picture))
+            }
+            None => Ok(None),
+        }
+    }
+
+    pub async fn find_by_user(&self, user_id: Uuid) -> Result<Vec<Picture>> {
+        let rows = sqlx::query!(
+            r#"
+            SELECT id, user_id, is_profile_picture, original_path, status::text as "status!", width, height, processed_at, created_at
+            FROM user_pictures
+            WHERE user_id = $1
+            ORDER BY is_profile_picture DESC, created_at
+            "#,
+            user_id
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        let mut pictures = Vec::new();
+        for row in rows {
+            let picture = Picture::from_row(
+                row.id,
+                row.user_id,
+                row.is_profile_picture,
+                row.original_path,
+                row.status,
+                row.width,
+                row.height,
+                row.processed_at,
+                row.created_at,
+            )?;
+            pictures.push(picture);
+        }
+
+        Ok(pictures)
+    }
+
+    pub async fn find_variants_by_user(&self, user_id: Uuid) -> Result<Vec<PictureVariant>> {
+        let variants = sqlx::query_as!(
+            PictureVariant,
+            r#"
+            SELECT v.picture_id, v.variant, v.format, v.path, v.width, v.height
+            FROM picture_variants v
+            JOIN user_pictures p ON p.id = v.picture_id
+            WHERE p.user_id = $1
+            "#,
+            user_id
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(variants)
+    }
+
+    pub async fn count_for_user(&self, user_id: Uuid) -> Result<i64> {
+        let count = sqlx::query_scalar!(
+            r#"
+            SELECT COUNT(*) FROM user_pictures
+            WHERE user_id = $1
+            "#,
+            user_id
+        )
+        .fetch_one(&self.pool)
+        .await?
+        .unwrap_or(0);
+
+        Ok(count)
+    }
+
+    /// Whether the user's profile picture finished processing and can be shown.
+    pub async fn has_profile_picture(&self, user_id: Uuid) -> Result<bool> {
+        let exists = sqlx::query_scalar!(
+            r#"
+            SELECT EXISTS (
+                SELECT 1 FROM user_pictures
+                WHERE user_id = $1 AND is_profile_picture AND status = 'ready'
+            ) as "exists!"
+            "#,
+            user_id
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(exists)
+    }
+
+    /// Pictures whose processing or re-rendering never finished, e.g. because the server restarted.
+    pub async fn find_unfinished(&self) -> Result<Vec<Picture>> {
+        let rows = sqlx::query!(
+            r#"
+            SELECT id, user_id, is_profile_picture, original_path, status::text as "status!", width, height, processed_at, created_at
+            FROM user_pictures p
+            WHERE status IN ('pending', 'processing')
+            OR EXISTS (
+                SELECT 1 FROM picture_versions v
+                WHERE v.picture_id = p.id AND v.rendered_at IS NULL
+            )
+            ORDER BY created_at
+            "#
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        let mut pictures = Vec::new();
+        for row in rows {
+            let picture = Picture::from_row(
+                row.id,
+                row.user_id,
+                row.is_profile_picture,
+                row.original_path,
+                row.status,
+                row.width,
+                row.height,
+                row.processed_at,
+                row.created_at,
+            )?;
+            pictures.push(picture);
+        }
+
+        Ok(pictures)
+    }
+
+    pub async fn mark_processing(&self, id: Uuid) -> Result<()> {
+        sqlx::query!(
+            r#"
+            UPDATE user_pictures
+            SET status = 'processing', processing_error = NULL
+            WHERE id = $1
+            "#,
+            id
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn mark_failed(&self, id: Uuid, error: &str) -> Result<()> {
+        sqlx::query!(
+            r#"
+            UPDATE user_pictures
+            SET status = 'failed', processing_error = $2
+            WHERE id = $1
+            "#,
+            id,
+            error
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Swaps the raw upload for the processed master and records its variants atomically.
+    pub async fn mark_ready(
+        &self,
+        id: Uuid,
+        original_path: &str,
+        width: i32,
+        height: i32,
+        variants: &[NewPictureVariant],
+    ) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+
+        sqlx::query!(
+            r#"
+            UPDATE user_pictures
+            SET status = 'ready', original_path = $2, width = $3, height = $4,
+                processing_error = NULL, processed_at = NOW()
+            WHERE id = $1
+            "#,
+            id,
+            original_path,
+            width,
+            height
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        Self::replace_variants_in(&mut tx, id, variants).await?;
+
+        tx.commit().await?;
+
+        Ok(())
+    }
+
+    /// Swaps in the renditions of a picture version, keeping the original untouched. Versions
+    /// saved before it and never rendered were replaced by it and are dropped.
+    pub async fn mark_rendered(
+        &self,
+        id: Uuid,
+        version: i32,
+        variants: &[NewPictureVariant],
+    ) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+
+        sqlx::query!(
+            r#"
+            UPDATE user_pictures
+            SET status = 'ready', processing_error = NULL
+            WHERE id = $1
+            "#,
+            id
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        sqlx::query!(
+            r#"
+            UPDATE picture_versions
+            SET rendered_at = NOW()
+            WHERE picture_id = $1 AND version = $2
+            "#,
+            id,
+            version
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        sqlx::query!(
+            r#"
+            DELETE FROM picture_versions
+            WHERE picture_id = $1 AND version < $2 AND rendered_at IS NULL
+            "#,
+            id,
+            version
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        Self::replace_variants_in(&mut tx, id, variants).await?;
+
+        tx.commit().await?;
+
+        Ok(())
+    }
+
+    /// Records a failed render. The version and the unrendered ones before it are dropped, so
+    /// the picture goes on showing, and building on, the last version that was rendered.
+    pub async fn mark_render_failed(&self, id: Uuid, version: i32, error: &str) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+
+        sqlx::query!(
+            r#"
+            UPDATE user_pictures
+            SET status = 'ready', processing_error = $2
+            WHERE id = $1
+            "#,
+            id,
+            error
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        sqlx::query!(
+            r#"
+            DELETE FROM picture_versions
+            WHERE picture_id = $1 AND version <= $2 AND rendered_at IS NULL
+            "#,
+            id,
+            version
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+
+        Ok(())
+    }
+
+    async fn replace_variants_in(
+        tx: &mut Transaction<'_, Postgres>,
+        id: Uuid,
+        variants: &[NewPictureVariant],
+    ) -> Result<()> {
+        sqlx::query!("DELETE FROM picture_variants WHERE picture_id = $1", id)
+            .execute(&mut **tx)
+            .await?;
+
+        for variant in variants {
+            sqlx::query!(
+                r#"
+                INSERT INTO picture_variants (picture_id, variant, format, path, width, height, byte_size)
+                VALUES ($1, $2, $3, $4, $5, $6, $7)
+                "#,
+                id,
+                variant.variant,
+                variant.format,
+                variant.path,
+                variant.width,
+                variant.height,
+                variant.byte_size
+            )
+            .execute(&mut **tx)
+            .await?;
+        }
+
+        Ok(())
+    }
+
+    pub async fn find_variants(&self, picture_id: Uuid) -> Result<Vec<PictureVariant>> {
+        let variants = sqlx::query_as!(
+            PictureVariant,
+            r#"
+            SELECT picture_id, variant, format, path, width, height
+            FROM picture_variants
+            WHERE picture_id = $1
+            "#,
+            picture_id
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(variants)
+    }
+
+    /// The newest version, which is displayed once rendered, or `None` while the picture is
+    /// unedited.
+    pub async fn find_current_version(&self, picture_id: Uuid) -> Result<Option<PictureVersion>> {
+        let version = sqlx::query_as!(
+            PictureVersion,
+            r#"
+            SELECT version, edits, rendered_at
+            FROM picture_versions
+            WHERE picture_id = $1
+            ORDER BY version DESC
+            LIMIT 1
+            "#,
+            picture_id
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(version)
+    }
+
+    /// Saves a version to be rendered. Edits of the same picture take turns on its row, so
+    /// they get consecutive numbers.
+    pub async fn create_version(
+        &self,
+        picture_id: Uuid,
+        edits: &serde_json::Value,
+    ) -> Result<PictureVersion> {
+        let mut tx = self.pool.begin().await?;
+
+        sqlx::query!("SELECT id FROM user_pictures WHERE id = $1 FOR UPDATE", picture_id)
+            .fetch_optional(&mut *tx)
+            .await?;
+
+        let version = sqlx::query_as!(
+            PictureVersion,
+            r#"
+            INSERT INTO picture_versions (picture_id, version, edits)
+            SELECT $1, COALESCE(MAX(version), 0) + 1, $2
+            FROM picture_versions
+            WHERE picture_id = $1
+            RETURNING version, edits, rendered_at
+            "#,
+            picture_id,
+            edits
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+        tx.commit().await?;
+
+        Ok(version)
+    }
+
+    /// Drops a version whose rendering could not be queued.
+    pub async fn delete_version(&self, picture_id: Uuid, version: i32) -> Result<()> {
+        sqlx::query!(
+            r#"
+            DELETE FROM picture_versions
+            WHERE picture_id = $1 AND version = $2 AND rendered_at IS NULL
+            "#,
+            picture_id,
+            version
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+}
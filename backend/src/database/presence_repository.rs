@@ -0,0 +1,66 @@
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+#[derive(Debug)]
+pub struct PresenceRepository {
+    pool: PgPool,
+}
+
+impl PresenceRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    pub async fn find_last_seen(&self, user_id: Uuid) -> Result<Option<DateTime<Utc>>> {
+        let last_seen_at = sqlx::query_scalar!(
+            "SELECT last_seen_at FROM user_presence WHERE user_id = $1",
+            user_id
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(last_seen_at)
+    }
+
+    pub async fn find_last_seen_many(
+        &self,
+        user_ids: &[Uuid],
+    ) -> Result<Vec<(Uuid, DateTime<Utc>)>> {
+        let rows = sqlx::query!(
+            "SELECT user_id, last_seen_at FROM user_presence WHERE user_id = ANY($1)",
+            user_ids
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| (row.user_id, row.last_seen_at))
+            .collect())
+    }
+
+    /// Stores last-seen times, never replacing a later one already stored.
+    pub async fn save_last_seen(&self, times: &[(Uuid, DateTime<Utc>)]) -> Result<()> {
+        let (user_ids, last_seen_at): (Vec<Uuid>, Vec<DateTime<Utc>>) =
+            times.iter().copied().unzip();
+
+        sqlx::query!(
+            r#"
+            INSERT INTO user_presence (user_id, last_seen_at)
+            SELECT t.user_id, t.last_seen_at
+            FROM UNNEST($1::uuid[], $2::timestamptz[]) AS t(user_id, last_seen_at)
+            JOIN users u ON u.id = t.user_id
+            ON CONFLICT (user_id) DO UPDATE
+            SET last_seen_at = GREATEST(user_presence.last_seen_at, EXCLUDED.last_seen_at)
+            "#,
+            &user_ids,
+            &last_seen_at
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+}
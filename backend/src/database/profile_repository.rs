@@ -0,0 +1,61 @@
+use crate::models::Profile;
+use anyhow::Result;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+#[derive(Debug)]
+pub struct ProfileRepository {
+    pool: PgPool,
+}
+
+impl ProfileRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    pub async fn find_by_user_id(&self, user_id: Uuid) -> Result<Option<Profile>> {
+        let row = sqlx::query!(
+            r#"
+            SELECT user_id, gender::text as "gender", sexual_preference::text as "sexual_preference", biography, birth_date, fame_rating
+            FROM user_profiles
+            WHERE user_id = $1
+            "#,
+            user_id
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        match row {
+            Some(row) => {
+                let profile = Profile::from_row(
+                    row.user_id,
+                    row.gender,
+                    row.sexual_preference,
+                    row.biography,
+                    row.birth_date,
+                    row.fame_rating,
+                )?;
+                Ok(Some(profile))
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// Tag names of `user_id` in alphabetical order.
+    pub async fn find_tags(&self, user_id: Uuid) -> Result<Vec<String>> {
+        let tags = sqlx::query_scalar!(
+            r#"
+            SELECT t.name
+            FROM user_tags ut
+            JOIN tags t ON t.id = ut.tag_id
+            WHERE ut.user_id = $1
+            ORDER BY t.name
+            "#,
+            user_id
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(tags)
+    }
+}
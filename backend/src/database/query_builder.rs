@@ -0,0 +1,348 @@
+use crate::enums::SortOrder;
+use chrono::{DateTime, Utc};
+use sqlx::{Postgres, QueryBuilder};
+use uuid::Uuid;
+
+/// A value sent as a query parameter, never spliced into the SQL text.
+#[derive(Debug, Clone, PartialEq)]
+pub enum BindValue {
+    Int(i32),
+    BigInt(i64),
+    Float(f64),
+    Text(String),
+    TextArray(Vec<String>),
+    Uuid(Uuid),
+    Timestamp(DateTime<Utc>),
+}
+
+impl From<i32> for BindValue {
+    fn from(value: i32) -> Self {
+        BindValue::Int(value)
+    }
+}
+
+impl From<i64> for BindValue {
+    fn from(value: i64) -> Self {
+        BindValue::BigInt(value)
+    }
+}
+
+impl From<f64> for BindValue {
+    fn from(value: f64) -> Self {
+        BindValue::Float(value)
+    }
+}
+
+impl From<String> for BindValue {
+    fn from(value: String) -> Self {
+        BindValue::Text(value)
+    }
+}
+
+impl From<Vec<String>> for BindValue {
+    fn from(value: Vec<String>) -> Self {
+        BindValue::TextArray(value)
+    }
+}
+
+impl From<Uuid> for BindValue {
+    fn from(value: Uuid) -> Self {
+        BindValue::Uuid(value)
+    }
+}
+
+impl From<DateTime<Utc>> for BindValue {
+    fn from(value: DateTime<Utc>) -> Self {
+        BindValue::Timestamp(value)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Part {
+    Sql(&'static str),
+    Bind(BindValue),
+}
+
+/// A piece of SQL assembled from string literals and bound values only.
+///
+/// Text can only be added as `&'static str`, so nothing read from a request can ever end up
+/// in the statement itself; request values have to go through `bind`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Sql {
+    parts: Vec<Part>,
+}
+
+impl Sql {
+    pub fn new(sql: &'static str) -> Self {
+        Self {
+            parts: vec![Part::Sql(sql)],
+        }
+    }
+
+    pub fn push(mut self, sql: &'static str) -> Self {
+        self.parts.push(Part::Sql(sql));
+        self
+    }
+
+    pub fn bind(mut self, value: impl Into<BindValue>) -> Self {
+        self.parts.push(Part::Bind(value.into()));
+        self
+    }
+
+    pub fn append(mut self, other: Sql) -> Self {
+        self.parts.extend(other.parts);
+        self
+    }
+
+    /// Hands the fragment to sqlx, which numbers the placeholders.
+    pub fn push_to(self, query: &mut QueryBuilder<'static, Postgres>) {
+        for part in self.parts {
+            match part {
+                Part::Sql(sql) => {
+                    query.push(sql);
+                }
+                Part::Bind(BindValue::Int(value)) => {
+                    query.push_bind(value);
+                }
+                Part::Bind(BindValue::BigInt(value)) => {
+                    query.push_bind(value);
+                }
+                Part::Bind(BindValue::Float(value)) => {
+                    query.push_bind(value);
+                }
+                Part::Bind(BindValue::Text(value)) => {
+                    query.push_bind(value);
+                }
+                Part::Bind(BindValue::TextArray(value)) => {
+                    query.push_bind(value);
+                }
+                Part::Bind(BindValue::Uuid(value)) => {
+                    query.push_bind(value);
+                }
+                Part::Bind(BindValue::Timestamp(value)) => {
+                    query.push_bind(value);
+                }
+            }
+        }
+    }
+
+    pub fn build(self) -> QueryBuilder<'static, Postgres> {
+        let mut query = QueryBuilder::new("");
+        self.push_to(&mut query);
+        query
+    }
+}
+
+/// Conditions that must all hold. Without any, everything matches.
+#[derive(Debug, Clone, Default)]
+pub struct Conditions {
+    clauses: Vec<Sql>,
+}
+
+impl Conditions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn and(&mut self, clause: Sql) -> &mut Self {
+        self.clauses.push(clause);
+        self
+    }
+
+    /// ` WHERE a AND b`, each clause parenthesized so `OR`s inside them stay contained.
+    pub fn into_where(self) -> Sql {
+        let mut sql = Sql::default();
+        for (index, clause) in self.clauses.into_iter().enumerate() {
+            sql = sql
+                .push(if index == 0 { " WHERE (" } else { " AND (" })
+                .append(clause)
+                .push(")");
+        }
+        sql
+    }
+}
+
+/// Something results may be sorted by, mapped to a fixed column or expression.
+///
+/// Request values can only select among the implementors, which makes them the whitelist.
+pub trait SortKey {
+    fn column(&self) -> &'static str;
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct OrderBy {
+    terms: Vec<(&'static str, SortOrder)>,
+}
+
+impl OrderBy {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn by(mut self, key: &impl SortKey, order: SortOrder) -> Self {
+        self.terms.push((key.column(), order));
+        self
+    }
+
+    /// ` ORDER BY a ASC NULLS LAST, b DESC NULLS LAST`; missing values always sort last.
+    pub fn into_sql(self) -> Sql {
+        let mut sql = Sql::default();
+        for (index, (column, order)) in self.terms.into_iter().enumerate() {
+            sql = sql
+                .push(if index == 0 { " ORDER BY " } else { ", " })
+                .push(column)
+                .push(match order {
+                    SortOrder::Asc => " ASC NULLS LAST",
+                    SortOrder::Desc => " DESC NULLS LAST",
+                });
+        }
+        sql
+    }
+}
+
+/// Keyset condition for rows after `(value, id)` in `ORDER BY column order NULLS LAST, id ASC`.
+///
+/// A `None` value means the last row had no sort key, so only rows without one remain.
+pub fn keyset_after(
+    column: &'static str,
+    order: SortOrder,
+    value: Option<BindValue>,
+    id_column: &'static str,
+    id: Uuid,
+) -> Sql {
+    match value {
+        None => Sql::new(column)
+            .push(" IS NULL AND ")
+            .push(id_column)
+            .push(" > ")
+            .bind(id),
+        Some(value) => Sql::new(column)
+            .push(match order {
+                SortOrder::Asc => " > ",
+                SortOrder::Desc => " < ",
+            })
+            .bind(value.clone())
+            .push(" OR (")
+            .push(column)
+            .push(" = ")
+            .bind(value)
+            .push(" AND ")
+            .push(id_column)
+            .push(" > ")
+            .bind(id)
+            .push(") OR ")
+            .push(column)
+            .push(" IS NULL"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    enum Column {
+        Name,
+        Age,
+    }
+
+    impl SortKey for Column {
+        fn column(&self) -> &'static str {
+            match self {
+                Column::Name => "name",
+                Column::Age => "age",
+            }
+        }
+    }
+
+    #[test]
+    fn numbers_bind_parameters_in_order() {
+        let sql = Sql::new("SELECT * FROM users WHERE age >= ")
+            .bind(18)
+            .push(" AND name = ")
+            .bind("alice".to_string());
+
+        assert_eq!(sql.build().sql(), "SELECT * FROM users WHERE age >= $1 AND name = $2");
+    }
+
+    #[test]
+    fn keeps_values_out_of_the_statement() {
+        let payload = "'; DROP TABLE users; --".to_string();
+        let query = Sql::new("SELECT * FROM users WHERE name = ")
+            .bind(payload)
+            .build();
+
+        assert_eq!(query.sql(), "SELECT * FROM users WHERE name = $1");
+    }
+
+    #[test]
+    fn joins_conditions_with_and() {
+        let mut conditions = Conditions::new();
+        conditions.and(Sql::new("age >= ").bind(18)).and(
+            Sql::new("name = ")
+                .bind("a".to_string())
+                .push(" OR name = ")
+                .bind("b".to_string()),
+        );
+        let query = Sql::new("SELECT * FROM users")
+            .append(conditions.into_where())
+            .build();
+
+        assert_eq!(
+            query.sql(),
+            "SELECT * FROM users WHERE (age >= $1) AND (name = $2 OR name = $3)"
+        );
+    }
+
+    #[test]
+    fn leaves_out_empty_clauses() {
+        let sql = Sql::new("SELECT * FROM users")
+            .append(Conditions::new().into_where())
+            .append(OrderBy::new().into_sql());
+
+        assert_eq!(sql.build().sql(), "SELECT * FROM users");
+    }
+
+    #[test]
+    fn orders_by_whitelisted_columns() {
+        let order = OrderBy::new()
+            .by(&Column::Age, SortOrder::Desc)
+            .by(&Column::Name, SortOrder::Asc);
+        let query = Sql::new("SELECT * FROM users")
+            .append(order.into_sql())
+            .build();
+
+        assert_eq!(
+            query.sql(),
+            "SELECT * FROM users ORDER BY age DESC NULLS LAST, name ASC NULLS LAST"
+        );
+    }
+
+    #[test]
+    fn continues_numbering_in_an_existing_builder() {
+        let mut query = QueryBuilder::new("SELECT * FROM users WHERE id = ");
+        query.push_bind(Uuid::nil()).push(" AND ");
+        Sql::new("tags && ")
+            .bind(vec!["music".to_string()])
+            .push(" LIMIT ")
+            .bind(20i64)
+            .push_to(&mut query);
+
+        assert_eq!(query.sql(), "SELECT * FROM users WHERE id = $1 AND tags && $2 LIMIT $3");
+    }
+
+    #[test]
+    fn continues_after_the_last_sort_key() {
+        let asc = keyset_after("age", SortOrder::Asc, Some(30.into()), "id", Uuid::nil());
+        assert_eq!(asc.build().sql(), "age > $1 OR (age = $2 AND id > $3) OR age IS NULL");
+
+        let desc = keyset_after("fame", SortOrder::Desc, Some(4.5.into()), "id", Uuid::nil());
+        assert_eq!(desc.build().sql(), "fame < $1 OR (fame = $2 AND id > $3) OR fame IS NULL");
+    }
+
+    #[test]
+    fn continues_among_missing_sort_keys() {
+        let after = keyset_after("age", SortOrder::Asc, None, "id", Uuid::nil());
+
+        assert_eq!(after.build().sql(), "age IS NULL AND id > $1");
+    }
+}
@@ -0,0 +1,171 @@
+use crate::enums::ReportReason;
+use anyhow::Result;
+use sqlx::{PgPool, Postgres, Transaction};
+use uuid::Uuid;
+
+/// A stored report and the queue item reviewing it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FiledReport {
+    pub id: Uuid,
+    pub moderation_item_id: Uuid,
+    /// Reports awaiting review on that item, this one included, with all automatic reports
+    /// counting as one.
+    pub pending_reports: i64,
+}
+
+#[derive(Debug)]
+pub struct ReportRepository {
+    pool: PgPool,
+}
+
+impl ReportRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// Stores a report and attaches it to the unresolved `reported_account` item about the
+    /// reported user, opening one if there is none. Returns `None` if the reporter already
+    /// reported this user.
+    pub async fn create(
+        &self,
+        reporter_id: Uuid,
+        reported_id: Uuid,
+        reason: ReportReason,
+        details: Option<&str>,
+    ) -> Result<Option<FiledReport>> {
+        let mut tx = self.pool.begin().await?;
+        lock_reported(&mut tx, reported_id).await?;
+
+        let Some(report_id) = sqlx::query_scalar!(
+            r#"
+            INSERT INTO reports (reporter_id, reported_id, reason, details)
+            VALUES ($1, $2, $3::text::report_reason, $4)
+            ON CONFLICT (reporter_id, reported_id) DO NOTHING
+            RETURNING id
+            "#,
+            reporter_id,
+            reported_id,
+            reason.to_string(),
+            details
+        )
+        .fetch_optional(&mut *tx)
+        .await?
+        else {
+            return Ok(None);
+        };
+
+        let report = attach_to_queue(&mut tx, report_id, reported_id).await?;
+        tx.commit().await?;
+
+        Ok(Some(report))
+    }
+
+    /// Files a `fake_account` report without a reporter, because a picture of the reported
+    /// user is a near-duplicate of one of `matched_user_id`. Returns `None` if the pair was
+    /// already reported.
+    pub async fn create_automatic(
+        &self,
+        reported_id: Uuid,
+        matched_user_id: Uuid,
+        details: &str,
+    ) -> Result<Option<FiledReport>> {
+        let mut tx = self.pool.begin().await?;
+        lock_reported(&mut tx, reported_id).await?;
+
+        let Some(report_id) = sqlx::query_scalar!(
+            r#"
+            INSERT INTO reports (reported_id, matched_user_id, reason, details)
+            VALUES ($1, $2, 'fake_account', $3)
+            ON CONFLICT (reported_id, matched_user_id) WHERE reporter_id IS NULL DO NOTHING
+            RETURNING id
+            "#,
+            reported_id,
+            matched_user_id,
+            details
+        )
+        .fetch_optional(&mut *tx)
+        .await?
+        else {
+            return Ok(None);
+        };
+
+        let report = attach_to_queue(&mut tx, report_id, reported_id).await?;
+        tx.commit().await?;
+
+        Ok(Some(report))
+    }
+}
+
+/// Concurrent reports about the same user must share one queue item.
+async fn lock_reported(tx: &mut Transaction<'_, Postgres>, reported_id: Uuid) -> Result<()> {
+    sqlx::query!("SELECT pg_advisory_xact_lock(hashtextextended($1::uuid::text, 0))", reported_id)
+        .execute(&mut **tx)
+        .await?;
+
+    Ok(())
+}
+
+/// Attaches a new report to the unresolved `reported_account` item about the user, opening
+/// one if there is none.
+async fn attach_to_queue(
+    tx: &mut Transaction<'_, Postgres>,
+    report_id: Uuid,
+    reported_id: Uuid,
+) -> Result<FiledReport> {
+    let open_item_id = sqlx::query_scalar!(
+        r#"
+        SELECT id FROM moderation_queue
+        WHERE subject_user_id = $1
+        AND kind = 'reported_account'
+        AND status IN ('open', 'reviewing')
+        ORDER BY created_at
+        LIMIT 1
+        "#,
+        reported_id
+    )
+    .fetch_optional(&mut **tx)
+    .await?;
+    let moderation_item_id = match open_item_id {
+        Some(id) => id,
+        None => {
+            sqlx::query_scalar!(
+                r#"
+                INSERT INTO moderation_queue (subject_user_id, kind)
+                VALUES ($1, 'reported_account')
+                RETURNING id
+                "#,
+                reported_id
+            )
+            .fetch_one(&mut **tx)
+            .await?
+        }
+    };
+
+    sqlx::query!(
+        "UPDATE reports SET moderation_item_id = $2 WHERE id = $1",
+        report_id,
+        moderation_item_id
+    )
+    .execute(&mut **tx)
+    .await?;
+
+    // Automatic reports count once together, so an account whose pictures were copied by
+    // several others is not suspended by the copies alone
+    let pending_reports = sqlx::query_scalar!(
+        r#"
+        SELECT (COUNT(DISTINCT reporter_id) + (COUNT(*) FILTER (WHERE reporter_id IS NULL) > 0)::int)
+            as "count!"
+        FROM reports
+        WHERE moderation_item_id = $1
+        "#,
+        moderation_item_id
+    )
+    .fetch_one(&mut **tx)
+    .await?;
+
+    Ok(FiledReport {
+        id: report_id,
+        moderation_item_id,
+        pending_reports,
+    })
+}
@@ -0,0 +1,32 @@
+use crate::enums::Role;
+use anyhow::Result;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+#[derive(Debug)]
+pub struct RoleRepository {
+    pool: PgPool,
+}
+
+impl RoleRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// Users without a granted role are plain users.
+    pub async fn find_role(&self, user_id: Uuid) -> Result<Role> {
+        let role = sqlx::query_scalar!(
+            r#"SELECT role::text as "role!" FROM user_roles WHERE user_id = $1"#,
+            user_id
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        match role {
+            Some(role) => role
+                .parse::<Role>()
+                .map_err(|_| anyhow::anyhow!("Invalid role: {}", role)),
+            None => Ok(Role::User),
+        }
+    }
+}
@@ -1,14 +1,34 @@
-use crate::enums::AccountStatus;
-use crate::models::User;
+use crate::database::block_repository::unblocked_condition;
+use crate::database::query_builder::{keyset_after, Conditions, OrderBy, SortKey, Sql};
+use crate::enums::{AccountStatus, Role, SearchSort, TagMode};
+use crate::models::{
+    AccountStatusChange, Coordinates, PublicLocation, SearchCriteria, SearchResult, User,
+};
+use crate::services::cursor::SortValue;
+use crate::services::geo::BoundingBox;
+use crate::services::matching::Orientation;
 use anyhow::Result;
-use sqlx::{PgPool, Row};
+use chrono::{DateTime, Utc};
+use sqlx::{PgPool, Postgres, QueryBuilder, Row};
 use uuid::Uuid;
 
+// Radii tried in turn by `find_nearest_candidates` until enough users are found; the last covers the globe
+const NEAREST_SEARCH_RADII_KM: [f64; 5] = [10.0, 50.0, 250.0, 1000.0, 20_050.0];
+
 #[derive(Debug)]
 pub struct UserRepository {
     pool: PgPool,
 }
 
+#[derive(Debug, Clone)]
+pub struct BrowseCandidate {
+    pub user: User,
+    pub location: PublicLocation,
+    pub distance_km: f64,
+    pub shared_tags: i64,
+    pub fame_rating: f64,
+}
+
 impl UserRepository {
     pub fn new(pool: PgPool) -> Self {
         Self { pool }
@@ -192,33 +212,60 @@ impl UserRepository {
         )
     }
 
-    pub async fn update_account_status(&self, id: Uuid, status: AccountStatus) -> Result<User> {
-        let status_str = status.to_string();
-
-        // Use raw query to avoid SQLx enum type checking
-        let query = format!(
+    /// Status changes of an account, newest first and continuing after `(created_at, id)`.
+    pub async fn find_status_history(
+        &self,
+        user_id: Uuid,
+        after: Option<(DateTime<Utc>, Uuid)>,
+        limit: i64,
+    ) -> Result<Vec<AccountStatusChange>> {
+        let (after_created_at, after_id) = after.unzip();
+        let rows = sqlx::query!(
             r#"
-            UPDATE users 
-            SET account_status = '{}'::account_status
-            WHERE id = $1 AND deleted_at IS NULL
-            RETURNING id, email, username, password_hash, email_verified_at, account_status::text as "account_status!", created_at, updated_at, deleted_at
+            SELECT h.id, h.status::text as "status!", h.previous_status::text as "previous_status!",
+                   h.changed_by, u.username as "changed_by_username?", h.reason, h.expires_at,
+                   h.created_at
+            FROM account_status_history h
+            LEFT JOIN users u ON u.id = h.changed_by
+            WHERE h.user_id = $1
+            AND ($2::timestamptz IS NULL OR (h.created_at, h.id) < ($2, $3))
+            ORDER BY h.created_at DESC, h.id DESC
+            LIMIT $4
             "#,
-            status_str
-        );
+            user_id,
+            after_created_at,
+            after_id,
+            limit
+        )
+        .fetch_all(&self.pool)
+        .await?;
 
-        let row = sqlx::query(&query).bind(id).fetch_one(&self.pool).await?;
+        rows.into_iter()
+            .map(|row| {
+                Ok(AccountStatusChange {
+                    id: row.id,
+                    status: parse_account_status(&row.status)?,
+                    previous_status: parse_account_status(&row.previous_status)?,
+                    changed_by: row.changed_by,
+                    changed_by_username: row.changed_by_username,
+                    reason: row.reason,
+                    expires_at: row.expires_at,
+                    created_at: row.created_at,
+                })
+            })
+            .collect()
+    }
 
-        User::from_row(
-            row.get("id"),
-            row.get("email"),
-            row.get("username"),
-            row.get("password_hash"),
-            row.get("email_verified_at"),
-            Some(row.get::<String, _>("account_status")),
-            row.get("created_at"),
-            row.get("updated_at"),
-            row.get("deleted_at"),
-        )
+    /// The change that gave an account its current status, if it was recorded.
+    pub async fn find_latest_status_change(
+        &self,
+        user_id: Uuid,
+    ) -> Result<Option<AccountStatusChange>> {
+        Ok(self
+            .find_status_history(user_id, None, 1)
+            .await?
+            .into_iter()
+            .next())
     }
 
     pub async fn soft_delete(&self, id: Uuid) -> Result<User> {
@@ -247,18 +294,26 @@ impl UserRepository {
         )
     }
 
-    pub async fn find_active_users(&self, limit: i64, offset: i64) -> Result<Vec<User>> {
+    /// Newest active users first, continuing after the user with the given creation time and id.
+    pub async fn find_active_users(
+        &self,
+        after: Option<(DateTime<Utc>, Uuid)>,
+        limit: i64,
+    ) -> Result<Vec<User>> {
+        let (after_created_at, after_id) = after.unzip();
         let rows = sqlx::query!(
             r#"
             SELECT id, email, username, password_hash, email_verified_at, account_status::text as "account_status!", created_at, updated_at, deleted_at
             FROM users 
             WHERE account_status = 'active' 
             AND deleted_at IS NULL
-            ORDER BY created_at DESC
-            LIMIT $1 OFFSET $2
+            AND ($1::timestamptz IS NULL OR (created_at, id) < ($1, $2))
+            ORDER BY created_at DESC, id DESC
+            LIMIT $3
             "#,
-            limit,
-            offset
+            after_created_at,
+            after_id,
+            limit
         )
         .fetch_all(&self.pool)
         .await?;
@@ -282,6 +337,199 @@ impl UserRepository {
         Ok(users)
     }
 
+    /// Users the viewer may be suggested within `radius_km` of `origin`, closest first.
+    ///
+    /// Candidates must be active, mutually compatible in orientation with the viewer, not
+    /// blocked in either direction and not already liked by the viewer. The bounding box is
+    /// matched against the spatial index, so only its few candidates get the exact haversine
+    /// distance computed.
+    pub async fn find_browse_candidates(
+        &self,
+        viewer_id: Uuid,
+        orientation: &Orientation,
+        origin: Coordinates,
+        radius_km: f64,
+        limit: i64,
+    ) -> Result<Vec<BrowseCandidate>> {
+        let bounds = BoundingBox::around(origin, radius_km);
+        let (genders, preferences) = orientation.compatible_orientation_names();
+
+        let rows = sqlx::query!(
+            r#"
+            SELECT u.id, u.email, u.username, u.password_hash, u.email_verified_at, u.account_status::text as "account_status!", u.created_at, u.updated_at, u.deleted_at,
+                l.latitude, l.longitude, l.neighborhood, l.city, l.country_code,
+                p.fame_rating,
+                d.distance_km as "distance_km!",
+                (
+                    SELECT COUNT(*)
+                    FROM user_tags theirs
+                    JOIN user_tags mine ON mine.tag_id = theirs.tag_id AND mine.user_id = $8
+                    WHERE theirs.user_id = u.id
+                ) as "shared_tags!"
+            FROM user_public_locations l
+            JOIN users u ON u.id = l.user_id
+            JOIN user_profiles p ON p.user_id = u.id
+            CROSS JOIN LATERAL (
+                SELECT 2 * 6371 * asin(least(1, sqrt(
+                    power(sin(radians(l.latitude - $1) / 2), 2)
+                    + cos(radians($1)) * cos(radians(l.latitude)) * power(sin(radians(l.longitude - $2) / 2), 2)
+                ))) AS distance_km
+            ) d
+            WHERE point(l.longitude, l.latitude) <@ box(point($3, $4), point($5, $6))
+            AND d.distance_km <= $7
+            AND u.id != $8
+            AND u.account_status = 'active'
+            AND u.deleted_at IS NULL
+            AND (p.gender::text, COALESCE(p.sexual_preference::text, 'bisexual'))
+                IN (SELECT * FROM UNNEST($9::text[], $10::text[]))
+            AND NOT is_blocked_between($8, u.id)
+            AND NOT EXISTS (
+                SELECT 1 FROM likes
+                WHERE liker_id = $8 AND liked_id = u.id
+            )
+            ORDER BY d.distance_km, u.id
+            LIMIT $11
+            "#,
+            origin.latitude,
+            origin.longitude,
+            bounds.min_longitude,
+            bounds.min_latitude,
+            bounds.max_longitude,
+            bounds.max_latitude,
+            radius_km,
+            viewer_id,
+            &genders,
+            &preferences,
+            limit
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        let mut candidates = Vec::new();
+        for row in rows {
+            let user = User::from_row(
+                row.id,
+                row.email,
+                row.username,
+                row.password_hash,
+                row.email_verified_at,
+                Some(row.account_status),
+                row.created_at,
+                row.updated_at,
+                row.deleted_at,
+            )?;
+            candidates.push(BrowseCandidate {
+                user,
+                location: PublicLocation {
+                    latitude: row.latitude,
+                    longitude: row.longitude,
+                    neighborhood: row.neighborhood,
+                    city: row.city,
+                    country_code: row.country_code,
+                },
+                distance_km: row.distance_km,
+                shared_tags: row.shared_tags,
+                fame_rating: row.fame_rating,
+            });
+        }
+
+        Ok(candidates)
+    }
+
+    /// Up to `count` browse candidates closest to `origin`, however far away they are.
+    ///
+    /// Widens the search radius step by step, so the common case of enough users close by
+    /// only ever touches a small part of the index.
+    pub async fn find_nearest_candidates(
+        &self,
+        viewer_id: Uuid,
+        orientation: &Orientation,
+        origin: Coordinates,
+        count: i64,
+    ) -> Result<Vec<BrowseCandidate>> {
+        let mut candidates = Vec::new();
+        for radius_km in NEAREST_SEARCH_RADII_KM {
+            candidates = self
+                .find_browse_candidates(viewer_id, orientation, origin, radius_km, count)
+                .await?;
+            if candidates.len() as i64 >= count {
+                break;
+            }
+        }
+
+        Ok(candidates)
+    }
+
+    /// Users matching `criteria`, as seen by the viewer at `origin`, continuing after the
+    /// result with the given sort key and id.
+    ///
+    /// Like browsing, results are limited to active, mutually compatible users who are not
+    /// blocked in either direction. Without an origin no distances are known.
+    pub async fn search(
+        &self,
+        viewer_id: Uuid,
+        orientation: &Orientation,
+        origin: Option<Coordinates>,
+        criteria: &SearchCriteria,
+        after: Option<(SortValue, Uuid)>,
+        limit: i64,
+    ) -> Result<Vec<SearchResult>> {
+        let rows = search_query(viewer_id, orientation, origin, criteria, after, limit)
+            .build()
+            .fetch_all(&self.pool)
+            .await?;
+
+        let mut results = Vec::new();
+        for row in rows {
+            let user = User::from_row(
+                row.get("id"),
+                row.get("email"),
+                row.get("username"),
+                row.get("password_hash"),
+                row.get("email_verified_at"),
+                Some(row.get::<String, _>("account_status")),
+                row.get("created_at"),
+                row.get("updated_at"),
+                row.get("deleted_at"),
+            )?;
+            let location = row
+                .get::<Option<f64>, _>("latitude")
+                .zip(row.get::<Option<f64>, _>("longitude"))
+                .map(|(latitude, longitude)| PublicLocation {
+                    latitude,
+                    longitude,
+                    neighborhood: row.get("neighborhood"),
+                    city: row.get("city"),
+                    country_code: row.get("country_code"),
+                });
+            results.push(SearchResult {
+                user,
+                location,
+                distance_km: row.get("distance_km"),
+                age: row.get("age"),
+                fame_rating: row.get("fame_rating"),
+                common_tags: row.get("common_tags"),
+            });
+        }
+
+        Ok(results)
+    }
+
+    pub async fn count_tags(&self, user_id: Uuid) -> Result<i64> {
+        let count = sqlx::query_scalar!(
+            r#"
+            SELECT COUNT(*) FROM user_tags
+            WHERE user_id = $1
+            "#,
+            user_id
+        )
+        .fetch_one(&self.pool)
+        .await?
+        .unwrap_or(0);
+
+        Ok(count)
+    }
+
     pub async fn count_active_users(&self) -> Result<i64> {
         let count = sqlx::query_scalar!(
             r#"
@@ -296,11 +544,247 @@ impl UserRepository {
 
         Ok(count)
     }
+
+    /// Any account, deleted ones included, with its role; for moderators.
+    pub async fn find_account(&self, id: Uuid) -> Result<Option<(User, Role)>> {
+        let row = sqlx::query!(
+            r#"
+            SELECT u.id, u.email, u.username, u.password_hash, u.email_verified_at,
+                   u.account_status::text as "account_status!", u.created_at, u.updated_at,
+                   u.deleted_at, COALESCE(r.role::text, 'user') as "role!"
+            FROM users u
+            LEFT JOIN user_roles r ON r.user_id = u.id
+            WHERE u.id = $1
+            "#,
+            id
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        row.map(|row| {
+            let user = User::from_row(
+                row.id,
+                row.email,
+                row.username,
+                row.password_hash,
+                row.email_verified_at,
+                Some(row.account_status),
+                row.created_at,
+                row.updated_at,
+                row.deleted_at,
+            )?;
+            let role = row
+                .role
+                .parse::<Role>()
+                .map_err(|_| anyhow::anyhow!("Invalid role: {}", row.role))?;
+            Ok((user, role))
+        })
+        .transpose()
+    }
+
+    /// Accounts whose username or email starts with `query`, deleted ones included, newest
+    /// first and continuing after `(created_at, id)`; for moderators.
+    pub async fn search_accounts(
+        &self,
+        query: Option<&str>,
+        status: Option<AccountStatus>,
+        after: Option<(DateTime<Utc>, Uuid)>,
+        limit: i64,
+    ) -> Result<Vec<(User, Role)>> {
+        let prefix = query.map(like_prefix);
+        let (after_created_at, after_id) = after.unzip();
+        let rows = sqlx::query!(
+            r#"
+            SELECT u.id, u.email, u.username, u.password_hash, u.email_verified_at,
+                   u.account_status::text as "account_status!", u.created_at, u.updated_at,
+                   u.deleted_at, COALESCE(r.role::text, 'user') as "role!"
+            FROM users u
+            LEFT JOIN user_roles r ON r.user_id = u.id
+            WHERE ($1::text IS NULL OR u.username ILIKE $1 OR u.email ILIKE $1)
+            AND ($2::text IS NULL OR u.account_status = $2::text::account_status)
+            AND ($3::timestamptz IS NULL OR (u.created_at, u.id) < ($3, $4))
+            ORDER BY u.created_at DESC, u.id DESC
+            LIMIT $5
+            "#,
+            prefix,
+            status.map(|status| status.to_string()),
+            after_created_at,
+            after_id,
+            limit
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.into_iter()
+            .map(|row| {
+                let user = User::from_row(
+                    row.id,
+                    row.email,
+                    row.username,
+                    row.password_hash,
+                    row.email_verified_at,
+                    Some(row.account_status),
+                    row.created_at,
+                    row.updated_at,
+                    row.deleted_at,
+                )?;
+                let role = row
+                    .role
+                    .parse::<Role>()
+                    .map_err(|_| anyhow::anyhow!("Invalid role: {}", row.role))?;
+                Ok((user, role))
+            })
+            .collect()
+    }
+}
+
+/// `ILIKE` pattern matching values that start with `prefix` literally.
+fn like_prefix(prefix: &str) -> String {
+    let escaped = prefix
+        .replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_");
+    format!("{}%", escaped)
+}
+
+impl SortKey for SearchSort {
+    fn column(&self) -> &'static str {
+        match self {
+            SearchSort::Distance => "d.distance_km",
+            SearchSort::Age => "a.age",
+            SearchSort::Fame => "p.fame_rating",
+            SearchSort::CommonTags => "c.common_tags",
+        }
+    }
+}
+
+/// Compiles search criteria into a single query; see `Sql` for why it cannot be injected into.
+fn search_query(
+    viewer_id: Uuid,
+    orientation: &Orientation,
+    origin: Option<Coordinates>,
+    criteria: &SearchCriteria,
+    after: Option<(SortValue, Uuid)>,
+    limit: i64,
+) -> QueryBuilder<'static, Postgres> {
+    let distance = match origin {
+        Some(origin) => Sql::new("2 * 6371 * asin(least(1, sqrt(power(sin(radians(l.latitude - ")
+            .bind(origin.latitude)
+            .push(") / 2), 2) + cos(radians(")
+            .bind(origin.latitude)
+            .push(")) * cos(radians(l.latitude)) * power(sin(radians(l.longitude - ")
+            .bind(origin.longitude)
+            .push(") / 2), 2))))"),
+        None => Sql::new("NULL::double precision"),
+    };
+
+    let mut conditions = Conditions::new();
+    conditions
+        .and(Sql::new("u.account_status = 'active' AND u.deleted_at IS NULL"))
+        .and(Sql::new("u.id != ").bind(viewer_id))
+        .and(orientation.sql_predicate("p.gender", "p.sexual_preference"))
+        .and(unblocked_condition(viewer_id, "u.id"));
+
+    if let Some(min_age) = criteria.min_age {
+        conditions.and(Sql::new("a.age >= ").bind(min_age));
+    }
+    if let Some(max_age) = criteria.max_age {
+        conditions.and(Sql::new("a.age <= ").bind(max_age));
+    }
+    if let Some(min_fame) = criteria.min_fame {
+        conditions.and(Sql::new("p.fame_rating >= ").bind(min_fame));
+    }
+    if let Some(max_fame) = criteria.max_fame {
+        conditions.and(Sql::new("p.fame_rating <= ").bind(max_fame));
+    }
+    if let (Some(origin), Some(max_distance_km)) = (origin, criteria.max_distance_km) {
+        // The bounding box lets the spatial index discard far away users before any distance is computed
+        let bounds = BoundingBox::around(origin, max_distance_km);
+        conditions
+            .and(
+                Sql::new("point(l.longitude, l.latitude) <@ box(point(")
+                    .bind(bounds.min_longitude)
+                    .push(", ")
+                    .bind(bounds.min_latitude)
+                    .push("), point(")
+                    .bind(bounds.max_longitude)
+                    .push(", ")
+                    .bind(bounds.max_latitude)
+                    .push("))"),
+            )
+            .and(Sql::new("d.distance_km <= ").bind(max_distance_km));
+    }
+    if !criteria.tags.is_empty() {
+        let matching_tags = Sql::new(
+            "(SELECT COUNT(*) FROM user_tags ut JOIN tags t ON t.id = ut.tag_id \
+             WHERE ut.user_id = u.id AND t.name = ANY(",
+        )
+        .bind(criteria.tags.clone())
+        .push("))");
+        conditions.and(match criteria.tag_mode {
+            TagMode::Any => matching_tags.push(" > 0"),
+            TagMode::All => matching_tags.push(" = ").bind(criteria.tags.len() as i64),
+        });
+    }
+    if let Some(min_common_tags) = criteria.min_common_tags {
+        conditions.and(Sql::new("c.common_tags >= ").bind(min_common_tags));
+    }
+    if let Some((value, id)) = after {
+        let value = match value {
+            SortValue::Null => None,
+            SortValue::Number(number) => Some(number.into()),
+            SortValue::Time(time) => Some(time.into()),
+        };
+        conditions.and(keyset_after(criteria.sort.column(), criteria.order, value, "u.id", id));
+    }
+
+    Sql::new(
+        r#"
+        SELECT u.id, u.email, u.username, u.password_hash, u.email_verified_at, u.account_status::text as account_status, u.created_at, u.updated_at, u.deleted_at,
+            l.latitude, l.longitude, l.neighborhood, l.city, l.country_code,
+            p.fame_rating, a.age, d.distance_km, c.common_tags
+        FROM users u
+        JOIN user_profiles p ON p.user_id = u.id
+        LEFT JOIN user_public_locations l ON l.user_id = u.id
+        CROSS JOIN LATERAL (
+            SELECT EXTRACT(YEAR FROM age(p.birth_date))::int AS age
+        ) a
+        CROSS JOIN LATERAL (
+            SELECT "#,
+    )
+    .append(distance)
+    .push(
+        r#" AS distance_km
+        ) d
+        CROSS JOIN LATERAL (
+            SELECT COUNT(*) AS common_tags
+            FROM user_tags theirs
+            JOIN user_tags mine ON mine.tag_id = theirs.tag_id AND mine.user_id = "#,
+    )
+    .bind(viewer_id)
+    .push(
+        r#"
+            WHERE theirs.user_id = u.id
+        ) c"#,
+    )
+    .append(conditions.into_where())
+    .append(OrderBy::new().by(&criteria.sort, criteria.order).into_sql())
+    // Ties are broken by id so pages neither repeat nor skip users
+    .push(", u.id LIMIT ")
+    .bind(limit)
+    .build()
+}
+
+fn parse_account_status(status: &str) -> Result<AccountStatus> {
+    status
+        .parse()
+        .map_err(|_| anyhow::anyhow!("Invalid account status: {}", status))
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::enums::SortOrder;
     use chrono::Utc;
     use uuid::Uuid;
 
@@ -327,4 +811,104 @@ mod tests {
         assert_eq!(AccountStatus::Active, AccountStatus::Active);
         assert_ne!(AccountStatus::Active, AccountStatus::Suspended);
     }
+
+    fn criteria() -> SearchCriteria {
+        SearchCriteria {
+            min_age: None,
+            max_age: None,
+            min_fame: None,
+            max_fame: None,
+            max_distance_km: None,
+            tags: Vec::new(),
+            tag_mode: TagMode::Any,
+            min_common_tags: None,
+            sort: SearchSort::Distance,
+            order: SortOrder::Asc,
+        }
+    }
+
+    fn orientation() -> Orientation {
+        Orientation::new(crate::enums::Gender::Female, None)
+    }
+
+    #[test]
+    fn binds_search_input_instead_of_inlining_it() {
+        let criteria = SearchCriteria {
+            min_age: Some(25),
+            max_fame: Some(80.0),
+            tags: vec!["x'); DROP TABLE users;--".to_string()],
+            ..criteria()
+        };
+        let origin = Coordinates::new(52.5, 13.4).unwrap();
+        let query = search_query(Uuid::new_v4(), &orientation(), Some(origin), &criteria, None, 20);
+        let sql = query.sql();
+
+        assert!(!sql.contains("DROP TABLE"));
+        assert!(!sql.contains("52.5"));
+        assert!(sql.contains("(a.age >= $"));
+        assert!(sql.contains("p.fame_rating <= $"));
+        assert!(sql.contains("t.name = ANY($"));
+        assert!(!sql.contains("p.fame_rating >= $"));
+        assert!(sql.contains("LIMIT $"));
+        assert!(sql.contains("ORDER BY d.distance_km ASC NULLS LAST, u.id"));
+        assert_eq!(sql.matches('$').count(), 12);
+    }
+
+    #[test]
+    fn requires_every_tag_in_all_mode() {
+        let tags = vec!["music".to_string(), "travel".to_string()];
+        let any = SearchCriteria {
+            tags: tags.clone(),
+            ..criteria()
+        };
+        let all = SearchCriteria {
+            tags,
+            tag_mode: TagMode::All,
+            ..criteria()
+        };
+
+        let any = search_query(Uuid::new_v4(), &orientation(), None, &any, None, 20);
+        let all = search_query(Uuid::new_v4(), &orientation(), None, &all, None, 20);
+        assert!(any.sql().contains(")) > 0"));
+        assert!(all.sql().contains(")) = $"));
+    }
+
+    #[test]
+    fn filters_distance_only_with_an_origin() {
+        let criteria = SearchCriteria {
+            max_distance_km: Some(50.0),
+            sort: SearchSort::CommonTags,
+            order: SortOrder::Desc,
+            ..criteria()
+        };
+        let origin = Coordinates::new(52.5, 13.4).unwrap();
+
+        let near = search_query(Uuid::new_v4(), &orientation(), Some(origin), &criteria, None, 20);
+        assert!(near.sql().contains("d.distance_km <= $"));
+        assert!(near.sql().contains("ORDER BY c.common_tags DESC"));
+
+        let anywhere = search_query(Uuid::new_v4(), &orientation(), None, &criteria, None, 20);
+        assert!(anywhere.sql().contains("NULL::double precision"));
+        assert!(!anywhere.sql().contains("d.distance_km <="));
+    }
+
+    #[test]
+    fn continues_after_the_cursor_position() {
+        let criteria = SearchCriteria {
+            sort: SearchSort::Fame,
+            order: SortOrder::Desc,
+            ..criteria()
+        };
+        let after = Some((SortValue::Number(42.0), Uuid::new_v4()));
+        let query = search_query(Uuid::new_v4(), &orientation(), None, &criteria, after, 20);
+
+        assert!(query.sql().contains("(p.fame_rating < $"));
+        assert!(query.sql().contains("AND u.id > $"));
+    }
+
+    #[test]
+    fn escapes_wildcards_in_prefixes() {
+        assert_eq!(like_prefix("ali"), "ali%");
+        assert_eq!(like_prefix("100%_a\\b"), "100\\%\\_a\\\\b%");
+    }
 }
@@ -0,0 +1,91 @@
+use crate::database::like_repository::lock_pair;
+use crate::models::ProfileVisit;
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+#[derive(Debug)]
+pub struct VisitRepository {
+    pool: PgPool,
+}
+
+impl VisitRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// Stores a visit unless the visitor already saw the profile after `since`.
+    /// Returns whether it was stored.
+    pub async fn record(
+        &self,
+        visitor_id: Uuid,
+        visited_id: Uuid,
+        since: DateTime<Utc>,
+    ) -> Result<bool> {
+        let mut tx = self.pool.begin().await?;
+        // Without it two requests at once would both find no recent visit and both store one
+        lock_pair(&mut tx, visitor_id, visited_id).await?;
+
+        let result = sqlx::query!(
+            r#"
+            INSERT INTO profile_visits (visitor_id, visited_id)
+            SELECT $1, $2
+            WHERE NOT EXISTS (
+                SELECT 1 FROM profile_visits
+                WHERE visitor_id = $1 AND visited_id = $2 AND visited_at > $3
+            )
+            "#,
+            visitor_id,
+            visited_id,
+            since
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Visits of `user_id`'s profile, newest first, continuing after `(visited_at, id)`.
+    /// Visitors who are blocked in either direction or no longer active are left out.
+    pub async fn find_visitors(
+        &self,
+        user_id: Uuid,
+        after: Option<(DateTime<Utc>, Uuid)>,
+        limit: i64,
+    ) -> Result<Vec<ProfileVisit>> {
+        let (after_visited_at, after_id) = after.unzip();
+        let rows = sqlx::query!(
+            r#"
+            SELECT v.id, v.visitor_id, u.username, v.visited_at
+            FROM profile_visits v
+            JOIN users u ON u.id = v.visitor_id
+            WHERE v.visited_id = $1
+            AND u.account_status = 'active'
+            AND u.deleted_at IS NULL
+            AND NOT is_blocked_between($1, v.visitor_id)
+            AND ($2::timestamptz IS NULL OR (v.visited_at, v.id) < ($2, $3))
+            ORDER BY v.visited_at DESC, v.id DESC
+            LIMIT $4
+            "#,
+            user_id,
+            after_visited_at,
+            after_id,
+            limit
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| ProfileVisit {
+                id: row.id,
+                visitor_id: row.visitor_id,
+                visitor_username: row.username,
+                visited_at: row.visited_at,
+            })
+            .collect())
+    }
+}
@@ -0,0 +1,60 @@
+use serde::{Deserialize, Serialize};
+use strum::{Display, EnumIter, EnumString};
+
+/// Interactions that count towards the fame rating of the user they happen to.
+#[derive(Debug, Clone, Copy, PartialEq, Display, EnumIter, EnumString, Serialize, Deserialize)]
+#[strum(serialize_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum FameEventKind {
+    Like,
+    View,
+    Connection,
+    Report,
+}
+
+/// Why a fame rating was recomputed.
+#[derive(Debug, Clone, Copy, PartialEq, Display, EnumIter, EnumString, Serialize, Deserialize)]
+#[strum(serialize_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum FameTrigger {
+    EventRecorded,
+    EventWithdrawn,
+    ProfileUpdated,
+    Scheduled,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use strum::IntoEnumIterator;
+
+    #[test]
+    fn parses_enum_from_str() {
+        assert_eq!("like".parse(), Ok(FameEventKind::Like));
+        assert_eq!("connection".parse(), Ok(FameEventKind::Connection));
+        assert!("invalid".parse::<FameEventKind>().is_err());
+
+        assert_eq!("event_withdrawn".parse(), Ok(FameTrigger::EventWithdrawn));
+        assert_eq!("scheduled".parse(), Ok(FameTrigger::Scheduled));
+        assert!("invalid".parse::<FameTrigger>().is_err());
+    }
+
+    #[test]
+    fn displays_all_values() {
+        assert_eq!(FameEventKind::Like.to_string(), "like");
+        assert_eq!(FameEventKind::View.to_string(), "view");
+        assert_eq!(FameEventKind::Connection.to_string(), "connection");
+        assert_eq!(FameEventKind::Report.to_string(), "report");
+
+        assert_eq!(FameTrigger::EventRecorded.to_string(), "event_recorded");
+        assert_eq!(FameTrigger::EventWithdrawn.to_string(), "event_withdrawn");
+        assert_eq!(FameTrigger::ProfileUpdated.to_string(), "profile_updated");
+        assert_eq!(FameTrigger::Scheduled.to_string(), "scheduled");
+    }
+
+    #[test]
+    fn iterates_all_values() {
+        assert_eq!(FameEventKind::iter().count(), 4);
+        assert_eq!(FameTrigger::iter().count(), 4);
+    }
+}
@@ -0,0 +1,59 @@
+use serde::{Deserialize, Serialize};
+use strum::{Display, EnumIter, EnumString};
+
+/// Where a location came from. Sources are listed from least to most trusted,
+/// so a manual override always wins over GPS, and GPS over the IP fallback.
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Display,
+    EnumIter,
+    EnumString,
+    Serialize,
+    Deserialize,
+)]
+#[strum(serialize_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum LocationSource {
+    Ip,
+    Gps,
+    Manual,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use strum::IntoEnumIterator;
+
+    #[test]
+    fn parses_enum_from_str() {
+        assert_eq!("ip".parse(), Ok(LocationSource::Ip));
+        assert_eq!("gps".parse(), Ok(LocationSource::Gps));
+        assert_eq!("manual".parse(), Ok(LocationSource::Manual));
+        assert!("invalid".parse::<LocationSource>().is_err());
+    }
+
+    #[test]
+    fn displays_all_values() {
+        assert_eq!(LocationSource::Ip.to_string(), "ip");
+        assert_eq!(LocationSource::Gps.to_string(), "gps");
+        assert_eq!(LocationSource::Manual.to_string(), "manual");
+    }
+
+    #[test]
+    fn iterates_all_values() {
+        let values: Vec<LocationSource> = LocationSource::iter().collect();
+        assert_eq!(values.len(), 3);
+    }
+
+    #[test]
+    fn orders_by_trust() {
+        assert!(LocationSource::Manual > LocationSource::Gps);
+        assert!(LocationSource::Gps > LocationSource::Ip);
+    }
+}
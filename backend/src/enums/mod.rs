@@ -1,5 +1,25 @@
 pub mod account_status;
 pub mod environment;
+pub mod fame_event_kind;
+pub mod location_source;
+pub mod moderation_item_kind;
+pub mod notification_kind;
+pub mod picture_status;
+pub mod report_reason;
+pub mod role;
+pub mod search_sort;
+pub mod storage_backend;
+pub mod tag_mode;
 
-pub use account_status::AccountStatus;
+pub use account_status::{AccountStatus, Gender, SexualPreference};
 pub use environment::Environment;
+pub use fame_event_kind::{FameEventKind, FameTrigger};
+pub use location_source::LocationSource;
+pub use moderation_item_kind::{ModerationActionKind, ModerationItemKind, ModerationStatus};
+pub use notification_kind::NotificationKind;
+pub use picture_status::PictureStatus;
+pub use report_reason::ReportReason;
+pub use role::Role;
+pub use search_sort::{SearchSort, SortOrder};
+pub use storage_backend::StorageBackend;
+pub use tag_mode::TagMode;
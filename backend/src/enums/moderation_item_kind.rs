@@ -0,0 +1,73 @@
+use serde::{Deserialize, Serialize};
+use strum::{Display, EnumIter, EnumString};
+
+#[derive(Debug, Clone, PartialEq, Display, EnumIter, EnumString, Serialize, Deserialize)]
+#[strum(serialize_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum ModerationItemKind {
+    DuplicatePicture,
+    ReportedAccount,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Display, EnumIter, EnumString, Serialize, Deserialize)]
+#[strum(serialize_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum ModerationStatus {
+    Open,
+    Reviewing,
+    Actioned,
+    Dismissed,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Display, EnumIter, EnumString, Serialize, Deserialize)]
+#[strum(serialize_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum ModerationActionKind {
+    Suspend,
+    Ban,
+    Reinstate,
+    Restore,
+    ChangeRole,
+    Review,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use strum::IntoEnumIterator;
+
+    #[test]
+    fn parses_enum_from_str() {
+        assert_eq!("duplicate_picture".parse(), Ok(ModerationItemKind::DuplicatePicture));
+        assert_eq!("reported_account".parse(), Ok(ModerationItemKind::ReportedAccount));
+        assert!("invalid".parse::<ModerationItemKind>().is_err());
+        assert_eq!("dismissed".parse(), Ok(ModerationStatus::Dismissed));
+        assert!("closed".parse::<ModerationStatus>().is_err());
+        assert_eq!("change_role".parse(), Ok(ModerationActionKind::ChangeRole));
+        assert!("invalid".parse::<ModerationActionKind>().is_err());
+    }
+
+    #[test]
+    fn displays_all_values() {
+        assert_eq!(ModerationItemKind::DuplicatePicture.to_string(), "duplicate_picture");
+        assert_eq!(ModerationItemKind::ReportedAccount.to_string(), "reported_account");
+        assert_eq!(ModerationStatus::Open.to_string(), "open");
+        assert_eq!(ModerationStatus::Reviewing.to_string(), "reviewing");
+        assert_eq!(ModerationStatus::Actioned.to_string(), "actioned");
+        assert_eq!(ModerationStatus::Dismissed.to_string(), "dismissed");
+        assert_eq!(ModerationActionKind::Suspend.to_string(), "suspend");
+        assert_eq!(ModerationActionKind::Ban.to_string(), "ban");
+        assert_eq!(ModerationActionKind::Reinstate.to_string(), "reinstate");
+        assert_eq!(ModerationActionKind::Restore.to_string(), "restore");
+        assert_eq!(ModerationActionKind::ChangeRole.to_string(), "change_role");
+        assert_eq!(ModerationActionKind::Review.to_string(), "review");
+    }
+
+    #[test]
+    fn iterates_all_values() {
+        let values: Vec<ModerationItemKind> = ModerationItemKind::iter().collect();
+        assert_eq!(values.len(), 2);
+        assert_eq!(ModerationStatus::iter().count(), 4);
+        assert_eq!(ModerationActionKind::iter().count(), 6);
+    }
+}
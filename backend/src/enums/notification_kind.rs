@@ -0,0 +1,39 @@
+use serde::{Deserialize, Serialize};
+use strum::{Display, EnumIter, EnumString};
+
+#[derive(Debug, Clone, Copy, PartialEq, Display, EnumIter, EnumString, Serialize, Deserialize)]
+#[strum(serialize_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum NotificationKind {
+    ProfileViewed,
+    Liked,
+    Connected,
+    Disconnected,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use strum::IntoEnumIterator;
+
+    #[test]
+    fn parses_enum_from_str() {
+        assert_eq!("profile_viewed".parse(), Ok(NotificationKind::ProfileViewed));
+        assert_eq!("disconnected".parse(), Ok(NotificationKind::Disconnected));
+        assert!("invalid".parse::<NotificationKind>().is_err());
+    }
+
+    #[test]
+    fn displays_all_values() {
+        assert_eq!(NotificationKind::ProfileViewed.to_string(), "profile_viewed");
+        assert_eq!(NotificationKind::Liked.to_string(), "liked");
+        assert_eq!(NotificationKind::Connected.to_string(), "connected");
+        assert_eq!(NotificationKind::Disconnected.to_string(), "disconnected");
+    }
+
+    #[test]
+    fn iterates_all_values() {
+        let values: Vec<NotificationKind> = NotificationKind::iter().collect();
+        assert_eq!(values.len(), 4);
+    }
+}
@@ -0,0 +1,41 @@
+use serde::{Deserialize, Serialize};
+use strum::{Display, EnumIter, EnumString};
+
+#[derive(Debug, Clone, PartialEq, Display, EnumIter, EnumString, Serialize, Deserialize)]
+#[strum(serialize_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum PictureStatus {
+    Pending,
+    Processing,
+    Ready,
+    Failed,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use strum::IntoEnumIterator;
+
+    #[test]
+    fn parses_enum_from_str() {
+        assert_eq!("pending".parse(), Ok(PictureStatus::Pending));
+        assert_eq!("processing".parse(), Ok(PictureStatus::Processing));
+        assert_eq!("ready".parse(), Ok(PictureStatus::Ready));
+        assert_eq!("failed".parse(), Ok(PictureStatus::Failed));
+        assert!("invalid".parse::<PictureStatus>().is_err());
+    }
+
+    #[test]
+    fn displays_all_values() {
+        assert_eq!(PictureStatus::Pending.to_string(), "pending");
+        assert_eq!(PictureStatus::Processing.to_string(), "processing");
+        assert_eq!(PictureStatus::Ready.to_string(), "ready");
+        assert_eq!(PictureStatus::Failed.to_string(), "failed");
+    }
+
+    #[test]
+    fn iterates_all_values() {
+        let values: Vec<PictureStatus> = PictureStatus::iter().collect();
+        assert_eq!(values.len(), 4);
+    }
+}
@@ -0,0 +1,43 @@
+use serde::{Deserialize, Serialize};
+use strum::{Display, EnumIter, EnumString};
+
+#[derive(Debug, Clone, Copy, PartialEq, Display, EnumIter, EnumString, Serialize, Deserialize)]
+#[strum(serialize_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum ReportReason {
+    FakeAccount,
+    Spam,
+    Harassment,
+    InappropriateContent,
+    Underage,
+    Other,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use strum::IntoEnumIterator;
+
+    #[test]
+    fn parses_enum_from_str() {
+        assert_eq!("fake_account".parse(), Ok(ReportReason::FakeAccount));
+        assert_eq!("inappropriate_content".parse(), Ok(ReportReason::InappropriateContent));
+        assert!("invalid".parse::<ReportReason>().is_err());
+    }
+
+    #[test]
+    fn displays_all_values() {
+        assert_eq!(ReportReason::FakeAccount.to_string(), "fake_account");
+        assert_eq!(ReportReason::Spam.to_string(), "spam");
+        assert_eq!(ReportReason::Harassment.to_string(), "harassment");
+        assert_eq!(ReportReason::InappropriateContent.to_string(), "inappropriate_content");
+        assert_eq!(ReportReason::Underage.to_string(), "underage");
+        assert_eq!(ReportReason::Other.to_string(), "other");
+    }
+
+    #[test]
+    fn iterates_all_values() {
+        let values: Vec<ReportReason> = ReportReason::iter().collect();
+        assert_eq!(values.len(), 6);
+    }
+}
@@ -0,0 +1,56 @@
+use serde::{Deserialize, Serialize};
+use strum::{Display, EnumIter, EnumString};
+
+/// What a user may do, each role including everything the ones before it may.
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Display,
+    EnumIter,
+    EnumString,
+    Serialize,
+    Deserialize,
+)]
+#[strum(serialize_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum Role {
+    User,
+    Moderator,
+    Admin,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use strum::IntoEnumIterator;
+
+    #[test]
+    fn parses_enum_from_str() {
+        assert_eq!("moderator".parse(), Ok(Role::Moderator));
+        assert!("invalid".parse::<Role>().is_err());
+    }
+
+    #[test]
+    fn displays_all_values() {
+        assert_eq!(Role::User.to_string(), "user");
+        assert_eq!(Role::Moderator.to_string(), "moderator");
+        assert_eq!(Role::Admin.to_string(), "admin");
+    }
+
+    #[test]
+    fn iterates_all_values() {
+        let values: Vec<Role> = Role::iter().collect();
+        assert_eq!(values.len(), 3);
+    }
+
+    #[test]
+    fn orders_roles_by_power() {
+        assert!(Role::User < Role::Moderator);
+        assert!(Role::Moderator < Role::Admin);
+    }
+}
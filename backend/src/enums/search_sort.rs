@@ -0,0 +1,79 @@
+use serde::{Deserialize, Serialize};
+use strum::{Display, EnumIter, EnumString};
+
+/// What search results can be ordered by.
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Display, EnumIter, EnumString, Serialize, Deserialize,
+)]
+#[strum(serialize_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum SearchSort {
+    Distance,
+    Age,
+    Fame,
+    CommonTags,
+}
+
+impl SearchSort {
+    /// The order used when none is requested: closest, youngest, most famous, most alike first.
+    pub fn default_order(&self) -> SortOrder {
+        match self {
+            SearchSort::Distance | SearchSort::Age => SortOrder::Asc,
+            SearchSort::Fame | SearchSort::CommonTags => SortOrder::Desc,
+        }
+    }
+}
+
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Display, EnumIter, EnumString, Serialize, Deserialize,
+)]
+#[strum(serialize_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum SortOrder {
+    Asc,
+    Desc,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use strum::IntoEnumIterator;
+
+    #[test]
+    fn parses_enum_from_str() {
+        assert_eq!("distance".parse(), Ok(SearchSort::Distance));
+        assert_eq!("age".parse(), Ok(SearchSort::Age));
+        assert_eq!("fame".parse(), Ok(SearchSort::Fame));
+        assert_eq!("common_tags".parse(), Ok(SearchSort::CommonTags));
+        assert!("invalid".parse::<SearchSort>().is_err());
+
+        assert_eq!("asc".parse(), Ok(SortOrder::Asc));
+        assert_eq!("desc".parse(), Ok(SortOrder::Desc));
+        assert!("invalid".parse::<SortOrder>().is_err());
+    }
+
+    #[test]
+    fn displays_all_values() {
+        assert_eq!(SearchSort::Distance.to_string(), "distance");
+        assert_eq!(SearchSort::Age.to_string(), "age");
+        assert_eq!(SearchSort::Fame.to_string(), "fame");
+        assert_eq!(SearchSort::CommonTags.to_string(), "common_tags");
+
+        assert_eq!(SortOrder::Asc.to_string(), "asc");
+        assert_eq!(SortOrder::Desc.to_string(), "desc");
+    }
+
+    #[test]
+    fn iterates_all_values() {
+        assert_eq!(SearchSort::iter().count(), 4);
+        assert_eq!(SortOrder::iter().count(), 2);
+    }
+
+    #[test]
+    fn orders_best_matches_first_by_default() {
+        assert_eq!(SearchSort::Distance.default_order(), SortOrder::Asc);
+        assert_eq!(SearchSort::Age.default_order(), SortOrder::Asc);
+        assert_eq!(SearchSort::Fame.default_order(), SortOrder::Desc);
+        assert_eq!(SearchSort::CommonTags.default_order(), SortOrder::Desc);
+    }
+}
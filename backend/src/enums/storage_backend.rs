@@ -0,0 +1,33 @@
+use strum::{Display, EnumIter, EnumString};
+
+#[derive(Debug, Clone, PartialEq, Display, EnumIter, EnumString)]
+#[strum(serialize_all = "lowercase")]
+pub enum StorageBackend {
+    Local,
+    S3,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use strum::IntoEnumIterator;
+
+    #[test]
+    fn parses_enum_from_str() {
+        assert_eq!("local".parse(), Ok(StorageBackend::Local));
+        assert_eq!("s3".parse(), Ok(StorageBackend::S3));
+        assert!("gcs".parse::<StorageBackend>().is_err());
+    }
+
+    #[test]
+    fn displays_all_values() {
+        assert_eq!(StorageBackend::Local.to_string(), "local");
+        assert_eq!(StorageBackend::S3.to_string(), "s3");
+    }
+
+    #[test]
+    fn iterates_all_values() {
+        let values: Vec<StorageBackend> = StorageBackend::iter().collect();
+        assert_eq!(values.len(), 2);
+    }
+}
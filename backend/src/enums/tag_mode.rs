@@ -0,0 +1,38 @@
+use serde::{Deserialize, Serialize};
+use strum::{Display, EnumIter, EnumString};
+
+/// Whether a tag filter matches users with any or with all of the given tags.
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Display, EnumIter, EnumString, Serialize, Deserialize,
+)]
+#[strum(serialize_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum TagMode {
+    Any,
+    All,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use strum::IntoEnumIterator;
+
+    #[test]
+    fn parses_enum_from_str() {
+        assert_eq!("any".parse(), Ok(TagMode::Any));
+        assert_eq!("all".parse(), Ok(TagMode::All));
+        assert!("invalid".parse::<TagMode>().is_err());
+    }
+
+    #[test]
+    fn displays_all_values() {
+        assert_eq!(TagMode::Any.to_string(), "any");
+        assert_eq!(TagMode::All.to_string(), "all");
+    }
+
+    #[test]
+    fn iterates_all_values() {
+        let values: Vec<TagMode> = TagMode::iter().collect();
+        assert_eq!(values.len(), 2);
+    }
+}
@@ -5,7 +5,8 @@ use axum::{
     Router,
 };
 use sqlx::PgPool;
-use std::net::SocketAddr;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::Arc;
 use tower::ServiceBuilder;
 use tower_http::{cors::CorsLayer, trace::TraceLayer};
 use tracing::info;
@@ -20,6 +21,7 @@ mod middleware;
 mod models;
 mod routes;
 mod services;
+mod socketio;
 mod utils;
 mod validation;
 mod websocket;
@@ -28,6 +30,18 @@ mod websocket;
 pub struct AppState {
     pub db: PgPool,
     pub jwt_service: services::jwt::JwtService,
+    pub jobs: services::jobs::JobQueue,
+    pub fame: services::fame::FameQueue,
+    pub storage: services::storage::SharedStorage,
+    pub media_signer: services::storage::MediaSigner,
+    pub cursor_signer: services::cursor::CursorSigner,
+    pub geoip: services::geoip::SharedGeoIp,
+    pub geo: services::geo::SharedGeo,
+    pub browse_weights: services::browse::BrowseWeights,
+    pub presence: services::presence::Presence,
+    pub hub: services::hub::Hub,
+    pub socket_io: socketio::SocketIo,
+    pub trusted_proxies: Arc<[IpAddr]>,
 }
 
 #[tokio::main]
@@ -47,9 +61,46 @@ async fn main() -> anyhow::Result<()> {
 
     let database_pool = database::create_pool(&config.database_url).await?;
     let jwt_service = services::jwt::JwtService::new(&config.jwt_secret);
+    let media_signer = services::storage::MediaSigner::new(&config.jwt_secret);
+    let cursor_signer = services::cursor::CursorSigner::new(&config.jwt_secret);
+    let storage = services::storage::create_storage(&config, media_signer.clone())?;
+    info!("Storing media with the {} backend", config.storage_backend);
+    let fame = services::fame::FameQueue::start(database_pool.clone());
+    let hub = services::hub::Hub::new();
+    let jobs = services::jobs::JobQueue::start(
+        database_pool.clone(),
+        storage.clone(),
+        fame.clone(),
+        hub.clone(),
+    );
+    services::fame::start_batch(database_pool.clone());
+    services::suspension::start_expiry(database_pool.clone());
+    let presence = services::presence::Presence::start(database_pool.clone());
+    let socket_io = socketio::SocketIo::start(&presence);
+    let geoip = services::geoip::GeoIp::open(config.geoip_database_path.as_deref())?;
+    if !geoip.is_enabled() {
+        info!("No GeoIP database configured, IP-based location fallback is disabled");
+    }
+    let geo = services::geo::Geo::open(config.gazetteer_path.as_deref())?;
+    match geo.gazetteer() {
+        Some(gazetteer) => info!("Loaded gazetteer with {} places", gazetteer.place_count()),
+        None => info!("No gazetteer configured, locations will not be named"),
+    }
     let app_state = AppState {
         db: database_pool,
         jwt_service,
+        jobs,
+        fame,
+        storage,
+        media_signer,
+        cursor_signer,
+        geoip: Arc::new(geoip),
+        geo: Arc::new(geo),
+        browse_weights: config.browse_weights,
+        presence,
+        hub,
+        socket_io,
+        trusted_proxies: config.trusted_proxies.clone().into(),
     };
 
     let cors = CorsLayer::new()
@@ -74,6 +125,7 @@ async fn main() -> anyhow::Result<()> {
         .route("/health", get(routes::health_check))
         .nest("/api", routes::create_router(app_state.clone()))
         .nest("/ws", routes::websocket::create_router(app_state.clone()))
+        .nest("/media", routes::media::create_router())
         .layer(
             ServiceBuilder::new()
                 .layer(TraceLayer::new_for_http())
@@ -85,7 +137,11 @@ async fn main() -> anyhow::Result<()> {
     let addr = SocketAddr::from(([127, 0, 0, 1], config.port));
     info!("Server listening on {}", addr);
 
-    axum::serve(tokio::net::TcpListener::bind(addr).await?, app.into_make_service()).await?;
+    axum::serve(
+        tokio::net::TcpListener::bind(addr).await?,
+        app.into_make_service_with_connect_info::<SocketAddr>(),
+    )
+    .await?;
 
     Ok(())
 }
@@ -7,6 +7,8 @@ use axum::{
 use axum_extra::extract::cookie::CookieJar;
 
 use crate::database::user_repository::UserRepository;
+use crate::database::RoleRepository;
+use crate::enums::Role;
 use crate::models::User;
 use crate::AppState;
 
@@ -74,6 +76,8 @@ pub async fn require_auth(
 
                 if let Ok(Some(user)) = user_repo.find_by_id(user_id).await {
                     if user.is_active() {
+                        state.presence.touch(user.id);
+
                         // Add user to request extensions
                         let mut request = request;
                         request.extensions_mut().insert(AuthUser { user });
@@ -91,3 +95,52 @@ pub async fn require_auth(
     // If no valid auth token, return unauthorized
     Err(StatusCode::UNAUTHORIZED)
 }
+
+/// Lets moderators and admins through. Must run inside `require_auth`; handlers can then
+/// read the caller's `Role` from the request extensions.
+pub async fn require_moderator(
+    State(state): State<AppState>,
+    request: Request,
+    next: Next,
+) -> Result<Response, StatusCode> {
+    require_role(state, Role::Moderator, request, next).await
+}
+
+/// Lets only admins through. Must run inside `require_auth`.
+pub async fn require_admin(
+    State(state): State<AppState>,
+    request: Request,
+    next: Next,
+) -> Result<Response, StatusCode> {
+    require_role(state, Role::Admin, request, next).await
+}
+
+async fn require_role(
+    state: AppState,
+    minimum: Role,
+    mut request: Request,
+    next: Next,
+) -> Result<Response, StatusCode> {
+    let role = match request.extensions().get::<Role>() {
+        // An outer layer already looked it up
+        Some(role) => *role,
+        None => {
+            let user_id = request
+                .extensions()
+                .get::<AuthUser>()
+                .map(|auth_user| auth_user.user.id)
+                .ok_or(StatusCode::UNAUTHORIZED)?;
+            RoleRepository::new(state.db)
+                .find_role(user_id)
+                .await
+                .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        }
+    };
+
+    if role < minimum {
+        return Err(StatusCode::FORBIDDEN);
+    }
+
+    request.extensions_mut().insert(role);
+    Ok(next.run(request).await)
+}
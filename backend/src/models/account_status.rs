@@ -0,0 +1,56 @@
+use crate::enums::AccountStatus;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use uuid::Uuid;
+
+/// An entry of an account's status history.
+#[derive(Debug, Clone, Serialize)]
+pub struct AccountStatusChange {
+    pub id: Uuid,
+    pub status: AccountStatus,
+    pub previous_status: AccountStatus,
+    pub changed_by: Option<Uuid>,
+    pub changed_by_username: Option<String>,
+    pub reason: Option<String>,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl AccountStatusChange {
+    /// Whether this is a suspension whose end has passed.
+    pub fn has_expired(&self, now: DateTime<Utc>) -> bool {
+        self.status == AccountStatus::Suspended
+            && self.expires_at.is_some_and(|expires_at| expires_at <= now)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn change(status: AccountStatus, expires_at: Option<DateTime<Utc>>) -> AccountStatusChange {
+        AccountStatusChange {
+            id: Uuid::nil(),
+            status,
+            previous_status: AccountStatus::Active,
+            changed_by: None,
+            changed_by_username: None,
+            reason: None,
+            expires_at,
+            created_at: DateTime::from_timestamp(1_800_000_000, 0).unwrap(),
+        }
+    }
+
+    #[test]
+    fn only_timed_suspensions_expire() {
+        let now = DateTime::from_timestamp(1_800_000_100, 0).unwrap();
+        let earlier = Some(now - chrono::Duration::seconds(1));
+        let later = Some(now + chrono::Duration::seconds(1));
+
+        assert!(change(AccountStatus::Suspended, earlier).has_expired(now));
+        assert!(change(AccountStatus::Suspended, Some(now)).has_expired(now));
+        assert!(!change(AccountStatus::Suspended, later).has_expired(now));
+        assert!(!change(AccountStatus::Suspended, None).has_expired(now));
+        assert!(!change(AccountStatus::Banned, earlier).has_expired(now));
+    }
+}
@@ -0,0 +1,10 @@
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+/// Someone a user blocked.
+#[derive(Debug, Clone)]
+pub struct BlockedUser {
+    pub blocked_id: Uuid,
+    pub blocked_username: String,
+    pub blocked_at: DateTime<Utc>,
+}
@@ -0,0 +1,128 @@
+use crate::validation::core::{ValidationError, ValidationResult};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use uuid::Uuid;
+
+pub const MAX_MESSAGE_LENGTH: usize = 2000;
+
+/// Characters of the last message shown in the conversation list.
+pub const PREVIEW_LENGTH: usize = 100;
+
+/// Most consecutive line breaks kept in a message.
+const MAX_LINE_BREAKS: usize = 2;
+
+/// Cleans up a message body: line endings become `\n`, control characters other than line
+/// breaks and tabs are dropped along with bidirectional overrides that could disguise the
+/// text, runs of blank lines are shortened and the result is trimmed. Markup is left as
+/// typed, clients show message bodies as plain text.
+pub fn validate_message_body(body: &str) -> ValidationResult<String> {
+    let mut sanitized = String::with_capacity(body.len());
+    let mut line_breaks = 0;
+    for c in body.replace("\r\n", "\n").chars() {
+        let c = if c == '\r' { '\n' } else { c };
+        if (c.is_control() && c != '\n' && c != '\t') || is_bidi_control(c) {
+            continue;
+        }
+        if c == '\n' {
+            line_breaks += 1;
+            if line_breaks > MAX_LINE_BREAKS {
+                continue;
+            }
+        } else {
+            line_breaks = 0;
+        }
+        sanitized.push(c);
+    }
+
+    let sanitized = sanitized.trim();
+    if sanitized.is_empty() {
+        return Err(ValidationError::new("body", "Message cannot be empty"));
+    }
+    if sanitized.chars().count() > MAX_MESSAGE_LENGTH {
+        return Err(ValidationError::new(
+            "body",
+            &format!("Message must be at most {} characters", MAX_MESSAGE_LENGTH),
+        ));
+    }
+    Ok(sanitized.to_string())
+}
+
+fn is_bidi_control(c: char) -> bool {
+    matches!(c, '\u{202A}'..='\u{202E}' | '\u{2066}'..='\u{2069}')
+}
+
+/// The start of a message on a single line.
+pub fn preview(body: &str) -> String {
+    let line = body.split_whitespace().collect::<Vec<_>>().join(" ");
+    if line.chars().count() <= PREVIEW_LENGTH {
+        return line;
+    }
+    let mut preview: String = line.chars().take(PREVIEW_LENGTH - 1).collect();
+    preview.truncate(preview.trim_end().len());
+    preview.push('…');
+    preview
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Message {
+    pub id: Uuid,
+    pub conversation_id: Uuid,
+    pub sender_id: Uuid,
+    pub body: String,
+    pub created_at: DateTime<Utc>,
+}
+
+/// The last message of a conversation as the list shows it.
+#[derive(Debug, Clone, Serialize)]
+pub struct MessagePreview {
+    pub sender_id: Uuid,
+    pub body: String,
+    pub created_at: DateTime<Utc>,
+}
+
+/// A conversation as one of its two users sees it.
+#[derive(Debug, Clone, Serialize)]
+pub struct Conversation {
+    pub id: Uuid,
+    pub other_user_id: Uuid,
+    pub other_username: String,
+    pub last_message: MessagePreview,
+    pub unread_count: i64,
+    pub last_message_at: DateTime<Utc>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sanitizes_message_bodies() {
+        assert_eq!(validate_message_body("  Hi there \n").unwrap(), "Hi there");
+        assert_eq!(validate_message_body("a\r\nb\rc").unwrap(), "a\nb\nc");
+        assert_eq!(validate_message_body("a\n\n\n\n\nb").unwrap(), "a\n\nb");
+        assert_eq!(validate_message_body("a\u{0}b\u{7}\tc").unwrap(), "ab\tc");
+        assert_eq!(validate_message_body("abc\u{202E}gpj.exe").unwrap(), "abcgpj.exe");
+        assert_eq!(validate_message_body("<b>hi</b>").unwrap(), "<b>hi</b>");
+    }
+
+    #[test]
+    fn rejects_empty_and_long_messages() {
+        assert!(validate_message_body("").is_err());
+        assert!(validate_message_body(" \n\u{0}\u{202E} ").is_err());
+
+        let longest = "é".repeat(MAX_MESSAGE_LENGTH);
+        assert_eq!(validate_message_body(&longest).unwrap(), longest);
+        assert!(validate_message_body(&format!("{}x", longest)).is_err());
+    }
+
+    #[test]
+    fn previews_fit_on_one_line() {
+        assert_eq!(preview("Hello\n\nthere  you"), "Hello there you");
+
+        let long = "word ".repeat(50);
+        let preview = preview(&long);
+        assert!(preview.ends_with('…'));
+        assert!(preview.chars().count() <= PREVIEW_LENGTH);
+        assert!(!preview.contains(" …"));
+    }
+}
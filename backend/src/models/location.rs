@@ -0,0 +1,148 @@
+use crate::enums::LocationSource;
+use crate::validation::core::{ValidationError, ValidationResult};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+// Browsers report accuracies in meters; anything beyond this is no better than the IP fallback
+const MAX_ACCURACY_M: f64 = 100_000.0;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct UserLocation {
+    pub latitude: f64,
+    pub longitude: f64,
+    pub accuracy_m: Option<f64>,
+    pub source: LocationSource,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// What other users get to see: coarsened to neighborhood level and named where possible.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PublicLocation {
+    pub latitude: f64,
+    pub longitude: f64,
+    pub neighborhood: Option<String>,
+    pub city: Option<String>,
+    pub country_code: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Coordinates {
+    pub latitude: f64,
+    pub longitude: f64,
+}
+
+impl UserLocation {
+    pub fn from_row(
+        latitude: f64,
+        longitude: f64,
+        accuracy_m: Option<f64>,
+        source: String,
+        updated_at: DateTime<Utc>,
+    ) -> Result<Self, anyhow::Error> {
+        let source = source
+            .parse::<LocationSource>()
+            .map_err(|_| anyhow::anyhow!("Invalid location source: {}", source))?;
+
+        Ok(UserLocation {
+            latitude,
+            longitude,
+            accuracy_m,
+            source,
+            updated_at,
+        })
+    }
+
+    pub fn coordinates(&self) -> Coordinates {
+        Coordinates {
+            latitude: self.latitude,
+            longitude: self.longitude,
+        }
+    }
+}
+
+impl PublicLocation {
+    /// "Kreuzberg, Berlin", or whichever part is known.
+    pub fn display_name(&self) -> Option<String> {
+        match (&self.neighborhood, &self.city) {
+            (Some(neighborhood), Some(city)) if neighborhood != city => {
+                Some(format!("{}, {}", neighborhood, city))
+            }
+            (Some(name), _) | (None, Some(name)) => Some(name.clone()),
+            (None, None) => None,
+        }
+    }
+}
+
+impl Coordinates {
+    pub fn new(latitude: f64, longitude: f64) -> ValidationResult<Self> {
+        if !(-90.0..=90.0).contains(&latitude) {
+            return Err(ValidationError::new("latitude", "Latitude must be between -90 and 90"));
+        }
+        if !(-180.0..=180.0).contains(&longitude) {
+            return Err(ValidationError::new(
+                "longitude",
+                "Longitude must be between -180 and 180",
+            ));
+        }
+
+        Ok(Self {
+            latitude,
+            longitude,
+        })
+    }
+}
+
+pub fn validate_accuracy(accuracy_m: Option<f64>) -> ValidationResult<Option<f64>> {
+    match accuracy_m {
+        Some(accuracy) if !(0.0..=MAX_ACCURACY_M).contains(&accuracy) => Err(ValidationError::new(
+            "accuracy",
+            &format!("Accuracy must be between 0 and {} meters", MAX_ACCURACY_M),
+        )),
+        _ => Ok(accuracy_m),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn accepts_valid_coordinates() {
+        let coordinates = Coordinates::new(52.4986, 13.4030).unwrap();
+        assert_eq!(coordinates.latitude, 52.4986);
+        assert!(Coordinates::new(-90.0, 180.0).is_ok());
+    }
+
+    #[test]
+    fn rejects_out_of_range_coordinates() {
+        assert_eq!(Coordinates::new(91.0, 0.0).unwrap_err().field, "latitude");
+        assert_eq!(Coordinates::new(0.0, -180.5).unwrap_err().field, "longitude");
+        assert!(Coordinates::new(f64::NAN, 0.0).is_err());
+    }
+
+    #[test]
+    fn names_public_locations() {
+        let mut location = PublicLocation {
+            latitude: 52.4987,
+            longitude: 13.4031,
+            neighborhood: Some("Kreuzberg".to_string()),
+            city: Some("Berlin".to_string()),
+            country_code: Some("DE".to_string()),
+        };
+        assert_eq!(location.display_name().unwrap(), "Kreuzberg, Berlin");
+
+        location.city = None;
+        assert_eq!(location.display_name().unwrap(), "Kreuzberg");
+
+        location.neighborhood = None;
+        assert_eq!(location.display_name(), None);
+    }
+
+    #[test]
+    fn validates_accuracy() {
+        assert_eq!(validate_accuracy(None).unwrap(), None);
+        assert_eq!(validate_accuracy(Some(25.0)).unwrap(), Some(25.0));
+        assert!(validate_accuracy(Some(-1.0)).is_err());
+        assert!(validate_accuracy(Some(MAX_ACCURACY_M * 2.0)).is_err());
+    }
+}
@@ -1,3 +1,28 @@
+pub mod account_status;
+pub mod block;
+pub mod chat;
+pub mod location;
+pub mod moderation;
+pub mod notification;
+pub mod page;
+pub mod picture;
+pub mod profile;
+pub mod report;
+pub mod search;
 pub mod user;
+pub mod user_view;
+pub mod visit;
 
+pub use account_status::AccountStatusChange;
+pub use block::BlockedUser;
+pub use chat::{Conversation, Message, MessagePreview};
+pub use location::{Coordinates, PublicLocation, UserLocation};
+pub use moderation::{ModerationAction, ModerationItem, NewModerationAction, Report};
+pub use notification::Notification;
+pub use page::Page;
+pub use picture::{Picture, PictureVariant, PictureVersion};
+pub use profile::Profile;
+pub use search::{SearchCriteria, SearchQuery, SearchResult};
 pub use user::User;
+pub use user_view::{AdminUserView, ConnectionStatus, PublicProfileView, SelfUserView};
+pub use visit::{ProfileVisit, ReceivedLike};
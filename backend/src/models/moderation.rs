@@ -0,0 +1,158 @@
+use crate::enums::{ModerationActionKind, ModerationItemKind, ModerationStatus, ReportReason};
+use crate::validation::core::{ValidationError, ValidationResult};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use serde_json::Value;
+use uuid::Uuid;
+
+pub const MAX_REASON_LENGTH: usize = 1000;
+
+/// Trims the reason a moderator gives for an action, which is required.
+pub fn validate_reason(reason: &str) -> ValidationResult<String> {
+    let reason = reason.trim();
+    if reason.is_empty() {
+        return Err(ValidationError::new("reason", "A reason is required"));
+    }
+    if reason.chars().count() > MAX_REASON_LENGTH {
+        return Err(ValidationError::new(
+            "reason",
+            &format!("Reason must be at most {} characters", MAX_REASON_LENGTH),
+        ));
+    }
+    Ok(reason.to_string())
+}
+
+/// Suspensions without an end last until a moderator lifts them.
+pub fn validate_suspension_end(
+    expires_at: Option<DateTime<Utc>>,
+    now: DateTime<Utc>,
+) -> ValidationResult<Option<DateTime<Utc>>> {
+    match expires_at {
+        Some(expires_at) if expires_at <= now => {
+            Err(ValidationError::new("expires_at", "Suspension must end in the future"))
+        }
+        _ => Ok(expires_at),
+    }
+}
+
+/// A queue item with the user it is about.
+#[derive(Debug, Clone, Serialize)]
+pub struct ModerationItem {
+    pub id: Uuid,
+    pub subject_user_id: Uuid,
+    pub subject_username: String,
+    pub kind: ModerationItemKind,
+    pub status: ModerationStatus,
+    pub details: Value,
+    pub report_count: i64,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// A report as moderators see it. Automatic reports have no reporter and name the account
+/// whose picture matched instead.
+#[derive(Debug, Clone, Serialize)]
+pub struct Report {
+    pub id: Uuid,
+    pub reporter_id: Option<Uuid>,
+    pub reporter_username: Option<String>,
+    pub matched_user_id: Option<Uuid>,
+    pub matched_username: Option<String>,
+    pub reason: ReportReason,
+    pub details: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// An entry of the moderation audit log.
+#[derive(Debug, Clone, Serialize)]
+pub struct ModerationAction {
+    pub id: Uuid,
+    pub kind: ModerationActionKind,
+    pub actor_id: Option<Uuid>,
+    pub actor_username: Option<String>,
+    pub subject_user_id: Uuid,
+    pub subject_username: String,
+    pub reason: Option<String>,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub moderation_item_id: Option<Uuid>,
+    pub details: Value,
+    pub created_at: DateTime<Utc>,
+}
+
+/// A moderation action to record alongside the change it makes.
+#[derive(Debug, Clone, PartialEq)]
+pub struct NewModerationAction {
+    /// `None` for actions the system takes by itself.
+    pub actor_id: Option<Uuid>,
+    pub subject_user_id: Uuid,
+    pub kind: ModerationActionKind,
+    pub reason: Option<String>,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub moderation_item_id: Option<Uuid>,
+    pub details: Value,
+}
+
+impl NewModerationAction {
+    pub fn new(actor_id: Option<Uuid>, subject_user_id: Uuid, kind: ModerationActionKind) -> Self {
+        Self {
+            actor_id,
+            subject_user_id,
+            kind,
+            reason: None,
+            expires_at: None,
+            moderation_item_id: None,
+            details: Value::Object(Default::default()),
+        }
+    }
+
+    pub fn reason(mut self, reason: Option<String>) -> Self {
+        self.reason = reason;
+        self
+    }
+
+    pub fn expires_at(mut self, expires_at: Option<DateTime<Utc>>) -> Self {
+        self.expires_at = expires_at;
+        self
+    }
+
+    pub fn moderation_item(mut self, id: Uuid) -> Self {
+        self.moderation_item_id = Some(id);
+        self
+    }
+
+    pub fn details(mut self, details: Value) -> Self {
+        self.details = details;
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn validates_reasons() {
+        assert_eq!(validate_reason("  Fake photos ").unwrap(), "Fake photos");
+        assert!(validate_reason("   ").is_err());
+        assert!(validate_reason(&"x".repeat(MAX_REASON_LENGTH + 1)).is_err());
+    }
+
+    #[test]
+    fn validates_suspension_ends() {
+        let now = Utc::now();
+
+        assert_eq!(validate_suspension_end(None, now).unwrap(), None);
+        assert!(validate_suspension_end(Some(now + chrono::Duration::days(1)), now).is_ok());
+        assert!(validate_suspension_end(Some(now), now).is_err());
+    }
+
+    #[test]
+    fn starts_actions_without_optional_fields() {
+        let action = NewModerationAction::new(None, Uuid::nil(), ModerationActionKind::Suspend)
+            .reason(Some("Spam".to_string()));
+
+        assert_eq!(action.reason.as_deref(), Some("Spam"));
+        assert_eq!(action.expires_at, None);
+        assert_eq!(action.details, serde_json::json!({}));
+    }
+}
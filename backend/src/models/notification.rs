@@ -0,0 +1,38 @@
+use crate::enums::NotificationKind;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use uuid::Uuid;
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Notification {
+    pub id: Uuid,
+    pub kind: NotificationKind,
+    pub actor_id: Option<Uuid>,
+    pub actor_username: Option<String>,
+    pub read_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl Notification {
+    pub fn from_row(
+        id: Uuid,
+        kind: String,
+        actor_id: Option<Uuid>,
+        actor_username: Option<String>,
+        read_at: Option<DateTime<Utc>>,
+        created_at: DateTime<Utc>,
+    ) -> Result<Self, anyhow::Error> {
+        let kind = kind
+            .parse::<NotificationKind>()
+            .map_err(|_| anyhow::anyhow!("Invalid notification kind: {}", kind))?;
+
+        Ok(Notification {
+            id,
+            kind,
+            actor_id,
+            actor_username,
+            read_at,
+            created_at,
+        })
+    }
+}
@@ -0,0 +1,69 @@
+use serde::Serialize;
+
+/// Response envelope shared by every paginated list.
+///
+/// `next_cursor` is absent on the last page; otherwise passing it back as `cursor`
+/// continues right after the last item, even while new items keep arriving.
+#[derive(Debug, Clone, Serialize)]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    pub next_cursor: Option<String>,
+}
+
+impl<T> Page<T> {
+    /// Builds a page from up to `limit + 1` items, the extra one only signalling that more follow.
+    pub fn from_overfetched(
+        mut items: Vec<T>,
+        limit: usize,
+        cursor_for: impl FnOnce(&T) -> String,
+    ) -> Self {
+        let has_more = items.len() > limit;
+        items.truncate(limit);
+        let next_cursor = if has_more {
+            items.last().map(cursor_for)
+        } else {
+            None
+        };
+
+        Page { items, next_cursor }
+    }
+
+    pub fn map<U>(self, f: impl FnMut(T) -> U) -> Page<U> {
+        Page {
+            items: self.items.into_iter().map(f).collect(),
+            next_cursor: self.next_cursor,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn points_the_cursor_at_the_last_item_when_more_follow() {
+        let page = Page::from_overfetched(vec![1, 2, 3, 4], 3, |item| item.to_string());
+
+        assert_eq!(page.items, vec![1, 2, 3]);
+        assert_eq!(page.next_cursor.as_deref(), Some("3"));
+    }
+
+    #[test]
+    fn ends_without_a_cursor() {
+        let page = Page::from_overfetched(vec![1, 2, 3], 3, |item| item.to_string());
+        assert_eq!(page.items, vec![1, 2, 3]);
+        assert_eq!(page.next_cursor, None);
+
+        let empty = Page::from_overfetched(Vec::<i32>::new(), 3, |item| item.to_string());
+        assert!(empty.items.is_empty());
+        assert_eq!(empty.next_cursor, None);
+    }
+
+    #[test]
+    fn maps_items_and_keeps_the_cursor() {
+        let page = Page::from_overfetched(vec![1, 2], 1, |item| item.to_string()).map(|n| n * 10);
+
+        assert_eq!(page.items, vec![10]);
+        assert_eq!(page.next_cursor.as_deref(), Some("1"));
+    }
+}
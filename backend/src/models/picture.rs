@@ -0,0 +1,71 @@
+use crate::enums::PictureStatus;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use uuid::Uuid;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Picture {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub is_profile_picture: bool,
+    #[serde(skip_serializing)]
+    pub original_path: String,
+    pub status: PictureStatus,
+    pub width: Option<i32>,
+    pub height: Option<i32>,
+    pub processed_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone)]
+pub struct PictureVariant {
+    pub picture_id: Uuid,
+    pub variant: String,
+    pub format: String,
+    pub path: String,
+    pub width: i32,
+    pub height: i32,
+}
+
+#[derive(Debug, Clone)]
+pub struct PictureVersion {
+    pub version: i32,
+    pub edits: serde_json::Value,
+    /// `None` while the version is being rendered; the previous one is shown meanwhile.
+    pub rendered_at: Option<DateTime<Utc>>,
+}
+
+impl Picture {
+    #[allow(clippy::too_many_arguments)]
+    pub fn from_row(
+        id: Uuid,
+        user_id: Uuid,
+        is_profile_picture: bool,
+        original_path: String,
+        status: String,
+        width: Option<i32>,
+        height: Option<i32>,
+        processed_at: Option<DateTime<Utc>>,
+        created_at: DateTime<Utc>,
+    ) -> Result<Self, anyhow::Error> {
+        let status = status
+            .parse::<PictureStatus>()
+            .map_err(|_| anyhow::anyhow!("Invalid picture status: {}", status))?;
+
+        Ok(Picture {
+            id,
+            user_id,
+            is_profile_picture,
+            original_path,
+            status,
+            width,
+            height,
+            processed_at,
+            created_at,
+        })
+    }
+
+    pub fn is_ready(&self) -> bool {
+        self.status == PictureStatus::Ready
+    }
+}
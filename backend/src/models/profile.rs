@@ -0,0 +1,85 @@
+use crate::enums::{Gender, SexualPreference};
+use chrono::{Datelike, NaiveDate};
+use serde::Serialize;
+use uuid::Uuid;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Profile {
+    pub user_id: Uuid,
+    pub gender: Option<Gender>,
+    pub sexual_preference: Option<SexualPreference>,
+    pub biography: Option<String>,
+    pub birth_date: Option<NaiveDate>,
+    pub fame_rating: f64,
+}
+
+impl Profile {
+    pub fn from_row(
+        user_id: Uuid,
+        gender: Option<String>,
+        sexual_preference: Option<String>,
+        biography: Option<String>,
+        birth_date: Option<NaiveDate>,
+        fame_rating: f64,
+    ) -> Result<Self, anyhow::Error> {
+        let gender = gender
+            .map(|gender| {
+                gender
+                    .parse::<Gender>()
+                    .map_err(|_| anyhow::anyhow!("Invalid gender: {}", gender))
+            })
+            .transpose()?;
+        let sexual_preference = sexual_preference
+            .map(|preference| {
+                preference
+                    .parse::<SexualPreference>()
+                    .map_err(|_| anyhow::anyhow!("Invalid sexual preference: {}", preference))
+            })
+            .transpose()?;
+
+        Ok(Profile {
+            user_id,
+            gender,
+            sexual_preference,
+            biography,
+            birth_date,
+            fame_rating,
+        })
+    }
+
+    /// Age in full years on `today`, if the birth date is known.
+    pub fn age_on(&self, today: NaiveDate) -> Option<i32> {
+        self.birth_date.map(|birth_date| {
+            let had_birthday =
+                (today.month(), today.day()) >= (birth_date.month(), birth_date.day());
+            today.year() - birth_date.year() - i32::from(!had_birthday)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn born(birth_date: Option<NaiveDate>) -> Profile {
+        Profile {
+            user_id: Uuid::nil(),
+            gender: None,
+            sexual_preference: None,
+            biography: None,
+            birth_date,
+            fame_rating: 0.0,
+        }
+    }
+
+    #[test]
+    fn counts_full_years() {
+        let date = |y, m, d| NaiveDate::from_ymd_opt(y, m, d).unwrap();
+        let profile = born(Some(date(1995, 3, 1)));
+
+        assert_eq!(profile.age_on(date(2026, 2, 28)), Some(30));
+        assert_eq!(profile.age_on(date(2026, 3, 1)), Some(31));
+        assert_eq!(born(Some(date(2000, 2, 29))).age_on(date(2026, 2, 28)), Some(25));
+        assert_eq!(born(None).age_on(date(2026, 3, 1)), None);
+    }
+}
@@ -0,0 +1,43 @@
+use crate::enums::ReportReason;
+use crate::validation::core::{ValidationError, ValidationResult};
+
+pub const MAX_REPORT_DETAILS_LENGTH: usize = 1000;
+
+/// Trims the free-text details of a report, which only `Other` requires.
+pub fn validate_report_details(
+    reason: ReportReason,
+    details: Option<&str>,
+) -> ValidationResult<Option<String>> {
+    let details = details.map(str::trim).filter(|details| !details.is_empty());
+    match details {
+        None if reason == ReportReason::Other => {
+            Err(ValidationError::new("details", "Please describe the problem"))
+        }
+        Some(details) if details.chars().count() > MAX_REPORT_DETAILS_LENGTH => {
+            Err(ValidationError::new(
+                "details",
+                &format!("Details must be at most {} characters", MAX_REPORT_DETAILS_LENGTH),
+            ))
+        }
+        _ => Ok(details.map(str::to_string)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn validates_report_details() {
+        assert_eq!(validate_report_details(ReportReason::Spam, None).unwrap(), None);
+        assert_eq!(validate_report_details(ReportReason::Spam, Some("  ")).unwrap(), None);
+        assert_eq!(
+            validate_report_details(ReportReason::Other, Some(" Fake photos ")).unwrap(),
+            Some("Fake photos".to_string())
+        );
+        assert!(validate_report_details(ReportReason::Other, Some(" ")).is_err());
+
+        let long = "x".repeat(MAX_REPORT_DETAILS_LENGTH + 1);
+        assert!(validate_report_details(ReportReason::FakeAccount, Some(&long)).is_err());
+    }
+}
@@ -0,0 +1,336 @@
+use crate::enums::{SearchSort, SortOrder, TagMode};
+use crate::models::{Coordinates, PublicLocation, User};
+use crate::validation::core::{ValidationError, ValidationResult};
+use serde::Deserialize;
+
+// Users must be adults, so younger bounds could never match anyone
+const MIN_AGE: i32 = 18;
+const MAX_AGE: i32 = 120;
+const MAX_FAME: f64 = 100.0;
+const MAX_DISTANCE_KM: f64 = 20_000.0;
+const MAX_TAGS: usize = 10;
+const MAX_TAG_LENGTH: usize = 50;
+
+/// Query string of `GET /api/users/search`. Tags are comma-separated, and `location`
+/// names a place to search around instead of the user's own location.
+#[derive(Debug, Default, Deserialize)]
+pub struct SearchQuery {
+    pub min_age: Option<i32>,
+    pub max_age: Option<i32>,
+    pub min_fame: Option<f64>,
+    pub max_fame: Option<f64>,
+    pub max_distance_km: Option<f64>,
+    pub location: Option<String>,
+    pub tags: Option<String>,
+    pub tag_mode: Option<TagMode>,
+    pub min_common_tags: Option<i64>,
+    pub sort: Option<SearchSort>,
+    pub order: Option<SortOrder>,
+    pub limit: Option<i64>,
+    pub cursor: Option<String>,
+}
+
+/// Validated search filters, ready to be compiled into a query.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SearchCriteria {
+    pub min_age: Option<i32>,
+    pub max_age: Option<i32>,
+    pub min_fame: Option<f64>,
+    pub max_fame: Option<f64>,
+    pub max_distance_km: Option<f64>,
+    pub tags: Vec<String>,
+    pub tag_mode: TagMode,
+    pub min_common_tags: Option<i64>,
+    pub sort: SearchSort,
+    pub order: SortOrder,
+}
+
+#[derive(Debug, Clone)]
+pub struct SearchResult {
+    pub user: User,
+    pub location: Option<PublicLocation>,
+    pub distance_km: Option<f64>,
+    pub age: Option<i32>,
+    pub fame_rating: f64,
+    pub common_tags: i64,
+}
+
+impl SearchCriteria {
+    pub fn from_query(query: &SearchQuery) -> ValidationResult<Self> {
+        for (field, age) in [("min_age", query.min_age), ("max_age", query.max_age)] {
+            if age.is_some_and(|age| !(MIN_AGE..=MAX_AGE).contains(&age)) {
+                return Err(ValidationError::new(
+                    field,
+                    &format!("Age must be between {} and {}", MIN_AGE, MAX_AGE),
+                ));
+            }
+        }
+        if let (Some(min_age), Some(max_age)) = (query.min_age, query.max_age) {
+            if min_age > max_age {
+                return Err(ValidationError::new(
+                    "max_age",
+                    "Maximum age must not be below the minimum age",
+                ));
+            }
+        }
+
+        for (field, fame) in [("min_fame", query.min_fame), ("max_fame", query.max_fame)] {
+            if fame.is_some_and(|fame| !(0.0..=MAX_FAME).contains(&fame)) {
+                return Err(ValidationError::new(
+                    field,
+                    &format!("Fame rating must be between 0 and {}", MAX_FAME),
+                ));
+            }
+        }
+        if let (Some(min_fame), Some(max_fame)) = (query.min_fame, query.max_fame) {
+            if min_fame > max_fame {
+                return Err(ValidationError::new(
+                    "max_fame",
+                    "Maximum fame rating must not be below the minimum",
+                ));
+            }
+        }
+
+        if let Some(distance) = query.max_distance_km {
+            if !(distance > 0.0 && distance <= MAX_DISTANCE_KM) {
+                return Err(ValidationError::new(
+                    "max_distance_km",
+                    &format!("Distance must be between 0 and {} km", MAX_DISTANCE_KM),
+                ));
+            }
+        }
+
+        if query.min_common_tags.is_some_and(|count| count < 0) {
+            return Err(ValidationError::new(
+                "min_common_tags",
+                "Common tag count must not be negative",
+            ));
+        }
+
+        let sort = query.sort.unwrap_or(SearchSort::Distance);
+
+        Ok(SearchCriteria {
+            min_age: query.min_age,
+            max_age: query.max_age,
+            min_fame: query.min_fame,
+            max_fame: query.max_fame,
+            max_distance_km: query.max_distance_km,
+            tags: parse_tags(query.tags.as_deref().unwrap_or(""))?,
+            tag_mode: query.tag_mode.unwrap_or(TagMode::Any),
+            min_common_tags: query.min_common_tags,
+            sort,
+            order: query.order.unwrap_or(sort.default_order()),
+        })
+    }
+
+    /// Whether the query needs to know where the searching user is.
+    pub fn needs_origin(&self) -> bool {
+        self.max_distance_km.is_some() || self.sort == SearchSort::Distance
+    }
+
+    /// Names the result list a cursor is issued for. Every filter and the centre of the search
+    /// are part of it, so a cursor cannot be carried over to a search with other results.
+    pub fn cursor_scope(&self, origin: Option<Coordinates>) -> String {
+        fn part<T: ToString>(value: Option<T>) -> String {
+            value.map(|value| value.to_string()).unwrap_or_default()
+        }
+
+        format!(
+            "search:{}:{}:{}:{}:{}:{}:{}:{}:{}:{}:{}",
+            self.sort,
+            self.order,
+            part(self.min_age),
+            part(self.max_age),
+            part(self.min_fame),
+            part(self.max_fame),
+            part(self.max_distance_km),
+            self.tag_mode,
+            self.tags.join(","),
+            part(self.min_common_tags),
+            part(origin.map(|origin| format!("{},{}", origin.latitude, origin.longitude))),
+        )
+    }
+}
+
+/// Splits a comma-separated tag list, ignoring case, blanks, duplicates and a leading `#`.
+fn parse_tags(tags: &str) -> ValidationResult<Vec<String>> {
+    let mut parsed: Vec<String> = Vec::new();
+    for tag in tags.split(',') {
+        let tag = tag.trim().trim_start_matches('#').to_lowercase();
+        if tag.is_empty() || parsed.contains(&tag) {
+            continue;
+        }
+        if tag.len() > MAX_TAG_LENGTH
+            || !tag
+                .chars()
+                .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_')
+        {
+            return Err(ValidationError::new(
+                "tags",
+                "Tags may only contain letters, digits and underscores",
+            ));
+        }
+        parsed.push(tag);
+    }
+
+    if parsed.len() > MAX_TAGS {
+        return Err(ValidationError::new(
+            "tags",
+            &format!("At most {} tags can be searched at once", MAX_TAGS),
+        ));
+    }
+
+    Ok(parsed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn defaults_to_the_closest_users_with_any_tag() {
+        let criteria = SearchCriteria::from_query(&SearchQuery::default()).unwrap();
+
+        assert_eq!(criteria.sort, SearchSort::Distance);
+        assert_eq!(criteria.order, SortOrder::Asc);
+        assert_eq!(criteria.tag_mode, TagMode::Any);
+        assert!(criteria.tags.is_empty());
+        assert!(criteria.needs_origin());
+    }
+
+    #[test]
+    fn orders_by_the_sort_default_unless_given() {
+        let query = SearchQuery {
+            sort: Some(SearchSort::Fame),
+            ..Default::default()
+        };
+        let criteria = SearchCriteria::from_query(&query).unwrap();
+        assert_eq!(criteria.order, SortOrder::Desc);
+        assert!(!criteria.needs_origin());
+
+        let query = SearchQuery {
+            sort: Some(SearchSort::Fame),
+            order: Some(SortOrder::Asc),
+            ..Default::default()
+        };
+        assert_eq!(SearchCriteria::from_query(&query).unwrap().order, SortOrder::Asc);
+    }
+
+    #[test]
+    fn scopes_cursors_to_the_filters_and_origin() {
+        let query = SearchQuery {
+            min_age: Some(25),
+            tags: Some("music".to_string()),
+            ..Default::default()
+        };
+        let criteria = SearchCriteria::from_query(&query).unwrap();
+        let origin = Coordinates {
+            latitude: 48.8566,
+            longitude: 2.3522,
+        };
+        let scope = criteria.cursor_scope(Some(origin));
+
+        assert_eq!(scope, criteria.cursor_scope(Some(origin)));
+        assert_ne!(scope, criteria.cursor_scope(None));
+        assert_ne!(
+            scope,
+            criteria.cursor_scope(Some(Coordinates {
+                latitude: 45.764,
+                ..origin
+            }))
+        );
+
+        let widened = SearchQuery {
+            min_age: Some(26),
+            tags: Some("music".to_string()),
+            ..Default::default()
+        };
+        assert_ne!(
+            scope,
+            SearchCriteria::from_query(&widened)
+                .unwrap()
+                .cursor_scope(Some(origin))
+        );
+    }
+
+    #[test]
+    fn normalizes_tags() {
+        assert_eq!(
+            parse_tags("Music, #travel,,music , vegan_food").unwrap(),
+            vec!["music", "travel", "vegan_food"]
+        );
+        assert!(parse_tags("").unwrap().is_empty());
+    }
+
+    #[test]
+    fn rejects_invalid_tags() {
+        assert_eq!(
+            parse_tags("music,'; DROP TABLE users;--")
+                .unwrap_err()
+                .field,
+            "tags"
+        );
+        assert!(parse_tags(&"a".repeat(MAX_TAG_LENGTH + 1)).is_err());
+
+        let too_many: Vec<String> = (0..=MAX_TAGS).map(|i| format!("tag{}", i)).collect();
+        assert!(parse_tags(&too_many.join(",")).is_err());
+    }
+
+    #[test]
+    fn rejects_out_of_range_bounds() {
+        let invalid = [
+            SearchQuery {
+                min_age: Some(17),
+                ..Default::default()
+            },
+            SearchQuery {
+                max_age: Some(121),
+                ..Default::default()
+            },
+            SearchQuery {
+                min_fame: Some(-1.0),
+                ..Default::default()
+            },
+            SearchQuery {
+                max_fame: Some(101.0),
+                ..Default::default()
+            },
+            SearchQuery {
+                max_distance_km: Some(0.0),
+                ..Default::default()
+            },
+            SearchQuery {
+                min_common_tags: Some(-1),
+                ..Default::default()
+            },
+        ];
+
+        for query in invalid {
+            assert!(SearchCriteria::from_query(&query).is_err(), "{:?}", query);
+        }
+    }
+
+    #[test]
+    fn rejects_inverted_ranges() {
+        let query = SearchQuery {
+            min_age: Some(30),
+            max_age: Some(25),
+            ..Default::default()
+        };
+        assert_eq!(SearchCriteria::from_query(&query).unwrap_err().field, "max_age");
+
+        let query = SearchQuery {
+            min_fame: Some(50.0),
+            max_fame: Some(10.0),
+            ..Default::default()
+        };
+        assert_eq!(SearchCriteria::from_query(&query).unwrap_err().field, "max_fame");
+
+        let query = SearchQuery {
+            min_age: Some(25),
+            max_age: Some(25),
+            ..Default::default()
+        };
+        assert!(SearchCriteria::from_query(&query).is_ok());
+    }
+}
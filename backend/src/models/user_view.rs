@@ -0,0 +1,194 @@
+use crate::enums::{AccountStatus, Gender, Role, SexualPreference};
+use crate::models::{Profile, User};
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::Serialize;
+use serde_json::Value;
+use uuid::Uuid;
+
+// `User` deliberately does not implement `Serialize`: responses go through one of these
+// views, each of which copies only the fields its audience may see.
+
+/// An account as its owner sees it.
+#[derive(Debug, Clone, Serialize)]
+pub struct SelfUserView {
+    pub id: Uuid,
+    pub email: String,
+    pub username: String,
+    pub email_verified: bool,
+    pub account_status: AccountStatus,
+    pub created_at: DateTime<Utc>,
+}
+
+impl From<&User> for SelfUserView {
+    fn from(user: &User) -> Self {
+        Self {
+            id: user.id,
+            email: user.email.clone(),
+            username: user.username.clone(),
+            email_verified: user.is_email_verified(),
+            account_status: user.account_status.clone(),
+            created_at: user.created_at,
+        }
+    }
+}
+
+/// An account as moderators see it: everything but the password hash.
+#[derive(Debug, Clone, Serialize)]
+pub struct AdminUserView {
+    pub id: Uuid,
+    pub email: String,
+    pub username: String,
+    pub email_verified_at: Option<DateTime<Utc>>,
+    pub account_status: AccountStatus,
+    pub role: Role,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    pub deleted_at: Option<DateTime<Utc>>,
+}
+
+impl AdminUserView {
+    pub fn new(user: &User, role: Role) -> Self {
+        Self {
+            id: user.id,
+            email: user.email.clone(),
+            username: user.username.clone(),
+            email_verified_at: user.email_verified_at,
+            account_status: user.account_status.clone(),
+            role,
+            created_at: user.created_at,
+            updated_at: user.updated_at,
+            deleted_at: user.deleted_at,
+        }
+    }
+}
+
+/// Likes between the viewer and the user they are looking at.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize)]
+pub struct ConnectionStatus {
+    pub liked: bool,
+    pub liked_me: bool,
+    pub connected: bool,
+}
+
+impl ConnectionStatus {
+    pub fn new(liked: bool, liked_me: bool) -> Self {
+        Self {
+            liked,
+            liked_me,
+            connected: liked && liked_me,
+        }
+    }
+}
+
+/// A profile as other users see it, without email, password or exact birth date.
+#[derive(Debug, Clone, Serialize)]
+pub struct PublicProfileView {
+    pub id: Uuid,
+    pub username: String,
+    pub gender: Option<Gender>,
+    pub sexual_preference: Option<SexualPreference>,
+    pub biography: Option<String>,
+    pub age: Option<i32>,
+    pub fame_rating: f64,
+    pub tags: Vec<String>,
+    pub pictures: Vec<Value>,
+    pub location: Option<Value>,
+    pub online: bool,
+    pub last_seen_at: Option<DateTime<Utc>>,
+    pub connection: ConnectionStatus,
+}
+
+impl PublicProfileView {
+    /// Starts from the account and profile; the rest is filled in by the caller.
+    pub fn new(user: &User, profile: Option<&Profile>, today: NaiveDate) -> Self {
+        Self {
+            id: user.id,
+            username: user.username.clone(),
+            gender: profile.and_then(|profile| profile.gender),
+            sexual_preference: profile.and_then(|profile| profile.sexual_preference),
+            biography: profile.and_then(|profile| profile.biography.clone()),
+            age: profile.and_then(|profile| profile.age_on(today)),
+            fame_rating: profile.map_or(0.0, |profile| profile.fame_rating),
+            tags: Vec::new(),
+            pictures: Vec::new(),
+            location: None,
+            online: false,
+            last_seen_at: None,
+            connection: ConnectionStatus::default(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn user() -> User {
+        User::from_row(
+            Uuid::nil(),
+            "alice@example.com".to_string(),
+            "alice".to_string(),
+            "$argon2id$secret-hash".to_string(),
+            None,
+            Some("active".to_string()),
+            Utc::now(),
+            Utc::now(),
+            None,
+        )
+        .unwrap()
+    }
+
+    fn today() -> NaiveDate {
+        NaiveDate::from_ymd_opt(2026, 10, 19).unwrap()
+    }
+
+    #[test]
+    fn public_view_hides_credentials() {
+        let profile = Profile {
+            user_id: Uuid::nil(),
+            gender: Some(Gender::Female),
+            sexual_preference: None,
+            biography: Some("Hi".to_string()),
+            birth_date: NaiveDate::from_ymd_opt(1995, 3, 1),
+            fame_rating: 12.5,
+        };
+        let view = PublicProfileView::new(&user(), Some(&profile), today());
+        let json = serde_json::to_string(&view).unwrap();
+
+        assert!(!json.contains("alice@example.com"));
+        assert!(!json.contains("secret-hash"));
+        assert!(!json.contains("1995"));
+        assert_eq!(view.age, Some(31));
+        assert_eq!(view.fame_rating, 12.5);
+    }
+
+    #[test]
+    fn public_view_works_without_a_profile() {
+        let view = PublicProfileView::new(&user(), None, today());
+
+        assert_eq!(view.username, "alice");
+        assert_eq!(view.gender, None);
+        assert_eq!(view.age, None);
+        assert_eq!(view.fame_rating, 0.0);
+    }
+
+    #[test]
+    fn self_and_admin_views_hide_the_password_hash() {
+        let own = serde_json::to_value(SelfUserView::from(&user())).unwrap();
+        let admin = serde_json::to_value(AdminUserView::new(&user(), Role::Moderator)).unwrap();
+
+        assert_eq!(own["email"], "alice@example.com");
+        assert_eq!(admin["email"], "alice@example.com");
+        assert_eq!(admin["role"], "moderator");
+        assert!(!own.to_string().contains("secret-hash"));
+        assert!(!admin.to_string().contains("secret-hash"));
+    }
+
+    #[test]
+    fn connects_only_mutual_likes() {
+        assert!(ConnectionStatus::new(true, true).connected);
+        assert!(!ConnectionStatus::new(true, false).connected);
+        assert!(!ConnectionStatus::new(false, true).connected);
+        assert!(!ConnectionStatus::new(false, false).connected);
+    }
+}
@@ -0,0 +1,19 @@
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+/// Someone who looked at a profile.
+#[derive(Debug, Clone)]
+pub struct ProfileVisit {
+    pub id: Uuid,
+    pub visitor_id: Uuid,
+    pub visitor_username: String,
+    pub visited_at: DateTime<Utc>,
+}
+
+/// Someone who liked a profile.
+#[derive(Debug, Clone)]
+pub struct ReceivedLike {
+    pub liker_id: Uuid,
+    pub liker_username: String,
+    pub liked_at: DateTime<Utc>,
+}
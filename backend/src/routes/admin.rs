@@ -0,0 +1,30 @@
+use axum::{
+    routing::{get, post, put},
+    Router,
+};
+
+use crate::api::admin;
+use crate::middleware::auth::{require_admin, require_auth, require_moderator};
+use crate::AppState;
+
+pub fn create_router(state: AppState) -> Router<AppState> {
+    let admin_only = Router::new()
+        .route("/users/:id/ban", post(admin::ban_user))
+        .route("/users/:id/restore", post(admin::restore_user))
+        .route("/users/:id/role", put(admin::set_role))
+        .route_layer(axum::middleware::from_fn_with_state(state.clone(), require_admin));
+
+    Router::new()
+        .route("/users", get(admin::search_users))
+        .route("/users/:id", get(admin::get_user))
+        .route("/users/:id/status-history", get(admin::get_status_history))
+        .route("/users/:id/suspend", post(admin::suspend_user))
+        .route("/users/:id/reinstate", post(admin::reinstate_user))
+        .route("/queue", get(admin::get_queue))
+        .route("/queue/:id", get(admin::get_queue_item))
+        .route("/queue/:id/review", post(admin::review_queue_item))
+        .route("/audit", get(admin::get_audit_log))
+        .merge(admin_only)
+        .layer(axum::middleware::from_fn_with_state(state.clone(), require_moderator))
+        .layer(axum::middleware::from_fn_with_state(state, require_auth))
+}
@@ -9,6 +9,7 @@ pub fn create_router(state: AppState) -> Router<AppState> {
         .route("/:id/like", post(interactions::like_user))
         .route("/:id/unlike", post(interactions::unlike_user))
         .route("/:id/block", post(interactions::block_user))
+        .route("/:id/unblock", post(interactions::unblock_user))
         .route("/:id/report", post(interactions::report_user))
         .layer(axum::middleware::from_fn_with_state(state, require_auth))
 }
@@ -0,0 +1,9 @@
+use axum::{routing::get, Router};
+
+use crate::api::media;
+use crate::AppState;
+
+// No require_auth here: the signature in the URL is the authorization, so <img> tags work
+pub fn create_router() -> Router<AppState> {
+    Router::new().route("/*key", get(media::serve_media))
+}
@@ -1,6 +1,8 @@
+pub mod admin;
 pub mod auth;
 pub mod chat;
 pub mod interactions;
+pub mod media;
 pub mod notifications;
 pub mod users;
 pub mod websocket;
@@ -16,6 +18,7 @@ pub fn create_router(state: AppState) -> Router<AppState> {
         .nest("/interactions", interactions::create_router(state.clone()))
         .nest("/chat", chat::create_router(state.clone()))
         .nest("/notifications", notifications::create_router(state.clone()))
+        .nest("/admin", admin::create_router(state.clone()))
 }
 
 pub async fn health_check() -> Json<Value> {
@@ -1,4 +1,7 @@
-use axum::{routing::post, Router};
+use axum::{
+    routing::{get, post},
+    Router,
+};
 
 use crate::api::notifications;
 use crate::middleware::auth::require_auth;
@@ -6,7 +9,9 @@ use crate::AppState;
 
 pub fn create_router(state: AppState) -> Router<AppState> {
     Router::new()
+        .route("/", get(notifications::get_notifications))
         .route("/read", post(notifications::mark_as_read))
         .route("/read/:id", post(notifications::mark_single_as_read))
+        .route("/stream", get(notifications::stream_events))
         .layer(axum::middleware::from_fn_with_state(state, require_auth))
 }
@@ -11,8 +11,20 @@ pub fn create_router(state: AppState) -> Router<AppState> {
     Router::new()
         .route("/profile", get(users::get_profile))
         .route("/profile", put(users::update_profile))
+        .route("/profile/fame", get(users::get_fame))
+        .route("/profile/pictures", get(users::get_pictures))
         .route("/profile/pictures", post(users::upload_pictures))
         .route("/profile/pictures/:id", delete(users::delete_picture))
+        .route("/profile/pictures/:id/edits", post(users::edit_picture))
+        .route("/profile/pictures/:id/revert", post(users::revert_picture))
+        .route("/profile/visits", get(users::get_visitors))
+        .route("/profile/likes", get(users::get_likers))
+        .route("/profile/blocks", get(users::get_blocked_users))
+        .route("/profile/location", get(users::get_location))
+        .route("/profile/location", put(users::update_location))
+        .route("/profile/location/ip", post(users::locate_by_ip))
+        .route("/profile/location/manual", put(users::set_manual_location))
+        .route("/profile/location/manual", delete(users::clear_manual_location))
         .route("/browse", get(users::browse_users))
         .route("/search", get(users::search_users))
         .route("/:id", get(users::get_user_profile))
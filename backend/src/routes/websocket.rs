@@ -1,10 +1,11 @@
 use axum::{routing::get, Router};
 
 use crate::AppState;
-use crate::{middleware::auth::require_auth, websocket};
+use crate::{middleware::auth::require_auth, socketio, websocket};
 
 pub fn create_router(state: AppState) -> Router<AppState> {
     Router::new()
         .route("/", get(websocket::handle_websocket))
+        .route("/socket.io/", get(socketio::handle_get).post(socketio::handle_post))
         .layer(axum::middleware::from_fn_with_state(state, require_auth))
 }
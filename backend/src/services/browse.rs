@@ -0,0 +1,238 @@
+use crate::database::user_repository::BrowseCandidate;
+use std::cmp::Ordering;
+use uuid::Uuid;
+
+// Distance at which the distance score has dropped to one half
+const DISTANCE_HALF_SCORE_KM: f64 = 10.0;
+
+/// Relative importance of each ranking signal; only their ratios matter.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BrowseWeights {
+    pub distance: f64,
+    pub shared_tags: f64,
+    pub fame: f64,
+}
+
+impl Default for BrowseWeights {
+    fn default() -> Self {
+        Self {
+            distance: 0.5,
+            shared_tags: 0.3,
+            fame: 0.2,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct RankedCandidate {
+    pub candidate: BrowseCandidate,
+    pub score: f64,
+}
+
+/// Where a candidate sits in the ranking: its score, distance and id.
+pub type RankPosition = (f64, f64, Uuid);
+
+impl RankedCandidate {
+    pub fn position(&self) -> RankPosition {
+        (self.score, self.candidate.distance_km, self.candidate.user.id)
+    }
+}
+
+/// Best match first; ties go to the closer, then the lower account id.
+fn compare_positions(a: RankPosition, b: RankPosition) -> Ordering {
+    b.0.total_cmp(&a.0)
+        .then(a.1.total_cmp(&b.1))
+        .then(a.2.cmp(&b.2))
+}
+
+impl BrowseWeights {
+    /// Combines the normalised signals into a score between 0 and 1.
+    pub fn score(&self, distance_km: f64, shared_tags: i64, viewer_tags: i64, fame: f64) -> f64 {
+        let total = self.distance + self.shared_tags + self.fame;
+        if total <= 0.0 {
+            return 0.0;
+        }
+
+        let distance_score = 1.0 / (1.0 + distance_km.max(0.0) / DISTANCE_HALF_SCORE_KM);
+        let tag_score = if viewer_tags > 0 {
+            (shared_tags as f64 / viewer_tags as f64).min(1.0)
+        } else {
+            0.0
+        };
+        let fame_score = (fame / 100.0).clamp(0.0, 1.0);
+
+        (self.distance * distance_score + self.shared_tags * tag_score + self.fame * fame_score)
+            / total
+    }
+}
+
+/// Orders candidates by `compare_positions`, leaving out those up to and including `after`.
+pub fn rank(
+    candidates: Vec<BrowseCandidate>,
+    weights: &BrowseWeights,
+    viewer_tags: i64,
+    after: Option<RankPosition>,
+) -> Vec<RankedCandidate> {
+    let mut ranked: Vec<RankedCandidate> = candidates
+        .into_iter()
+        .map(|candidate| RankedCandidate {
+            score: weights.score(
+                candidate.distance_km,
+                candidate.shared_tags,
+                viewer_tags,
+                candidate.fame_rating,
+            ),
+            candidate,
+        })
+        .filter(|ranked| {
+            after.is_none_or(|after| {
+                compare_positions(ranked.position(), after) == Ordering::Greater
+            })
+        })
+        .collect();
+
+    ranked.sort_by(|a, b| compare_positions(a.position(), b.position()));
+
+    ranked
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::enums::AccountStatus;
+    use crate::models::{PublicLocation, User};
+    use chrono::Utc;
+    use uuid::Uuid;
+
+    fn candidate(distance_km: f64, shared_tags: i64, fame_rating: f64) -> BrowseCandidate {
+        BrowseCandidate {
+            user: User {
+                id: Uuid::new_v4(),
+                email: "test@example.com".to_string(),
+                username: "testuser".to_string(),
+                password_hash: "hashed_password".to_string(),
+                email_verified_at: Some(Utc::now()),
+                account_status: AccountStatus::Active,
+                created_at: Utc::now(),
+                updated_at: Utc::now(),
+                deleted_at: None,
+            },
+            location: PublicLocation {
+                latitude: 52.52,
+                longitude: 13.40,
+                neighborhood: None,
+                city: Some("Berlin".to_string()),
+                country_code: Some("DE".to_string()),
+            },
+            distance_km,
+            shared_tags,
+            fame_rating,
+        }
+    }
+
+    #[test]
+    fn scores_between_zero_and_one() {
+        let weights = BrowseWeights::default();
+
+        assert_eq!(weights.score(0.0, 5, 5, 100.0), 1.0);
+        let score = weights.score(10.0, 2, 4, 50.0);
+        assert!(score > 0.0 && score < 1.0);
+        assert!(weights.score(20_000.0, 0, 4, 0.0) < 0.01);
+    }
+
+    #[test]
+    fn halves_the_distance_score_at_the_half_score_distance() {
+        let weights = BrowseWeights {
+            distance: 1.0,
+            shared_tags: 0.0,
+            fame: 0.0,
+        };
+
+        assert_eq!(weights.score(DISTANCE_HALF_SCORE_KM, 0, 0, 0.0), 0.5);
+    }
+
+    #[test]
+    fn ignores_tags_when_the_viewer_has_none() {
+        let weights = BrowseWeights {
+            distance: 0.0,
+            shared_tags: 1.0,
+            fame: 0.0,
+        };
+
+        assert_eq!(weights.score(0.0, 3, 0, 0.0), 0.0);
+        assert_eq!(weights.score(0.0, 3, 3, 0.0), 1.0);
+    }
+
+    #[test]
+    fn only_weight_ratios_matter() {
+        let weights = BrowseWeights::default();
+        let scaled = BrowseWeights {
+            distance: weights.distance * 10.0,
+            shared_tags: weights.shared_tags * 10.0,
+            fame: weights.fame * 10.0,
+        };
+
+        let score = weights.score(7.0, 1, 3, 42.0);
+        assert!((score - scaled.score(7.0, 1, 3, 42.0)).abs() < 1e-12);
+    }
+
+    #[test]
+    fn scores_zero_without_weights() {
+        let weights = BrowseWeights {
+            distance: 0.0,
+            shared_tags: 0.0,
+            fame: 0.0,
+        };
+
+        assert_eq!(weights.score(0.0, 5, 5, 100.0), 0.0);
+    }
+
+    #[test]
+    fn ranks_shared_interests_above_distance_when_weighted_so() {
+        let near = candidate(1.0, 0, 0.0);
+        let alike = candidate(30.0, 4, 0.0);
+        let weights = BrowseWeights {
+            distance: 0.2,
+            shared_tags: 0.8,
+            fame: 0.0,
+        };
+
+        let ranked = rank(vec![near.clone(), alike.clone()], &weights, 4, None);
+        assert_eq!(ranked[0].candidate.user.id, alike.user.id);
+
+        let ranked = rank(vec![near.clone(), alike], &BrowseWeights::default(), 0, None);
+        assert_eq!(ranked[0].candidate.user.id, near.user.id);
+    }
+
+    #[test]
+    fn breaks_ties_by_distance() {
+        let weights = BrowseWeights {
+            distance: 0.0,
+            shared_tags: 0.0,
+            fame: 1.0,
+        };
+        let far = candidate(50.0, 0, 10.0);
+        let close = candidate(5.0, 0, 10.0);
+
+        let ranked = rank(vec![far, close.clone()], &weights, 0, None);
+        assert_eq!(ranked[0].candidate.user.id, close.user.id);
+        assert_eq!(ranked[0].score, ranked[1].score);
+    }
+
+    #[test]
+    fn continues_after_a_position() {
+        let weights = BrowseWeights::default();
+        let candidates: Vec<BrowseCandidate> = (0..5)
+            .map(|i| candidate(i as f64 * 3.0, i % 2, 20.0))
+            .collect();
+        let all = rank(candidates.clone(), &weights, 2, None);
+
+        let rest = rank(candidates, &weights, 2, Some(all[1].position()));
+        let ids: Vec<Uuid> = rest.iter().map(|ranked| ranked.candidate.user.id).collect();
+        let expected: Vec<Uuid> = all[2..]
+            .iter()
+            .map(|ranked| ranked.candidate.user.id)
+            .collect();
+        assert_eq!(ids, expected);
+    }
+}
@@ -0,0 +1,111 @@
+use sqlx::PgPool;
+use std::fmt;
+use uuid::Uuid;
+
+use crate::database::{BlockRepository, ChatRepository, UserRepository};
+use crate::models::chat::validate_message_body;
+use crate::models::Message;
+use crate::services::hub::{ConnectionId, Hub};
+use crate::validation::core::ValidationError;
+use crate::websocket::protocol::ServerMessage;
+
+/// Why a chat request was refused.
+#[derive(Debug)]
+pub enum ChatError {
+    Invalid(ValidationError),
+    ToYourself,
+    /// The sender was suspended or banned while a socket of theirs stayed open.
+    NotActive,
+    /// The other user is deleted, blocked in either direction or, when sending, not active.
+    UserNotFound,
+    NotConnected,
+    Database(anyhow::Error),
+}
+
+impl fmt::Display for ChatError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ChatError::Invalid(e) => write!(f, "{}", e.message),
+            ChatError::ToYourself => write!(f, "You cannot message yourself"),
+            ChatError::NotActive => write!(f, "Account is not active"),
+            ChatError::UserNotFound => write!(f, "User not found"),
+            ChatError::NotConnected => {
+                write!(f, "You can only message users you are connected with")
+            }
+            ChatError::Database(_) => write!(f, "Database error"),
+        }
+    }
+}
+
+impl From<anyhow::Error> for ChatError {
+    fn from(e: anyhow::Error) -> Self {
+        ChatError::Database(e)
+    }
+}
+
+/// Stores a message and pushes it to the recipient and to the sender's other connections.
+/// `from` is the connection it was sent through, which is answered separately.
+pub async fn send_message(
+    db: &PgPool,
+    hub: &Hub,
+    sender_id: Uuid,
+    recipient_id: Uuid,
+    body: &str,
+    from: Option<ConnectionId>,
+) -> Result<Message, ChatError> {
+    let body = validate_message_body(body).map_err(ChatError::Invalid)?;
+    match UserRepository::new(db.clone())
+        .find_by_id(sender_id)
+        .await?
+    {
+        Some(sender) if sender.is_active() => {}
+        _ => return Err(ChatError::NotActive),
+    }
+    check_other_user(db, sender_id, recipient_id, true).await?;
+
+    let message = ChatRepository::new(db.clone())
+        .send(sender_id, recipient_id, &body)
+        .await?
+        .ok_or(ChatError::NotConnected)?;
+
+    let push = ServerMessage::ChatMessage {
+        message: message.clone(),
+    };
+    hub.send(recipient_id, &push);
+    hub.send_except(sender_id, from, &push);
+
+    Ok(message)
+}
+
+/// Tells the sender's connections that a message reached a client of its recipient.
+pub fn confirm_delivery(hub: &Hub, message: &Message) {
+    hub.send(
+        message.sender_id,
+        &ServerMessage::MessageDelivered {
+            message_id: message.id,
+            conversation_id: message.conversation_id,
+        },
+    );
+}
+
+/// Rejects chatting with yourself, and with users who are deleted or blocked in either
+/// direction. Sending also needs the other user to be active.
+pub async fn check_other_user(
+    db: &PgPool,
+    user_id: Uuid,
+    other_id: Uuid,
+    sending: bool,
+) -> Result<(), ChatError> {
+    if other_id == user_id {
+        return Err(ChatError::ToYourself);
+    }
+
+    let other = UserRepository::new(db.clone()).find_by_id(other_id).await?;
+    let blocked = BlockRepository::new(db.clone())
+        .is_blocked_between(user_id, other_id)
+        .await?;
+    match other {
+        Some(other) if !blocked && (!sending || other.is_active()) => Ok(()),
+        _ => Err(ChatError::UserNotFound),
+    }
+}
@@ -0,0 +1,509 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::Notify;
+use tracing::{error, info};
+use uuid::Uuid;
+
+use crate::database::FameRepository;
+use crate::enums::{FameEventKind, FameTrigger};
+
+/// Events lose half their weight every this many days.
+pub const HALF_LIFE_DAYS: f64 = 30.0;
+
+/// How often every rating is recomputed, so that decay shows even without new events.
+pub const BATCH_INTERVAL: Duration = Duration::from_secs(6 * 60 * 60);
+
+const MAX_RATING: f64 = 100.0;
+
+// Decayed counts at which popularity and connections reach ~63% of their share
+const LIKES_SCALE: f64 = 10.0;
+const CONNECTIONS_SCALE: f64 = 5.0;
+
+// Each ~3 decayed reports cut the rating to ~37%
+const REPORTS_SCALE: f64 = 3.0;
+
+// Prior added to the like ratio, so one view followed by one like is not a perfect score
+const RATIO_PRIOR_LIKES: f64 = 1.0;
+const RATIO_PRIOR_VIEWS: f64 = 5.0;
+
+const POPULARITY_WEIGHT: f64 = 0.35;
+const LIKE_RATIO_WEIGHT: f64 = 0.2;
+const CONNECTIONS_WEIGHT: f64 = 0.25;
+const COMPLETENESS_WEIGHT: f64 = 0.2;
+
+/// Which parts of a profile are filled in.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct ProfileCompleteness {
+    pub gender: bool,
+    pub sexual_preference: bool,
+    pub biography: bool,
+    pub birth_date: bool,
+    pub pictures: bool,
+    pub profile_picture: bool,
+    pub tags: bool,
+}
+
+impl ProfileCompleteness {
+    /// Share of the parts that are filled in, between 0 and 1.
+    pub fn fraction(&self) -> f64 {
+        let parts = [
+            self.gender,
+            self.sexual_preference,
+            self.biography,
+            self.birth_date,
+            self.pictures,
+            self.profile_picture,
+            self.tags,
+        ];
+        parts.iter().filter(|&&filled| filled).count() as f64 / parts.len() as f64
+    }
+}
+
+/// Event counts weighted by their age, plus the current profile state.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct FameInputs {
+    pub likes: f64,
+    pub views: f64,
+    pub connections: f64,
+    pub reports: f64,
+    pub completeness: ProfileCompleteness,
+}
+
+/// A rating together with everything it was computed from.
+///
+/// Components are between 0 and 1; the rating is their weighted sum scaled to 100,
+/// multiplied by the report penalty.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FameBreakdown {
+    pub inputs: FameInputs,
+    pub popularity: f64,
+    pub like_ratio: f64,
+    pub connections: f64,
+    pub completeness: f64,
+    pub report_penalty: f64,
+    pub rating: f64,
+}
+
+/// Weight of an event that happened `age_days` ago.
+pub fn decay(age_days: f64) -> f64 {
+    0.5f64.powf(age_days.max(0.0) / HALF_LIFE_DAYS)
+}
+
+/// Puts the running total of each kind of event, already decayed, next to the profile state.
+pub fn inputs_from_totals(
+    totals: &[(FameEventKind, f64)],
+    completeness: ProfileCompleteness,
+) -> FameInputs {
+    let mut inputs = FameInputs {
+        completeness,
+        ..Default::default()
+    };
+    for &(kind, total) in totals {
+        let input = match kind {
+            FameEventKind::Like => &mut inputs.likes,
+            FameEventKind::View => &mut inputs.views,
+            FameEventKind::Connection => &mut inputs.connections,
+            FameEventKind::Report => &mut inputs.reports,
+        };
+        *input += total;
+    }
+    inputs
+}
+
+pub fn compute(inputs: &FameInputs) -> FameBreakdown {
+    let popularity = saturate(inputs.likes, LIKES_SCALE);
+    // Likes from browsing without opening the profile can outnumber views
+    let like_ratio = ((inputs.likes + RATIO_PRIOR_LIKES)
+        / (inputs.views.max(inputs.likes) + RATIO_PRIOR_VIEWS))
+        .min(1.0);
+    let connections = saturate(inputs.connections, CONNECTIONS_SCALE);
+    let completeness = inputs.completeness.fraction();
+    let report_penalty = (-inputs.reports.max(0.0) / REPORTS_SCALE).exp();
+
+    let score = POPULARITY_WEIGHT * popularity
+        + LIKE_RATIO_WEIGHT * like_ratio
+        + CONNECTIONS_WEIGHT * connections
+        + COMPLETENESS_WEIGHT * completeness;
+    let rating = (score * report_penalty * MAX_RATING).clamp(0.0, MAX_RATING);
+
+    FameBreakdown {
+        inputs: *inputs,
+        popularity,
+        like_ratio,
+        connections,
+        completeness,
+        report_penalty,
+        rating: (rating * 100.0).round() / 100.0,
+    }
+}
+
+/// Grows from 0 towards 1, quickly at first and ever slower.
+fn saturate(count: f64, scale: f64) -> f64 {
+    1.0 - (-count.max(0.0) / scale).exp()
+}
+
+/// Records interactions and keeps fame ratings up to date with them.
+///
+/// Ratings are recomputed by the fame worker, so callers never wait for them.
+#[derive(Clone)]
+pub struct FameService {
+    db: PgPool,
+    queue: FameQueue,
+}
+
+impl FameService {
+    pub fn new(db: PgPool, queue: FameQueue) -> Self {
+        Self { db, queue }
+    }
+
+    /// Counts an interaction of `actor_id` towards the fame of `user_id`.
+    pub async fn record(
+        &self,
+        user_id: Uuid,
+        actor_id: Option<Uuid>,
+        kind: FameEventKind,
+    ) -> Result<()> {
+        FameRepository::new(self.db.clone())
+            .insert_event(user_id, actor_id, kind)
+            .await?;
+        self.queue
+            .schedule(user_id, FameTrigger::EventRecorded, Some(kind));
+        Ok(())
+    }
+
+    /// Takes back what `record` counted, e.g. when a like is withdrawn.
+    pub async fn withdraw(&self, user_id: Uuid, actor_id: Uuid, kind: FameEventKind) -> Result<()> {
+        let removed = FameRepository::new(self.db.clone())
+            .delete_events(user_id, actor_id, kind)
+            .await?;
+        if removed > 0 {
+            self.queue
+                .schedule(user_id, FameTrigger::EventWithdrawn, Some(kind));
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct PendingRecompute {
+    trigger: FameTrigger,
+    event_kind: Option<FameEventKind>,
+}
+
+/// Users whose rating is to be recomputed, worked off by a task of its own so that neither
+/// requests nor picture processing wait for ratings.
+///
+/// A user is waiting at most once: scheduling them again replaces the trigger, since one
+/// recompute takes every event so far into account.
+#[derive(Clone)]
+pub struct FameQueue {
+    pending: Arc<Mutex<HashMap<Uuid, PendingRecompute>>>,
+    wake: Arc<Notify>,
+}
+
+impl FameQueue {
+    /// Spawns the worker.
+    pub fn start(db: PgPool) -> Self {
+        let queue = Self {
+            pending: Arc::default(),
+            wake: Arc::new(Notify::new()),
+        };
+        tokio::spawn(queue.clone().run(db));
+
+        queue
+    }
+
+    /// Never waits, however far behind the worker is.
+    pub fn schedule(&self, user_id: Uuid, trigger: FameTrigger, event_kind: Option<FameEventKind>) {
+        self.lock().insert(
+            user_id,
+            PendingRecompute {
+                trigger,
+                event_kind,
+            },
+        );
+        self.wake.notify_one();
+    }
+
+    async fn run(self, db: PgPool) {
+        loop {
+            self.wake.notified().await;
+            let pending = std::mem::take(&mut *self.lock());
+            for (
+                user_id,
+                PendingRecompute {
+                    trigger,
+                    event_kind,
+                },
+            ) in pending
+            {
+                if let Err(e) = recompute(&db, user_id, trigger, event_kind).await {
+                    error!("Failed to recompute fame of {}: {:#}", user_id, e);
+                }
+            }
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<Uuid, PendingRecompute>> {
+        self.pending
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+/// Computes and stores the rating of one user, recording it in the history.
+pub async fn recompute(
+    db: &PgPool,
+    user_id: Uuid,
+    trigger: FameTrigger,
+    event_kind: Option<FameEventKind>,
+) -> Result<FameBreakdown> {
+    let fame_repo = FameRepository::new(db.clone());
+    let breakdown = compute(&load_inputs(&fame_repo, user_id).await?);
+    fame_repo
+        .save(user_id, &breakdown, trigger, event_kind)
+        .await?;
+
+    Ok(breakdown)
+}
+
+async fn load_inputs(fame_repo: &FameRepository, user_id: Uuid) -> Result<FameInputs> {
+    let totals = fame_repo.find_totals(user_id).await?;
+    let completeness = fame_repo.find_completeness(user_id).await?;
+
+    Ok(inputs_from_totals(&totals, completeness))
+}
+
+/// Spawns the periodic recomputation of every rating.
+pub fn start_batch(db: PgPool) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(BATCH_INTERVAL);
+        loop {
+            interval.tick().await;
+            match recompute_all(&db).await {
+                Ok(updated) => info!("Recomputed fame ratings, {} changed", updated),
+                Err(e) => error!("Failed to recompute fame ratings: {:#}", e),
+            }
+        }
+    });
+}
+
+const BATCH_SIZE: i64 = 500;
+
+/// Recomputes every rating, only storing those that changed. Returns how many did.
+async fn recompute_all(db: &PgPool) -> Result<usize> {
+    let fame_repo = FameRepository::new(db.clone());
+    let mut updated = 0;
+    let mut after = None;
+
+    loop {
+        let user_ids = fame_repo.find_user_ids(after, BATCH_SIZE).await?;
+        let Some(&last) = user_ids.last() else {
+            return Ok(updated);
+        };
+
+        // One user failing must not hold up everyone after them
+        for user_id in user_ids {
+            match recompute_one(&fame_repo, user_id).await {
+                Ok(true) => updated += 1,
+                Ok(false) => {}
+                Err(e) => error!("Failed to recompute fame of {}: {:#}", user_id, e),
+            }
+        }
+
+        after = Some(last);
+    }
+}
+
+/// Stores the user's rating if it changed, returning whether it did.
+async fn recompute_one(fame_repo: &FameRepository, user_id: Uuid) -> Result<bool> {
+    let breakdown = compute(&load_inputs(fame_repo, user_id).await?);
+    if fame_repo.find_rating(user_id).await? == Some(breakdown.rating) {
+        return Ok(false);
+    }
+    fame_repo
+        .save(user_id, &breakdown, FameTrigger::Scheduled, None)
+        .await?;
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn complete() -> ProfileCompleteness {
+        ProfileCompleteness {
+            gender: true,
+            sexual_preference: true,
+            biography: true,
+            birth_date: true,
+            pictures: true,
+            profile_picture: true,
+            tags: true,
+        }
+    }
+
+    #[test]
+    fn halves_event_weight_every_half_life() {
+        assert_eq!(decay(0.0), 1.0);
+        assert!((decay(HALF_LIFE_DAYS) - 0.5).abs() < 1e-12);
+        assert!((decay(2.0 * HALF_LIFE_DAYS) - 0.25).abs() < 1e-12);
+        // Clock skew must not make events count more than once
+        assert_eq!(decay(-1.0), 1.0);
+    }
+
+    #[test]
+    fn decays_running_totals_like_the_events_in_them() {
+        // One event 10 days ago, updated with another 5 days ago, read today
+        let running = (decay(5.0) + 1.0) * decay(5.0);
+        let events = decay(10.0) + decay(5.0);
+
+        assert!((running - events).abs() < 1e-12);
+    }
+
+    #[test]
+    fn sums_totals_by_kind() {
+        let totals = [
+            (FameEventKind::Like, 1.5),
+            (FameEventKind::View, 1.0),
+            (FameEventKind::Report, 0.25),
+        ];
+        let inputs = inputs_from_totals(&totals, complete());
+
+        assert_eq!(inputs.likes, 1.5);
+        assert_eq!(inputs.views, 1.0);
+        assert_eq!(inputs.connections, 0.0);
+        assert_eq!(inputs.reports, 0.25);
+        assert_eq!(inputs.completeness, complete());
+    }
+
+    #[test]
+    fn measures_profile_completeness() {
+        assert_eq!(ProfileCompleteness::default().fraction(), 0.0);
+        assert_eq!(complete().fraction(), 1.0);
+
+        let partial = ProfileCompleteness {
+            gender: true,
+            tags: true,
+            ..Default::default()
+        };
+        assert!((partial.fraction() - 2.0 / 7.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn rates_an_empty_profile_near_zero() {
+        let breakdown = compute(&FameInputs::default());
+
+        assert_eq!(breakdown.popularity, 0.0);
+        assert_eq!(breakdown.connections, 0.0);
+        assert_eq!(breakdown.report_penalty, 1.0);
+        assert_eq!(breakdown.rating, 4.0);
+    }
+
+    #[test]
+    fn stays_within_bounds() {
+        let inputs = FameInputs {
+            likes: 1e9,
+            views: 0.0,
+            connections: 1e9,
+            reports: 0.0,
+            completeness: complete(),
+        };
+        let breakdown = compute(&inputs);
+
+        assert!(breakdown.like_ratio <= 1.0);
+        assert!(breakdown.rating <= MAX_RATING);
+        assert!(breakdown.rating > 99.0);
+    }
+
+    #[test]
+    fn grows_with_each_input() {
+        let base = FameInputs {
+            likes: 3.0,
+            views: 20.0,
+            connections: 1.0,
+            reports: 0.0,
+            completeness: ProfileCompleteness {
+                gender: true,
+                ..Default::default()
+            },
+        };
+        let rating = compute(&base).rating;
+
+        let more_likes = FameInputs { likes: 4.0, ..base };
+        let more_connections = FameInputs {
+            connections: 2.0,
+            ..base
+        };
+        let more_complete = FameInputs {
+            completeness: complete(),
+            ..base
+        };
+        let more_views = FameInputs {
+            views: 40.0,
+            ..base
+        };
+        let reported = FameInputs {
+            reports: 1.0,
+            ..base
+        };
+
+        assert!(compute(&more_likes).rating > rating);
+        assert!(compute(&more_connections).rating > rating);
+        assert!(compute(&more_complete).rating > rating);
+        assert!(compute(&more_views).rating < rating);
+        assert!(compute(&reported).rating < rating);
+    }
+
+    #[test]
+    fn favors_a_high_like_ratio() {
+        let liked = FameInputs {
+            likes: 5.0,
+            views: 10.0,
+            ..Default::default()
+        };
+        let ignored = FameInputs {
+            views: 100.0,
+            ..liked
+        };
+
+        assert!(compute(&liked).like_ratio > compute(&ignored).like_ratio);
+    }
+
+    #[test]
+    fn penalizes_reports_multiplicatively() {
+        let inputs = FameInputs {
+            likes: 10.0,
+            views: 10.0,
+            connections: 5.0,
+            reports: REPORTS_SCALE,
+            completeness: complete(),
+        };
+        let unreported = compute(&FameInputs {
+            reports: 0.0,
+            ..inputs
+        });
+        let reported = compute(&inputs);
+
+        assert!((reported.report_penalty - (-1.0f64).exp()).abs() < 1e-12);
+        assert!((reported.rating - unreported.rating * reported.report_penalty).abs() < 0.01);
+    }
+
+    #[test]
+    fn serializes_the_breakdown_for_history() {
+        let breakdown = compute(&FameInputs {
+            likes: 2.5,
+            views: 7.0,
+            ..Default::default()
+        });
+        let stored = serde_json::to_value(&breakdown).unwrap();
+
+        assert_eq!(stored["inputs"]["likes"], 2.5);
+        assert_eq!(stored["inputs"]["completeness"]["tags"], false);
+        assert_eq!(serde_json::from_value::<FameBreakdown>(stored).unwrap(), breakdown);
+    }
+}
@@ -0,0 +1,454 @@
+use anyhow::{Context, Result};
+use rstar::{primitives::GeomWithData, RTree};
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::sync::Arc;
+
+use crate::models::{Coordinates, PublicLocation};
+
+const EARTH_RADIUS_KM: f64 = 6371.0;
+
+// A neighborhood further away than this does not describe the position anymore
+const NEIGHBORHOOD_RADIUS_KM: f64 = 3.0;
+const CITY_RADIUS_KM: f64 = 25.0;
+
+// Without a nearby neighborhood, positions are snapped to the centre of a cell of roughly 2 km
+const GRID_STEP_DEGREES: f64 = 0.02;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum PlaceKind {
+    Neighborhood,
+    City,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Place {
+    pub name: String,
+    pub country_code: String,
+    pub coordinates: Coordinates,
+    pub population: u64,
+}
+
+type IndexedPlace = GeomWithData<[f64; 3], usize>;
+
+/// Populated places from a GeoNames-style dump, indexed for nearest-place lookups.
+///
+/// Places are indexed as points on the unit sphere, where straight-line distance grows
+/// with great-circle distance, so nearest-neighbor queries stay correct near the poles
+/// and across the antimeridian.
+pub struct Gazetteer {
+    places: Vec<Place>,
+    // Lowercased place names, both native and ASCII spellings
+    names: HashMap<String, Vec<usize>>,
+    neighborhoods: RTree<IndexedPlace>,
+    cities: RTree<IndexedPlace>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReverseGeocode<'a> {
+    pub neighborhood: Option<&'a Place>,
+    pub city: Option<&'a Place>,
+}
+
+impl Gazetteer {
+    pub fn load(path: &str) -> Result<Self> {
+        let file =
+            File::open(path).with_context(|| format!("Failed to open gazetteer {}", path))?;
+        Self::from_reader(BufReader::new(file))
+    }
+
+    /// Parses the tab-separated GeoNames format (e.g. `cities1000.txt`), keeping populated places.
+    pub fn from_reader(reader: impl BufRead) -> Result<Self> {
+        let mut places = Vec::new();
+        let mut names: HashMap<String, Vec<usize>> = HashMap::new();
+        let mut neighborhoods = Vec::new();
+        let mut cities = Vec::new();
+
+        for (index, line) in reader.lines().enumerate() {
+            let line = line.context("Failed to read gazetteer")?;
+            let Some((place, ascii_name, kind)) = parse_line(&line)
+                .with_context(|| format!("Invalid gazetteer line {}", index + 1))?
+            else {
+                continue;
+            };
+
+            let indexed = GeomWithData::new(to_unit_vector(place.coordinates), places.len());
+            match kind {
+                PlaceKind::Neighborhood => neighborhoods.push(indexed),
+                PlaceKind::City => cities.push(indexed),
+            }
+            for name in [place.name.to_lowercase(), ascii_name.to_lowercase()] {
+                let indices = names.entry(name).or_default();
+                if !indices.contains(&places.len()) {
+                    indices.push(places.len());
+                }
+            }
+            places.push(place);
+        }
+
+        Ok(Self {
+            places,
+            names,
+            neighborhoods: RTree::bulk_load(neighborhoods),
+            cities: RTree::bulk_load(cities),
+        })
+    }
+
+    pub fn place_count(&self) -> usize {
+        self.places.len()
+    }
+
+    pub fn reverse(&self, coordinates: Coordinates) -> ReverseGeocode<'_> {
+        ReverseGeocode {
+            neighborhood: self.nearest(&self.neighborhoods, coordinates, NEIGHBORHOOD_RADIUS_KM),
+            city: self.nearest(&self.cities, coordinates, CITY_RADIUS_KM),
+        }
+    }
+
+    /// Looks a place up by name, optionally followed by a country code as in `Paris, FR`.
+    ///
+    /// Names are matched case-insensitively; among places sharing a name the most populous wins.
+    pub fn find(&self, query: &str) -> Option<&Place> {
+        let (name, country_code) = match query.rsplit_once(',') {
+            Some((name, country_code)) => (name, Some(country_code.trim())),
+            None => (query, None),
+        };
+
+        self.names
+            .get(&name.trim().to_lowercase())?
+            .iter()
+            .map(|&index| &self.places[index])
+            .filter(|place| {
+                country_code.is_none_or(|code| place.country_code.eq_ignore_ascii_case(code))
+            })
+            .max_by_key(|place| place.population)
+    }
+
+    fn nearest(
+        &self,
+        tree: &RTree<IndexedPlace>,
+        coordinates: Coordinates,
+        max_distance_km: f64,
+    ) -> Option<&Place> {
+        let nearest = tree.nearest_neighbor(&to_unit_vector(coordinates))?;
+        let place = &self.places[nearest.data];
+
+        (haversine_km(coordinates, place.coordinates) <= max_distance_km).then_some(place)
+    }
+}
+
+/// Reverse geocoding and coarsening for user locations. Without a gazetteer, public
+/// locations are still coarsened but carry no names.
+pub struct Geo {
+    gazetteer: Option<Gazetteer>,
+}
+
+pub type SharedGeo = Arc<Geo>;
+
+impl Geo {
+    pub fn open(gazetteer_path: Option<&str>) -> Result<Self> {
+        let gazetteer = gazetteer_path.map(Gazetteer::load).transpose()?;
+        Ok(Self { gazetteer })
+    }
+
+    pub fn gazetteer(&self) -> Option<&Gazetteer> {
+        self.gazetteer.as_ref()
+    }
+
+    /// The location other users may see for someone at `coordinates`.
+    ///
+    /// The position is replaced by the centre of the nearest neighborhood, or of a grid cell
+    /// when there is none nearby, so the precise position can never be recovered from it.
+    pub fn public_location(&self, coordinates: Coordinates) -> PublicLocation {
+        let reverse = self
+            .gazetteer
+            .as_ref()
+            .map(|gazetteer| gazetteer.reverse(coordinates));
+        let neighborhood = reverse.as_ref().and_then(|reverse| reverse.neighborhood);
+        let city = reverse.as_ref().and_then(|reverse| reverse.city);
+
+        let public = match neighborhood {
+            Some(neighborhood) => neighborhood.coordinates,
+            None => snap_to_grid(coordinates),
+        };
+
+        PublicLocation {
+            latitude: public.latitude,
+            longitude: public.longitude,
+            neighborhood: neighborhood.map(|place| place.name.clone()),
+            city: city.map(|place| place.name.clone()),
+            country_code: neighborhood
+                .or(city)
+                .map(|place| place.country_code.clone()),
+        }
+    }
+}
+
+/// Latitude/longitude box containing every point within a radius, for index prefiltering.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BoundingBox {
+    pub min_latitude: f64,
+    pub max_latitude: f64,
+    pub min_longitude: f64,
+    pub max_longitude: f64,
+}
+
+impl BoundingBox {
+    /// Near the poles or across the antimeridian the box spans all longitudes instead of
+    /// wrapping around, which keeps it a single indexable range.
+    pub fn around(center: Coordinates, radius_km: f64) -> Self {
+        let angular_radius = radius_km / EARTH_RADIUS_KM;
+        let delta_latitude = angular_radius.to_degrees();
+        let min_latitude = center.latitude - delta_latitude;
+        let max_latitude = center.latitude + delta_latitude;
+
+        let everywhere = Self {
+            min_latitude: min_latitude.max(-90.0),
+            max_latitude: max_latitude.min(90.0),
+            min_longitude: -180.0,
+            max_longitude: 180.0,
+        };
+
+        let latitude_cos = center.latitude.to_radians().cos();
+        if min_latitude <= -90.0 || max_latitude >= 90.0 || angular_radius.sin() >= latitude_cos {
+            return everywhere;
+        }
+
+        let delta_longitude = (angular_radius.sin() / latitude_cos).asin().to_degrees();
+        let min_longitude = center.longitude - delta_longitude;
+        let max_longitude = center.longitude + delta_longitude;
+        if min_longitude < -180.0 || max_longitude > 180.0 {
+            return everywhere;
+        }
+
+        Self {
+            min_latitude,
+            max_latitude,
+            min_longitude,
+            max_longitude,
+        }
+    }
+}
+
+pub fn haversine_km(a: Coordinates, b: Coordinates) -> f64 {
+    let (lat_a, lat_b) = (a.latitude.to_radians(), b.latitude.to_radians());
+    let delta_lat = lat_b - lat_a;
+    let delta_lon = (b.longitude - a.longitude).to_radians();
+
+    let h = (delta_lat / 2.0).sin().powi(2)
+        + lat_a.cos() * lat_b.cos() * (delta_lon / 2.0).sin().powi(2);
+    2.0 * EARTH_RADIUS_KM * h.sqrt().asin()
+}
+
+fn snap_to_grid(coordinates: Coordinates) -> Coordinates {
+    let snap = |value: f64, limit: f64| {
+        let centre = ((value / GRID_STEP_DEGREES).floor() + 0.5) * GRID_STEP_DEGREES;
+        // Rounded to drop floating point noise, which would otherwise leak sub-cell digits
+        ((centre * 1e6).round() / 1e6).clamp(-limit, limit)
+    };
+
+    Coordinates {
+        latitude: snap(coordinates.latitude, 90.0),
+        longitude: snap(coordinates.longitude, 180.0),
+    }
+}
+
+fn to_unit_vector(coordinates: Coordinates) -> [f64; 3] {
+    let latitude = coordinates.latitude.to_radians();
+    let longitude = coordinates.longitude.to_radians();
+
+    [
+        latitude.cos() * longitude.cos(),
+        latitude.cos() * longitude.sin(),
+        latitude.sin(),
+    ]
+}
+
+/// Reads one GeoNames row; rows that are not inhabited places are skipped.
+fn parse_line(line: &str) -> Result<Option<(Place, &str, PlaceKind)>> {
+    let fields: Vec<&str> = line.split('\t').collect();
+    if fields.len() < 9 {
+        anyhow::bail!("Expected at least 9 columns, found {}", fields.len());
+    }
+
+    let kind = match (fields[6], fields[7]) {
+        ("P", "PPLX") => PlaceKind::Neighborhood,
+        // Historical, abandoned and destroyed places no longer describe where anyone lives
+        ("P", "PPLH" | "PPLQ" | "PPLW" | "PPLCH") => return Ok(None),
+        ("P", _) => PlaceKind::City,
+        _ => return Ok(None),
+    };
+
+    let coordinates = Coordinates::new(
+        fields[4].parse().context("Invalid latitude")?,
+        fields[5].parse().context("Invalid longitude")?,
+    )?;
+
+    // Population is the 15th column, which trimmed-down dumps may leave out
+    let population = match fields.get(14) {
+        Some(population) if !population.is_empty() => {
+            population.parse().context("Invalid population")?
+        }
+        _ => 0,
+    };
+
+    Ok(Some((
+        Place {
+            name: fields[1].to_string(),
+            country_code: fields[8].to_string(),
+            coordinates,
+            population,
+        },
+        fields[2],
+        kind,
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GAZETTEER: &str = "\
+2950159\tBerlin\tBerlin\t\t52.52437\t13.41053\tP\tPPLC\tDE\t\t16\t00\t11000\t11000000\t3426354\t\t74\tEurope/Berlin\t2022-01-01
+2884134\tKreuzberg\tKreuzberg\t\t52.49973\t13.40338\tP\tPPLX\tDE\t\t16\t00\t11000\t11000000\t147227\t\t\tEurope/Berlin\t2022-01-01
+2870912\tMitte\tMitte\t\t52.52003\t13.40489\tP\tPPLX\tDE\t\t16\t00\t11000\t11000000\t98990\t\t\tEurope/Berlin\t2022-01-01
+2988507\tParis\tParis\t\t48.85341\t2.3488\tP\tPPLC\tFR\t\t11\t75\t751\t75056\t2138551\t\t42\tEurope/Paris\t2022-01-01
+4717560\tParis\tParis\t\t33.66094\t-95.55551\tP\tPPLA2\tUS\t\tTX\t277\t\t\t24782\t\t183\tAmerica/Chicago\t2022-01-01
+2867714\tMünchen\tMunich\t\t48.13743\t11.57549\tP\tPPLA\tDE\t\t02\t091\t09162\t09162000\t1260391\t\t524\tEurope/Berlin\t2022-01-01
+6255148\tEurope\tEurope\t\t48.69096\t9.14062\tL\tCONT\t\t\t00\t\t\t\t0\t\t\t\t2022-01-01
+2950158\tAlt-Berlin\tAlt-Berlin\t\t52.51667\t13.4\tP\tPPLH\tDE\t\t16\t00\t\t\t0\t\t\t\t2022-01-01
+";
+
+    fn gazetteer() -> Gazetteer {
+        Gazetteer::from_reader(GAZETTEER.as_bytes()).unwrap()
+    }
+
+    fn at(latitude: f64, longitude: f64) -> Coordinates {
+        Coordinates::new(latitude, longitude).unwrap()
+    }
+
+    fn contains(bounds: &BoundingBox, coordinates: Coordinates) -> bool {
+        (bounds.min_latitude..=bounds.max_latitude).contains(&coordinates.latitude)
+            && (bounds.min_longitude..=bounds.max_longitude).contains(&coordinates.longitude)
+    }
+
+    #[test]
+    fn keeps_only_inhabited_places() {
+        assert_eq!(gazetteer().place_count(), 6);
+        assert!(Gazetteer::from_reader("not\ta\tgazetteer".as_bytes()).is_err());
+    }
+
+    #[test]
+    fn finds_neighborhood_and_city() {
+        let gazetteer = gazetteer();
+        let reverse = gazetteer.reverse(at(52.4960, 13.4105));
+
+        assert_eq!(reverse.neighborhood.unwrap().name, "Kreuzberg");
+        assert_eq!(reverse.city.unwrap().name, "Berlin");
+    }
+
+    #[test]
+    fn finds_places_by_name() {
+        let gazetteer = gazetteer();
+
+        assert_eq!(gazetteer.find("kreuzberg").unwrap().name, "Kreuzberg");
+        assert_eq!(gazetteer.find(" Munich ").unwrap().name, "München");
+        assert_eq!(gazetteer.find("münchen").unwrap().name, "München");
+        assert_eq!(gazetteer.find("Atlantis"), None);
+        assert_eq!(gazetteer.find("Alt-Berlin"), None);
+    }
+
+    #[test]
+    fn prefers_the_most_populous_place_unless_a_country_is_given() {
+        let gazetteer = gazetteer();
+
+        assert_eq!(gazetteer.find("Paris").unwrap().country_code, "FR");
+        assert_eq!(gazetteer.find("Paris, us").unwrap().country_code, "US");
+        assert_eq!(gazetteer.find("Paris, DE"), None);
+    }
+
+    #[test]
+    fn ignores_places_that_are_too_far() {
+        let gazetteer = gazetteer();
+
+        // Spandau is close enough to Berlin's centre but not to any listed neighborhood
+        let spandau = gazetteer.reverse(at(52.5350, 13.2000));
+        assert_eq!(spandau.neighborhood, None);
+        assert_eq!(spandau.city.unwrap().name, "Berlin");
+
+        let atlantic = gazetteer.reverse(at(45.0, -30.0));
+        assert_eq!(atlantic.neighborhood, None);
+        assert_eq!(atlantic.city, None);
+    }
+
+    #[test]
+    fn coarsens_to_the_neighborhood_centre() {
+        let geo = Geo {
+            gazetteer: Some(gazetteer()),
+        };
+        let public = geo.public_location(at(52.4960, 13.4105));
+
+        assert_eq!((public.latitude, public.longitude), (52.49973, 13.40338));
+        assert_eq!(public.display_name().unwrap(), "Kreuzberg, Berlin");
+        assert_eq!(public.country_code.as_deref(), Some("DE"));
+    }
+
+    #[test]
+    fn coarsens_to_a_grid_cell_without_gazetteer() {
+        let geo = Geo::open(None).unwrap();
+        let precise = at(52.4961, 13.4105);
+        let public = geo.public_location(precise);
+
+        assert_eq!(public.display_name(), None);
+        assert!((public.latitude - 52.49).abs() < 1e-9);
+        assert!((public.longitude - 13.41).abs() < 1e-9);
+        // Everyone in the same cell gets the same public position
+        assert_eq!(public, geo.public_location(at(52.4999, 13.4001)));
+        assert!(haversine_km(precise, at(public.latitude, public.longitude)) < 2.0);
+    }
+
+    #[test]
+    fn bounds_every_point_within_the_radius() {
+        let center = at(52.52, 13.41);
+        let bounds = BoundingBox::around(center, 50.0);
+
+        assert!(bounds.min_longitude > 12.0 && bounds.max_longitude < 14.9);
+        for bearing in (0..360).step_by(15) {
+            let bearing = (bearing as f64).to_radians();
+            // 49.9 km along the bearing, computed with the spherical destination formula
+            let distance = 49.9 / EARTH_RADIUS_KM;
+            let lat = center.latitude.to_radians();
+            let lat2 =
+                (lat.sin() * distance.cos() + lat.cos() * distance.sin() * bearing.cos()).asin();
+            let lon2 = center.longitude.to_radians()
+                + (bearing.sin() * distance.sin() * lat.cos())
+                    .atan2(distance.cos() - lat.sin() * lat2.sin());
+            let point = at(lat2.to_degrees(), lon2.to_degrees());
+
+            assert!(haversine_km(center, point) < 50.0);
+            assert!(contains(&bounds, point));
+        }
+    }
+
+    #[test]
+    fn widens_boxes_at_the_poles_and_antimeridian() {
+        let polar = BoundingBox::around(at(89.9, 0.0), 50.0);
+        assert_eq!((polar.min_longitude, polar.max_longitude), (-180.0, 180.0));
+        assert_eq!(polar.max_latitude, 90.0);
+
+        let fiji = BoundingBox::around(at(-17.8, 179.9), 50.0);
+        assert_eq!((fiji.min_longitude, fiji.max_longitude), (-180.0, 180.0));
+        assert!(contains(&fiji, at(-17.8, -179.9)));
+    }
+
+    #[test]
+    fn measures_great_circle_distances() {
+        let berlin = at(52.52437, 13.41053);
+        let paris = at(48.85341, 2.3488);
+
+        assert!((haversine_km(berlin, paris) - 877.5).abs() < 1.0);
+        assert_eq!(haversine_km(berlin, berlin), 0.0);
+        assert!((haversine_km(at(0.0, 179.9), at(0.0, -179.9)) - 22.2).abs() < 0.1);
+    }
+}
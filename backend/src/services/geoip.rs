@@ -0,0 +1,189 @@
+use anyhow::{Context, Result};
+use maxminddb::{geoip2, MaxMindDBError, Reader};
+use std::net::IpAddr;
+use std::sync::Arc;
+
+use crate::models::Coordinates;
+
+/// Looks up client IPs in a locally bundled MaxMind-format city database.
+///
+/// No external service is ever queried; without a database the IP fallback is simply unavailable.
+pub struct GeoIp {
+    reader: Option<Reader<Vec<u8>>>,
+}
+
+pub type SharedGeoIp = Arc<GeoIp>;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct IpLocation {
+    pub coordinates: Coordinates,
+    pub accuracy_m: Option<f64>,
+}
+
+impl GeoIp {
+    pub fn open(path: Option<&str>) -> Result<Self> {
+        let reader = match path {
+            Some(path) => Some(
+                Reader::open_readfile(path)
+                    .with_context(|| format!("Failed to open GeoIP database {}", path))?,
+            ),
+            None => None,
+        };
+
+        Ok(Self { reader })
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.reader.is_some()
+    }
+
+    /// Returns `None` for addresses the database does not know, including private ones.
+    pub fn locate(&self, ip: IpAddr) -> Result<Option<IpLocation>> {
+        let Some(reader) = &self.reader else {
+            return Ok(None);
+        };
+
+        let city = match reader.lookup::<geoip2::City>(ip) {
+            Ok(city) => city,
+            Err(MaxMindDBError::AddressNotFoundError(_)) => return Ok(None),
+            Err(e) => return Err(e).context("GeoIP lookup failed"),
+        };
+
+        let Some(location) = city.location else {
+            return Ok(None);
+        };
+        let (Some(latitude), Some(longitude)) = (location.latitude, location.longitude) else {
+            return Ok(None);
+        };
+
+        Ok(Some(IpLocation {
+            coordinates: Coordinates::new(latitude, longitude)?,
+            // MaxMind reports the radius in kilometers
+            accuracy_m: location
+                .accuracy_radius
+                .map(|radius| f64::from(radius) * 1000.0),
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn control(data_type: u8, size: usize) -> Vec<u8> {
+        if data_type <= 7 {
+            vec![(data_type << 5) | size as u8]
+        } else {
+            vec![size as u8, data_type - 7]
+        }
+    }
+
+    fn string(value: &str) -> Vec<u8> {
+        let mut bytes = control(2, value.len());
+        bytes.extend_from_slice(value.as_bytes());
+        bytes
+    }
+
+    fn double(value: f64) -> Vec<u8> {
+        let mut bytes = control(3, 8);
+        bytes.extend_from_slice(&value.to_be_bytes());
+        bytes
+    }
+
+    fn unsigned(data_type: u8, value: u64, size: usize) -> Vec<u8> {
+        let mut bytes = control(data_type, size);
+        bytes.extend_from_slice(&value.to_be_bytes()[8 - size..]);
+        bytes
+    }
+
+    fn map(entries: Vec<(&str, Vec<u8>)>) -> Vec<u8> {
+        let mut bytes = control(7, entries.len());
+        for (key, value) in entries {
+            bytes.extend(string(key));
+            bytes.extend(value);
+        }
+        bytes
+    }
+
+    /// Builds an IPv4 city database with a single record for 81.0.0.0/8.
+    fn city_database() -> Vec<u8> {
+        let network: u8 = 81;
+        let node_count: u32 = 8;
+        let data_pointer = node_count + 16;
+
+        let mut database = Vec::new();
+        for depth in 0..8 {
+            let next = if depth == 7 {
+                data_pointer
+            } else {
+                depth as u32 + 1
+            };
+            let records = if network >> (7 - depth) & 1 == 1 {
+                [node_count, next]
+            } else {
+                [next, node_count]
+            };
+            for record in records {
+                database.extend_from_slice(&record.to_be_bytes()[1..]);
+            }
+        }
+        database.extend_from_slice(&[0; 16]);
+
+        database.extend(map(vec![(
+            "location",
+            map(vec![
+                ("accuracy_radius", unsigned(5, 20, 1)),
+                ("latitude", double(52.4986)),
+                ("longitude", double(13.4030)),
+            ]),
+        )]));
+
+        database.extend_from_slice(b"\xab\xcd\xefMaxMind.com");
+        database.extend(map(vec![
+            ("binary_format_major_version", unsigned(5, 2, 1)),
+            ("binary_format_minor_version", unsigned(5, 0, 1)),
+            ("build_epoch", unsigned(9, 1_700_000_000, 4)),
+            ("database_type", string("GeoLite2-City")),
+            ("description", map(vec![("en", string("Test"))])),
+            ("ip_version", unsigned(5, 4, 1)),
+            ("languages", control(11, 0)),
+            ("node_count", unsigned(6, node_count as u64, 1)),
+            ("record_size", unsigned(5, 24, 1)),
+        ]));
+
+        database
+    }
+
+    fn geoip() -> GeoIp {
+        GeoIp {
+            reader: Some(Reader::from_source(city_database()).unwrap()),
+        }
+    }
+
+    #[test]
+    fn locates_known_addresses() {
+        let location = geoip()
+            .locate("81.2.69.160".parse().unwrap())
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(location.coordinates, Coordinates::new(52.4986, 13.4030).unwrap());
+        assert_eq!(location.accuracy_m, Some(20_000.0));
+    }
+
+    #[test]
+    fn ignores_unknown_addresses() {
+        let geoip = geoip();
+
+        assert_eq!(geoip.locate("82.2.69.160".parse().unwrap()).unwrap(), None);
+        assert_eq!(geoip.locate("10.0.0.1".parse().unwrap()).unwrap(), None);
+    }
+
+    #[test]
+    fn is_disabled_without_a_database() {
+        let geoip = GeoIp::open(None).unwrap();
+
+        assert!(!geoip.is_enabled());
+        assert_eq!(geoip.locate("81.2.69.160".parse().unwrap()).unwrap(), None);
+    }
+}
@@ -0,0 +1,232 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use tokio::sync::mpsc;
+use tracing::warn;
+use uuid::Uuid;
+
+use crate::websocket::protocol::ServerMessage;
+
+/// Messages waiting to be written to one socket. A client this far behind is disconnected
+/// so it resynchronizes through the REST endpoints after reconnecting.
+pub const OUTBOX_CAPACITY: usize = 256;
+
+pub type ConnectionId = u64;
+
+/// What the hub queues for one socket.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Push {
+    /// A message for the client, which each transport serializes in its own framing.
+    Message(ServerMessage),
+    /// The user no longer sees the presence of this one, after a block either way.
+    Unwatch(Uuid),
+}
+
+/// Pushes for one socket, closed when the hub drops the connection.
+pub type Outbox = mpsc::Receiver<Arc<Push>>;
+
+type OutboxSender = mpsc::Sender<Arc<Push>>;
+
+#[derive(Debug, Default)]
+struct Connections {
+    next_id: ConnectionId,
+    by_user: HashMap<Uuid, Vec<(ConnectionId, OutboxSender)>>,
+}
+
+/// Every open socket of every user, so messages reach all the tabs a user has open.
+///
+/// Sending never waits: each socket has a bounded outbox and one that is full is dropped, so
+/// a slow client cannot hold up the sender or anyone else.
+#[derive(Debug, Clone, Default)]
+pub struct Hub {
+    connections: Arc<Mutex<Connections>>,
+}
+
+impl Hub {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn register(&self, user_id: Uuid) -> (ConnectionId, Outbox) {
+        let (sender, outbox) = mpsc::channel(OUTBOX_CAPACITY);
+        let mut connections = self.lock();
+        connections.next_id += 1;
+        let id = connections.next_id;
+        connections
+            .by_user
+            .entry(user_id)
+            .or_default()
+            .push((id, sender));
+
+        (id, outbox)
+    }
+
+    pub fn unregister(&self, user_id: Uuid, id: ConnectionId) {
+        let mut connections = self.lock();
+        if let Some(sockets) = connections.by_user.get_mut(&user_id) {
+            sockets.retain(|(socket_id, _)| *socket_id != id);
+            if sockets.is_empty() {
+                connections.by_user.remove(&user_id);
+            }
+        }
+    }
+
+    /// Closes every socket of the user once they have written what is already queued, after
+    /// the account is suspended or banned. The user cannot connect again until reinstated.
+    pub fn disconnect_user(&self, user_id: Uuid) -> usize {
+        self.lock()
+            .by_user
+            .remove(&user_id)
+            .map_or(0, |sockets| sockets.len())
+    }
+
+    /// Queues a message for every socket of the user. Returns how many took it.
+    pub fn send(&self, user_id: Uuid, message: &ServerMessage) -> usize {
+        self.send_except(user_id, None, message)
+    }
+
+    /// Like `send`, skipping the connection the message came from.
+    pub fn send_except(
+        &self,
+        user_id: Uuid,
+        except: Option<ConnectionId>,
+        message: &ServerMessage,
+    ) -> usize {
+        self.push(user_id, except, Push::Message(message.clone()))
+    }
+
+    /// Stops every socket of the user from following the presence of `other_id`.
+    ///
+    /// Going through the outboxes keeps it in order with everything else sent, and a socket
+    /// that misses it is dropped and filters blocks again once the client reconnects.
+    pub fn unwatch(&self, user_id: Uuid, other_id: Uuid) -> usize {
+        self.push(user_id, None, Push::Unwatch(other_id))
+    }
+
+    fn push(&self, user_id: Uuid, except: Option<ConnectionId>, push: Push) -> usize {
+        let push = Arc::new(push);
+        let mut connections = self.lock();
+        let Some(sockets) = connections.by_user.get_mut(&user_id) else {
+            return 0;
+        };
+        let mut queued = 0;
+        sockets.retain(|(id, sender)| {
+            if Some(*id) == except {
+                return true;
+            }
+            match sender.try_send(push.clone()) {
+                Ok(()) => {
+                    queued += 1;
+                    true
+                }
+                Err(mpsc::error::TrySendError::Full(_)) => {
+                    warn!("Dropping websocket {} of {}, its outbox is full", id, user_id);
+                    false
+                }
+                Err(mpsc::error::TrySendError::Closed(_)) => false,
+            }
+        });
+        if sockets.is_empty() {
+            connections.by_user.remove(&user_id);
+        }
+
+        queued
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Connections> {
+        self.connections
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn user() -> Uuid {
+        Uuid::from_u128(1)
+    }
+
+    #[test]
+    fn reaches_every_tab_of_the_user() {
+        let hub = Hub::new();
+        let (_, mut first) = hub.register(user());
+        let (_, mut second) = hub.register(user());
+        let (_, mut other) = hub.register(Uuid::from_u128(2));
+
+        assert_eq!(hub.send(user(), &ServerMessage::Pong), 2);
+        assert_eq!(*first.try_recv().unwrap(), Push::Message(ServerMessage::Pong));
+        assert_eq!(*second.try_recv().unwrap(), Push::Message(ServerMessage::Pong));
+        assert!(other.try_recv().is_err());
+    }
+
+    #[test]
+    fn queues_unwatching_in_order_with_messages() {
+        let hub = Hub::new();
+        let (_, mut outbox) = hub.register(user());
+        let other = Uuid::from_u128(2);
+
+        hub.send(user(), &ServerMessage::Pong);
+        assert_eq!(hub.unwatch(user(), other), 1);
+        assert_eq!(*outbox.try_recv().unwrap(), Push::Message(ServerMessage::Pong));
+        assert_eq!(*outbox.try_recv().unwrap(), Push::Unwatch(other));
+    }
+
+    #[test]
+    fn skips_the_sending_connection() {
+        let hub = Hub::new();
+        let (sender, mut sending) = hub.register(user());
+        let (_, mut other_tab) = hub.register(user());
+
+        assert_eq!(hub.send_except(user(), Some(sender), &ServerMessage::Pong), 1);
+        assert!(sending.try_recv().is_err());
+        assert!(other_tab.try_recv().is_ok());
+    }
+
+    #[test]
+    fn drops_a_full_outbox_without_holding_up_the_others() {
+        let hub = Hub::new();
+        let (_, mut slow) = hub.register(user());
+        let (_, mut fast) = hub.register(user());
+
+        for _ in 0..OUTBOX_CAPACITY {
+            hub.send(user(), &ServerMessage::Pong);
+            fast.try_recv().unwrap();
+        }
+        assert_eq!(hub.send(user(), &ServerMessage::Pong), 1);
+        assert!(fast.try_recv().is_ok());
+
+        // The slow socket gets what was queued, then sees its outbox closed
+        for _ in 0..OUTBOX_CAPACITY {
+            slow.try_recv().unwrap();
+        }
+        assert_eq!(slow.try_recv(), Err(mpsc::error::TryRecvError::Disconnected));
+    }
+
+    #[test]
+    fn disconnects_every_tab_of_the_user() {
+        let hub = Hub::new();
+        let (_, mut first) = hub.register(user());
+        let (_, mut second) = hub.register(user());
+        let (_, mut other) = hub.register(Uuid::from_u128(2));
+        hub.send(user(), &ServerMessage::Pong);
+
+        assert_eq!(hub.disconnect_user(user()), 2);
+        assert_eq!(*first.try_recv().unwrap(), Push::Message(ServerMessage::Pong));
+        assert_eq!(first.try_recv(), Err(mpsc::error::TryRecvError::Disconnected));
+        assert_eq!(second.try_recv().map(|_| ()), Ok(()));
+        assert_eq!(second.try_recv(), Err(mpsc::error::TryRecvError::Disconnected));
+        assert_eq!(other.try_recv(), Err(mpsc::error::TryRecvError::Empty));
+        assert_eq!(hub.send(user(), &ServerMessage::Pong), 0);
+    }
+
+    #[test]
+    fn forgets_closed_connections() {
+        let hub = Hub::new();
+        let (id, _outbox) = hub.register(user());
+        hub.unregister(user(), id);
+
+        assert_eq!(hub.send(user(), &ServerMessage::Pong), 0);
+        assert!(hub.lock().by_user.is_empty());
+    }
+}
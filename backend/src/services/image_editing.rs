@@ -0,0 +1,327 @@
+use image::{DynamicImage, Rgb, RgbImage};
+use serde::{Deserialize, Serialize};
+
+use crate::validation::core::{ValidationError, ValidationResult};
+
+pub const MAX_EDITS: usize = 50;
+
+/// One step of a declarative edit list, applied in order.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum Edit {
+    Crop {
+        x: u32,
+        y: u32,
+        width: u32,
+        height: u32,
+    },
+    /// Clockwise, in multiples of 90 degrees
+    Rotate {
+        degrees: i32,
+    },
+    Flip {
+        direction: FlipDirection,
+    },
+    /// -100 (black) to 100 (white)
+    Brightness {
+        value: i32,
+    },
+    /// -100 (flat grey) to 100 (double contrast)
+    Contrast {
+        value: i32,
+    },
+    Filter {
+        name: FilterName,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FlipDirection {
+    Horizontal,
+    Vertical,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FilterName {
+    Grayscale,
+    Sepia,
+    Invert,
+    Warm,
+    Cool,
+}
+
+/// Checks the whole list against the dimensions it will be applied to and returns the
+/// resulting dimensions, so nothing is rendered for an edit list that cannot succeed.
+pub fn validate_edits(edits: &[Edit], width: u32, height: u32) -> ValidationResult<(u32, u32)> {
+    if edits.len() > MAX_EDITS {
+        return Err(ValidationError::new(
+            "edits",
+            &format!("At most {} edits can be applied to a picture", MAX_EDITS),
+        ));
+    }
+
+    let (mut width, mut height) = (width, height);
+
+    for (index, edit) in edits.iter().enumerate() {
+        match edit {
+            Edit::Crop {
+                x,
+                y,
+                width: crop_width,
+                height: crop_height,
+            } => {
+                if *crop_width == 0 || *crop_height == 0 {
+                    return Err(edit_error(index, "Crop rectangle must not be empty"));
+                }
+                if x.saturating_add(*crop_width) > width || y.saturating_add(*crop_height) > height
+                {
+                    return Err(edit_error(
+                        index,
+                        &format!("Crop rectangle exceeds the {}x{} image", width, height),
+                    ));
+                }
+                width = *crop_width;
+                height = *crop_height;
+            }
+            Edit::Rotate { degrees } => {
+                if degrees % 90 != 0 {
+                    return Err(edit_error(index, "Rotation must be a multiple of 90 degrees"));
+                }
+                if degrees.rem_euclid(180) == 90 {
+                    std::mem::swap(&mut width, &mut height);
+                }
+            }
+            Edit::Brightness { value } | Edit::Contrast { value } => {
+                if !(-100..=100).contains(value) {
+                    return Err(edit_error(index, "Value must be between -100 and 100"));
+                }
+            }
+            Edit::Flip { .. } | Edit::Filter { .. } => {}
+        }
+    }
+
+    Ok((width, height))
+}
+
+/// Applies an edit list that passed `validate_edits` for this image's dimensions.
+pub fn apply_edits(image: DynamicImage, edits: &[Edit]) -> DynamicImage {
+    edits.iter().fold(image, |image, edit| match edit {
+        Edit::Crop {
+            x,
+            y,
+            width,
+            height,
+        } => image.crop_imm(*x, *y, *width, *height),
+        Edit::Rotate { degrees } => match degrees.rem_euclid(360) {
+            90 => image.rotate90(),
+            180 => image.rotate180(),
+            270 => image.rotate270(),
+            _ => image,
+        },
+        Edit::Flip {
+            direction: FlipDirection::Horizontal,
+        } => image.fliph(),
+        Edit::Flip {
+            direction: FlipDirection::Vertical,
+        } => image.flipv(),
+        Edit::Brightness { value } => image.brighten(value * 255 / 100),
+        Edit::Contrast { value } => image.adjust_contrast(*value as f32),
+        Edit::Filter { name } => apply_filter(&image, *name),
+    })
+}
+
+fn apply_filter(image: &DynamicImage, name: FilterName) -> DynamicImage {
+    match name {
+        FilterName::Grayscale => DynamicImage::ImageRgb8(image.grayscale().to_rgb8()),
+        FilterName::Invert => {
+            let mut inverted = image.clone();
+            inverted.invert();
+            inverted
+        }
+        FilterName::Sepia => map_pixels(image, |[r, g, b]| {
+            [
+                0.393 * r + 0.769 * g + 0.189 * b,
+                0.349 * r + 0.686 * g + 0.168 * b,
+                0.272 * r + 0.534 * g + 0.131 * b,
+            ]
+        }),
+        FilterName::Warm => map_pixels(image, |[r, g, b]| [r * 1.1 + 10.0, g * 1.02, b * 0.9]),
+        FilterName::Cool => map_pixels(image, |[r, g, b]| [r * 0.9, g * 1.02, b * 1.1 + 10.0]),
+    }
+}
+
+fn map_pixels(image: &DynamicImage, transform: impl Fn([f32; 3]) -> [f32; 3]) -> DynamicImage {
+    let source = image.to_rgb8();
+    let mapped = RgbImage::from_fn(source.width(), source.height(), |x, y| {
+        let Rgb([r, g, b]) = *source.get_pixel(x, y);
+        let [r, g, b] = transform([r as f32, g as f32, b as f32]);
+        Rgb([clamp_channel(r), clamp_channel(g), clamp_channel(b)])
+    });
+    DynamicImage::ImageRgb8(mapped)
+}
+
+fn clamp_channel(value: f32) -> u8 {
+    value.round().clamp(0.0, 255.0) as u8
+}
+
+fn edit_error(index: usize, message: &str) -> ValidationError {
+    ValidationError::new(&format!("edits[{}]", index), message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_image(width: u32, height: u32) -> DynamicImage {
+        let image =
+            RgbImage::from_fn(width, height, |x, y| Rgb([(x * 10) as u8, (y * 10) as u8, 100]));
+        DynamicImage::ImageRgb8(image)
+    }
+
+    fn pixel(image: &DynamicImage, x: u32, y: u32) -> [u8; 3] {
+        image.to_rgb8().get_pixel(x, y).0
+    }
+
+    #[test]
+    fn parses_edit_lists() {
+        let edits: Vec<Edit> = serde_json::from_str(
+            r#"[
+                {"op": "crop", "x": 1, "y": 2, "width": 3, "height": 4},
+                {"op": "rotate", "degrees": 270},
+                {"op": "flip", "direction": "horizontal"},
+                {"op": "brightness", "value": -20},
+                {"op": "contrast", "value": 15},
+                {"op": "filter", "name": "sepia"}
+            ]"#,
+        )
+        .unwrap();
+
+        assert_eq!(edits.len(), 6);
+        assert_eq!(
+            edits[0],
+            Edit::Crop {
+                x: 1,
+                y: 2,
+                width: 3,
+                height: 4
+            }
+        );
+        assert!(serde_json::from_str::<Edit>(r#"{"op": "filter", "name": "glitter"}"#).is_err());
+        assert!(serde_json::from_str::<Edit>(r#"{"op": "resize", "width": 10}"#).is_err());
+    }
+
+    #[test]
+    fn tracks_dimensions_through_the_list() {
+        let edits = vec![
+            Edit::Rotate { degrees: 90 },
+            Edit::Crop {
+                x: 0,
+                y: 10,
+                width: 20,
+                height: 30,
+            },
+            Edit::Rotate { degrees: -90 },
+        ];
+
+        assert_eq!(validate_edits(&edits, 60, 40).unwrap(), (30, 20));
+    }
+
+    #[test]
+    fn rejects_invalid_edits() {
+        let crop_outside = vec![Edit::Crop {
+            x: 50,
+            y: 0,
+            width: 20,
+            height: 10,
+        }];
+        let error = validate_edits(&crop_outside, 60, 40).unwrap_err();
+        assert_eq!(error.field, "edits[0]");
+
+        let crop_after_rotation = vec![
+            Edit::Rotate { degrees: 90 },
+            Edit::Crop {
+                x: 0,
+                y: 0,
+                width: 60,
+                height: 10,
+            },
+        ];
+        assert_eq!(
+            validate_edits(&crop_after_rotation, 60, 40)
+                .unwrap_err()
+                .field,
+            "edits[1]"
+        );
+
+        assert!(validate_edits(&[Edit::Rotate { degrees: 45 }], 60, 40).is_err());
+        assert!(validate_edits(&[Edit::Brightness { value: 101 }], 60, 40).is_err());
+        assert!(validate_edits(&[Edit::Contrast { value: -101 }], 60, 40).is_err());
+        assert!(validate_edits(&vec![Edit::Rotate { degrees: 0 }; MAX_EDITS + 1], 60, 40).is_err());
+    }
+
+    #[test]
+    fn applies_geometry_edits() {
+        let image = sample_image(6, 4);
+
+        let rotated = apply_edits(image.clone(), &[Edit::Rotate { degrees: 90 }]);
+        assert_eq!((rotated.width(), rotated.height()), (4, 6));
+        // The bottom-left pixel ends up in the top-left corner after a clockwise rotation
+        assert_eq!(pixel(&rotated, 0, 0), pixel(&image, 0, 3));
+
+        let flipped = apply_edits(
+            image.clone(),
+            &[Edit::Flip {
+                direction: FlipDirection::Horizontal,
+            }],
+        );
+        assert_eq!(pixel(&flipped, 0, 0), pixel(&image, 5, 0));
+
+        let cropped = apply_edits(
+            image.clone(),
+            &[Edit::Crop {
+                x: 2,
+                y: 1,
+                width: 3,
+                height: 2,
+            }],
+        );
+        assert_eq!((cropped.width(), cropped.height()), (3, 2));
+        assert_eq!(pixel(&cropped, 0, 0), pixel(&image, 2, 1));
+    }
+
+    #[test]
+    fn applies_tone_edits_and_filters() {
+        let image = sample_image(6, 4);
+
+        let bright = apply_edits(image.clone(), &[Edit::Brightness { value: 100 }]);
+        assert_eq!(pixel(&bright, 1, 1), [255, 255, 255]);
+
+        let gray = apply_edits(
+            image.clone(),
+            &[Edit::Filter {
+                name: FilterName::Grayscale,
+            }],
+        );
+        let [r, g, b] = pixel(&gray, 3, 2);
+        assert!(r == g && g == b);
+
+        let inverted = apply_edits(
+            image.clone(),
+            &[Edit::Filter {
+                name: FilterName::Invert,
+            }],
+        );
+        assert_eq!(pixel(&inverted, 0, 0), [255, 255, 155]);
+
+        let sepia = apply_edits(
+            image,
+            &[Edit::Filter {
+                name: FilterName::Sepia,
+            }],
+        );
+        let [r, g, b] = pixel(&sepia, 3, 2);
+        assert!(r >= g && g >= b);
+    }
+}
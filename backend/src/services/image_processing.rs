@@ -0,0 +1,282 @@
+use anyhow::{Context, Result};
+use image::{
+    codecs::jpeg::JpegEncoder, imageops::FilterType, DynamicImage, ImageDecoder, ImageReader,
+    Limits,
+};
+use std::io::Cursor;
+use strum::{Display, EnumIter, IntoEnumIterator};
+
+use crate::services::perceptual_hash;
+
+// Uploads larger than this are rejected before their pixels are allocated
+const MAX_SOURCE_DIMENSION: u32 = 12_000;
+
+// The master replaces the upload as the picture's original, so it is kept generous
+const MASTER_MAX_DIMENSION: u32 = 2560;
+const MASTER_JPEG_QUALITY: u8 = 92;
+
+const VARIANT_JPEG_QUALITY: u8 = 85;
+const VARIANT_WEBP_QUALITY: f32 = 80.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Display, EnumIter)]
+#[strum(serialize_all = "lowercase")]
+pub enum ImageVariant {
+    Thumbnail,
+    Card,
+    Full,
+}
+
+impl ImageVariant {
+    fn render(&self, image: &DynamicImage) -> DynamicImage {
+        match self {
+            ImageVariant::Thumbnail => image.resize_to_fill(160, 160, FilterType::Lanczos3),
+            ImageVariant::Card => image.resize_to_fill(480, 600, FilterType::Lanczos3),
+            ImageVariant::Full => fit_within(image, 1600),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Display, EnumIter)]
+#[strum(serialize_all = "lowercase")]
+pub enum ImageFormat {
+    Webp,
+    Jpeg,
+}
+
+impl ImageFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ImageFormat::Webp => "webp",
+            ImageFormat::Jpeg => "jpg",
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct EncodedImage {
+    pub width: u32,
+    pub height: u32,
+    pub data: Vec<u8>,
+}
+
+#[derive(Debug)]
+pub struct EncodedVariant {
+    pub variant: ImageVariant,
+    pub format: ImageFormat,
+    pub image: EncodedImage,
+}
+
+#[derive(Debug)]
+pub struct ProcessedPicture {
+    pub master: EncodedImage,
+    /// Perceptual hash of the unedited master, for duplicate detection
+    pub dhash: u64,
+    pub variants: Vec<EncodedVariant>,
+}
+
+/// Sniffs the upload's magic bytes; the extension and content type sent by the client are ignored.
+pub fn is_supported_upload(data: &[u8]) -> bool {
+    matches!(
+        image::guess_format(data),
+        Ok(image::ImageFormat::Jpeg | image::ImageFormat::Png | image::ImageFormat::WebP)
+    )
+}
+
+/// Turns an upload into a metadata-free master plus all responsive variants.
+///
+/// Re-encoding from raw pixels is what strips EXIF (including GPS coordinates),
+/// so the EXIF orientation has to be applied to the pixels first.
+pub fn process_picture(data: &[u8]) -> Result<ProcessedPicture> {
+    let image = decode_oriented(data)?;
+    let master = fit_within(&image, MASTER_MAX_DIMENSION);
+
+    Ok(ProcessedPicture {
+        master: encode_jpeg(&master, MASTER_JPEG_QUALITY)?,
+        dhash: perceptual_hash::dhash(&master),
+        variants: render_variants(&master)?,
+    })
+}
+
+pub fn decode_oriented(data: &[u8]) -> Result<DynamicImage> {
+    let mut limits = Limits::default();
+    limits.max_image_width = Some(MAX_SOURCE_DIMENSION);
+    limits.max_image_height = Some(MAX_SOURCE_DIMENSION);
+
+    let mut reader = ImageReader::new(Cursor::new(data))
+        .with_guessed_format()
+        .context("Failed to read image")?;
+    reader.limits(limits);
+
+    let mut decoder = reader.into_decoder().context("Unsupported image format")?;
+    let orientation = decoder
+        .orientation()
+        .context("Failed to read image orientation")?;
+    let mut image = DynamicImage::from_decoder(decoder).context("Failed to decode image")?;
+    image.apply_orientation(orientation);
+
+    // JPEG has no alpha channel, and the variants must look the same in both formats
+    Ok(DynamicImage::ImageRgb8(image.to_rgb8()))
+}
+
+pub fn render_variants(image: &DynamicImage) -> Result<Vec<EncodedVariant>> {
+    let mut variants = Vec::new();
+
+    for variant in ImageVariant::iter() {
+        let rendered = variant.render(image);
+        for format in ImageFormat::iter() {
+            let encoded = match format {
+                ImageFormat::Webp => encode_webp(&rendered, VARIANT_WEBP_QUALITY)?,
+                ImageFormat::Jpeg => encode_jpeg(&rendered, VARIANT_JPEG_QUALITY)?,
+            };
+            variants.push(EncodedVariant {
+                variant,
+                format,
+                image: encoded,
+            });
+        }
+    }
+
+    Ok(variants)
+}
+
+pub fn encode_jpeg(image: &DynamicImage, quality: u8) -> Result<EncodedImage> {
+    let rgb = image.to_rgb8();
+    let mut data = Vec::new();
+    JpegEncoder::new_with_quality(&mut data, quality)
+        .encode_image(&rgb)
+        .context("Failed to encode JPEG")?;
+
+    Ok(EncodedImage {
+        width: rgb.width(),
+        height: rgb.height(),
+        data,
+    })
+}
+
+pub fn encode_webp(image: &DynamicImage, quality: f32) -> Result<EncodedImage> {
+    let rgb = image.to_rgb8();
+    let data = webp::Encoder::from_rgb(rgb.as_raw(), rgb.width(), rgb.height())
+        .encode(quality)
+        .to_vec();
+
+    Ok(EncodedImage {
+        width: rgb.width(),
+        height: rgb.height(),
+        data,
+    })
+}
+
+/// Downscales so that neither side exceeds `max_dimension`; never upscales.
+fn fit_within(image: &DynamicImage, max_dimension: u32) -> DynamicImage {
+    if image.width() <= max_dimension && image.height() <= max_dimension {
+        return image.clone();
+    }
+    image.resize(max_dimension, max_dimension, FilterType::Lanczos3)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{Rgb, RgbImage};
+
+    fn sample_jpeg(width: u32, height: u32) -> Vec<u8> {
+        let image = RgbImage::from_fn(width, height, |x, _| Rgb([(x % 256) as u8, 64, 128]));
+        encode_jpeg(&DynamicImage::ImageRgb8(image), 90)
+            .unwrap()
+            .data
+    }
+
+    /// Splices an EXIF APP1 segment carrying an orientation tag and a GPS IFD pointer right after SOI.
+    fn with_exif_orientation(jpeg: &[u8], orientation: u16) -> Vec<u8> {
+        let mut tiff = vec![b'M', b'M', 0x00, 0x2A, 0x00, 0x00, 0x00, 0x08];
+        tiff.extend_from_slice(&[0x00, 0x02]); // two IFD entries
+        tiff.extend_from_slice(&[0x01, 0x12, 0x00, 0x03, 0x00, 0x00, 0x00, 0x01]); // Orientation, SHORT
+        tiff.extend_from_slice(&orientation.to_be_bytes());
+        tiff.extend_from_slice(&[0x00, 0x00]);
+        tiff.extend_from_slice(&[0x88, 0x25, 0x00, 0x04, 0x00, 0x00, 0x00, 0x01]); // GPS IFD, LONG
+        tiff.extend_from_slice(&[0x00, 0x00, 0x00, 0x26]);
+        tiff.extend_from_slice(&[0x00, 0x00, 0x00, 0x00]); // no next IFD
+        tiff.extend_from_slice(&[0x00, 0x00, 0x00, 0x00, 0x00, 0x00]); // empty GPS IFD
+
+        let mut payload = b"Exif\0\0".to_vec();
+        payload.extend_from_slice(&tiff);
+
+        let mut result = jpeg[..2].to_vec();
+        result.extend_from_slice(&[0xFF, 0xE1]);
+        result.extend_from_slice(&((payload.len() + 2) as u16).to_be_bytes());
+        result.extend_from_slice(&payload);
+        result.extend_from_slice(&jpeg[2..]);
+        result
+    }
+
+    fn contains(haystack: &[u8], needle: &[u8]) -> bool {
+        haystack
+            .windows(needle.len())
+            .any(|window| window == needle)
+    }
+
+    #[test]
+    fn detects_supported_uploads() {
+        assert!(is_supported_upload(&sample_jpeg(8, 8)));
+        assert!(!is_supported_upload(b"<svg xmlns=\"http://www.w3.org/2000/svg\"/>"));
+        assert!(!is_supported_upload(b""));
+    }
+
+    #[test]
+    fn applies_exif_orientation() {
+        let upload = with_exif_orientation(&sample_jpeg(80, 40), 6);
+        let image = decode_oriented(&upload).unwrap();
+
+        assert_eq!((image.width(), image.height()), (40, 80));
+    }
+
+    #[test]
+    fn strips_metadata_from_all_outputs() {
+        let upload = with_exif_orientation(&sample_jpeg(80, 40), 6);
+        assert!(contains(&upload, b"Exif"));
+
+        let processed = process_picture(&upload).unwrap();
+
+        assert!(!contains(&processed.master.data, b"Exif"));
+        for variant in &processed.variants {
+            assert!(!contains(&variant.image.data, b"Exif"));
+        }
+    }
+
+    #[test]
+    fn renders_every_variant_in_every_format() {
+        let processed = process_picture(&sample_jpeg(2000, 1000)).unwrap();
+
+        assert_eq!(processed.variants.len(), 6);
+        for variant in &processed.variants {
+            let decoded = image::load_from_memory(&variant.image.data).unwrap();
+            assert_eq!(decoded.width(), variant.image.width);
+            assert_eq!(decoded.height(), variant.image.height);
+
+            let expected = match variant.variant {
+                ImageVariant::Thumbnail => (160, 160),
+                ImageVariant::Card => (480, 600),
+                ImageVariant::Full => (1600, 800),
+            };
+            assert_eq!((variant.image.width, variant.image.height), expected);
+        }
+    }
+
+    #[test]
+    fn never_upscales() {
+        let processed = process_picture(&sample_jpeg(300, 200)).unwrap();
+
+        assert_eq!((processed.master.width, processed.master.height), (300, 200));
+        let full = processed
+            .variants
+            .iter()
+            .find(|variant| variant.variant == ImageVariant::Full)
+            .unwrap();
+        assert_eq!((full.image.width, full.image.height), (300, 200));
+    }
+
+    #[test]
+    fn rejects_garbage() {
+        assert!(process_picture(b"definitely not an image").is_err());
+    }
+}
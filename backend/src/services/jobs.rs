@@ -0,0 +1,372 @@
+use anyhow::{Context, Result};
+use sqlx::PgPool;
+use tokio::sync::mpsc;
+use tracing::{error, info, warn};
+use uuid::Uuid;
+
+use crate::database::picture_repository::{NewPictureVariant, PictureRepository};
+use crate::database::{ModerationRepository, PictureHashRepository, ReportRepository};
+use crate::enums::{FameTrigger, ModerationItemKind};
+use crate::models::Picture;
+use crate::services::fame::FameQueue;
+use crate::services::hub::Hub;
+use crate::services::storage::{content_type_for, SharedStorage};
+use crate::services::{image_editing, image_processing, moderation, perceptual_hash};
+
+const QUEUE_CAPACITY: usize = 256;
+
+#[derive(Debug, Clone)]
+pub enum Job {
+    ProcessPicture {
+        picture_id: Uuid,
+    },
+    /// Re-renders the variants of an already processed picture from its latest version
+    RenderPictureVersion {
+        picture_id: Uuid,
+    },
+}
+
+/// Handle for submitting work to the background worker, so requests can return early.
+#[derive(Clone)]
+pub struct JobQueue {
+    sender: mpsc::Sender<Job>,
+}
+
+impl JobQueue {
+    /// Spawns the worker and re-queues pictures left unprocessed by a previous run.
+    pub fn start(db: PgPool, storage: SharedStorage, fame: FameQueue, hub: Hub) -> Self {
+        let (sender, receiver) = mpsc::channel(QUEUE_CAPACITY);
+        let queue = Self { sender };
+
+        let worker = Worker {
+            db: db.clone(),
+            storage,
+            fame,
+            hub,
+        };
+        tokio::spawn(worker.run(receiver));
+
+        let resume_queue = queue.clone();
+        tokio::spawn(async move {
+            if let Err(e) = resume_queue.resume_unprocessed(db).await {
+                error!("Failed to resume unprocessed pictures: {:#}", e);
+            }
+        });
+
+        queue
+    }
+
+    pub async fn enqueue(&self, job: Job) -> Result<()> {
+        self.sender
+            .send(job)
+            .await
+            .map_err(|_| anyhow::anyhow!("Job queue is closed"))
+    }
+
+    async fn resume_unprocessed(&self, db: PgPool) -> Result<()> {
+        let pictures = PictureRepository::new(db).find_unfinished().await?;
+        if !pictures.is_empty() {
+            info!("Resuming processing of {} pictures", pictures.len());
+        }
+
+        for picture in pictures {
+            let job = match picture.processed_at {
+                Some(_) => Job::RenderPictureVersion {
+                    picture_id: picture.id,
+                },
+                None => Job::ProcessPicture {
+                    picture_id: picture.id,
+                },
+            };
+            self.enqueue(job).await?;
+        }
+
+        Ok(())
+    }
+}
+
+struct Worker {
+    db: PgPool,
+    storage: SharedStorage,
+    fame: FameQueue,
+    /// Closes the connections of users that reports suspend.
+    hub: Hub,
+}
+
+impl Worker {
+    async fn run(self, mut receiver: mpsc::Receiver<Job>) {
+        while let Some(job) = receiver.recv().await {
+            match job {
+                Job::ProcessPicture { picture_id } => {
+                    if let Err(e) = self.process_picture(picture_id).await {
+                        error!("Failed to process picture {}: {:#}", picture_id, e);
+                        let picture_repo = PictureRepository::new(self.db.clone());
+                        if let Err(e) = picture_repo.mark_failed(picture_id, &e.to_string()).await {
+                            error!("Failed to mark picture {} as failed: {:#}", picture_id, e);
+                        }
+                    }
+                }
+                Job::RenderPictureVersion { picture_id } => {
+                    if let Err(e) = self.render_picture_version(picture_id).await {
+                        error!("Failed to render picture {}: {:#}", picture_id, e);
+                    }
+                }
+            }
+        }
+    }
+
+    async fn process_picture(&self, picture_id: Uuid) -> Result<()> {
+        let picture_repo = PictureRepository::new(self.db.clone());
+
+        let picture = match picture_repo.find_by_id(picture_id).await? {
+            Some(picture) => picture,
+            None => {
+                warn!("Picture {} vanished before it could be processed", picture_id);
+                return Ok(());
+            }
+        };
+
+        picture_repo.mark_processing(picture_id).await?;
+
+        let upload = self
+            .storage
+            .get(&picture.original_path)
+            .await
+            .context("Failed to read upload")?;
+
+        // Decoding and resizing are CPU-bound, keep them off the async runtime
+        let processed =
+            tokio::task::spawn_blocking(move || image_processing::process_picture(&upload))
+                .await??;
+
+        let dhash = processed.dhash;
+        let picture_dir = picture_dir(picture.user_id, picture_id);
+
+        let original_path = format!("{}/original.jpg", picture_dir);
+        self.store(&original_path, processed.master.data).await?;
+
+        let variants = self
+            .store_variants(&picture_dir, processed.variants)
+            .await?;
+
+        picture_repo
+            .mark_ready(
+                picture_id,
+                &original_path,
+                processed.master.width as i32,
+                processed.master.height as i32,
+                &variants,
+            )
+            .await?;
+
+        // The raw upload still carries its EXIF metadata (GPS included), so it must not linger
+        if let Err(e) = self.storage.delete(&picture.original_path).await {
+            warn!("Failed to remove raw upload {}: {:#}", picture.original_path, e);
+        }
+
+        // A lookup failure must not fail a picture that is already stored and usable
+        if let Err(e) = self.flag_duplicates(&picture, dhash).await {
+            error!("Failed to check picture {} for duplicates: {:#}", picture_id, e);
+        }
+
+        // A first or new profile picture makes the profile more complete
+        self.fame
+            .schedule(picture.user_id, FameTrigger::ProfileUpdated, None);
+
+        info!("Processed picture {}", picture_id);
+
+        Ok(())
+    }
+
+    /// Renders the newest version of a picture, which stays shown with its previous
+    /// renditions until the new ones are in place.
+    async fn render_picture_version(&self, picture_id: Uuid) -> Result<()> {
+        let picture_repo = PictureRepository::new(self.db.clone());
+
+        let picture = match picture_repo.find_by_id(picture_id).await? {
+            Some(picture) => picture,
+            None => {
+                warn!("Picture {} vanished before it could be rendered", picture_id);
+                return Ok(());
+            }
+        };
+
+        let (version_number, edits) = match picture_repo.find_current_version(picture_id).await? {
+            // Already rendered by the job of an earlier edit
+            Some(version) if version.rendered_at.is_some() => return Ok(()),
+            Some(version) => (version.version, version.edits),
+            None => (0, serde_json::Value::Array(Vec::new())),
+        };
+
+        let rendered = self.render_version(&picture, version_number, edits).await;
+        if let Err(e) = &rendered {
+            if let Err(e) = picture_repo
+                .mark_render_failed(picture_id, version_number, &e.to_string())
+                .await
+            {
+                error!("Failed to record render failure of {}: {:#}", picture_id, e);
+            }
+        }
+        rendered
+    }
+
+    async fn render_version(
+        &self,
+        picture: &Picture,
+        version_number: i32,
+        edits: serde_json::Value,
+    ) -> Result<()> {
+        let picture_repo = PictureRepository::new(self.db.clone());
+        let edits: Vec<image_editing::Edit> = serde_json::from_value(edits)?;
+
+        let original = self
+            .storage
+            .get(&picture.original_path)
+            .await
+            .context("Failed to read original")?;
+
+        let variants = tokio::task::spawn_blocking(move || -> Result<_> {
+            let image = image_processing::decode_oriented(&original)?;
+            image_editing::validate_edits(&edits, image.width(), image.height())?;
+            image_processing::render_variants(&image_editing::apply_edits(image, &edits))
+        })
+        .await??;
+
+        // Every version gets its own keys, so links handed out for the previous one keep working
+        // until they expire instead of suddenly showing a different image
+        let version_dir =
+            format!("{}/v{}", picture_dir(picture.user_id, picture.id), version_number);
+        let previous = picture_repo.find_variants(picture.id).await?;
+        let variants = self.store_variants(&version_dir, variants).await?;
+
+        picture_repo
+            .mark_rendered(picture.id, version_number, &variants)
+            .await?;
+
+        for old in previous {
+            if variants.iter().all(|new| new.path != old.path) {
+                if let Err(e) = self.storage.delete(&old.path).await {
+                    warn!("Failed to remove old rendition {}: {:#}", old.path, e);
+                }
+            }
+        }
+
+        info!("Rendered version {} of picture {}", version_number, picture.id);
+
+        Ok(())
+    }
+
+    /// Stores the picture's hash and queues a moderation item for every other account's
+    /// picture it matches, since reusing someone else's photos is a strong fake-account signal.
+    async fn flag_duplicates(&self, picture: &Picture, dhash: u64) -> Result<()> {
+        let hash_repo = PictureHashRepository::new(self.db.clone());
+        let moderation_repo = ModerationRepository::new(self.db.clone());
+        let report_repo = ReportRepository::new(self.db.clone());
+
+        let bands = perceptual_hash::bands(dhash);
+        hash_repo
+            .upsert_hash(picture.id, picture.user_id, dhash, &bands)
+            .await?;
+
+        let duplicates = hash_repo
+            .find_near_duplicates(
+                picture.user_id,
+                dhash,
+                &bands,
+                perceptual_hash::MAX_DUPLICATE_DISTANCE,
+            )
+            .await?;
+
+        for duplicate in duplicates {
+            if !hash_repo
+                .record_duplicate(picture.id, duplicate.picture_id, duplicate.distance)
+                .await?
+            {
+                continue;
+            }
+
+            let details = serde_json::json!({
+                "picture_id": picture.id,
+                "matched_picture_id": duplicate.picture_id,
+                "matched_user_id": duplicate.user_id,
+                "distance": duplicate.distance,
+            });
+            moderation_repo
+                .enqueue(picture.user_id, ModerationItemKind::DuplicatePicture, &details)
+                .await?;
+
+            warn!(
+                "Picture {} of user {} duplicates picture {} of user {}",
+                picture.id, picture.user_id, duplicate.picture_id, duplicate.user_id
+            );
+
+            // Either account may be the one that copied, so both are reported for review
+            let report_details = format!(
+                "Picture {} is a near-duplicate of picture {}",
+                picture.id, duplicate.picture_id
+            );
+            for (reported_id, matched_user_id) in [
+                (picture.user_id, duplicate.user_id),
+                (duplicate.user_id, picture.user_id),
+            ] {
+                if let Some(report) = report_repo
+                    .create_automatic(reported_id, matched_user_id, &report_details)
+                    .await?
+                {
+                    moderation::suspend_if_reported_enough(
+                        &self.db,
+                        &self.hub,
+                        reported_id,
+                        &report,
+                    )
+                    .await;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn store_variants(
+        &self,
+        dir: &str,
+        encoded: Vec<image_processing::EncodedVariant>,
+    ) -> Result<Vec<NewPictureVariant>> {
+        let mut variants = Vec::new();
+        for variant in encoded {
+            let path = format!("{}/{}.{}", dir, variant.variant, variant.format.extension());
+            variants.push(NewPictureVariant {
+                variant: variant.variant.to_string(),
+                format: variant.format.to_string(),
+                path: path.clone(),
+                width: variant.image.width as i32,
+                height: variant.image.height as i32,
+                byte_size: variant.image.data.len() as i32,
+            });
+
+            self.store(&path, variant.image.data).await?;
+        }
+
+        Ok(variants)
+    }
+
+    async fn store(&self, key: &str, data: Vec<u8>) -> Result<()> {
+        self.storage.put(key, data, content_type_for(key)).await
+    }
+}
+
+fn picture_dir(user_id: Uuid, picture_id: Uuid) -> String {
+    format!("pictures/{}/{}", user_id, picture_id)
+}
+
+/// Stores a raw upload until the worker has processed it, returning its storage key.
+pub async fn store_upload(
+    storage: &SharedStorage,
+    upload_id: Uuid,
+    data: Vec<u8>,
+) -> Result<String> {
+    let key = format!("uploads/{}", upload_id);
+    storage.put(&key, data, "application/octet-stream").await?;
+
+    Ok(key)
+}
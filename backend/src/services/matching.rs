@@ -0,0 +1,208 @@
+use crate::database::query_builder::Sql;
+use crate::enums::{Gender, SexualPreference};
+use strum::IntoEnumIterator;
+
+/// What a user is and who they want to meet.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Orientation {
+    pub gender: Gender,
+    pub preference: SexualPreference,
+}
+
+impl Orientation {
+    /// Users who never said what they are looking for are treated as bisexual.
+    pub fn new(gender: Gender, preference: Option<SexualPreference>) -> Self {
+        Self {
+            gender,
+            preference: preference.unwrap_or(SexualPreference::Bisexual),
+        }
+    }
+
+    /// Whether this user wants to meet someone of `gender`.
+    ///
+    /// Heterosexual and homosexual are only defined between men and women, so users of
+    /// other genders are matched by bisexual users alone and only match bisexual users.
+    pub fn is_interested_in(&self, gender: Gender) -> bool {
+        match self.preference {
+            SexualPreference::Bisexual => true,
+            SexualPreference::Heterosexual => {
+                self.gender != Gender::Other && gender != Gender::Other && gender != self.gender
+            }
+            SexualPreference::Homosexual => self.gender != Gender::Other && gender == self.gender,
+        }
+    }
+
+    /// Whether either user may be shown to the other: both must be interested.
+    pub fn is_compatible_with(&self, other: &Orientation) -> bool {
+        self.is_interested_in(other.gender) && other.is_interested_in(self.gender)
+    }
+
+    /// Every orientation compatible with this one, for binding into queries.
+    pub fn compatible_orientations(&self) -> Vec<Orientation> {
+        Gender::iter()
+            .flat_map(|gender| {
+                SexualPreference::iter().map(move |preference| Orientation { gender, preference })
+            })
+            .filter(|other| self.is_compatible_with(other))
+            .collect()
+    }
+
+    /// Compatible genders and preferences as parallel arrays of names, for `UNNEST`ing in SQL.
+    pub fn compatible_orientation_names(&self) -> (Vec<String>, Vec<String>) {
+        self.compatible_orientations()
+            .iter()
+            .map(|other| (other.gender.to_string(), other.preference.to_string()))
+            .unzip()
+    }
+
+    /// Predicate matching rows whose orientation is compatible with this one.
+    ///
+    /// Queries checked at compile time cannot embed it, but apply the same rules by binding
+    /// `compatible_orientation_names` into the same `UNNEST` comparison.
+    pub fn sql_predicate(
+        &self,
+        gender_column: &'static str,
+        preference_column: &'static str,
+    ) -> Sql {
+        let (genders, preferences) = self.compatible_orientation_names();
+
+        Sql::new("(")
+            .push(gender_column)
+            .push("::text, COALESCE(")
+            .push(preference_column)
+            .push("::text, 'bisexual')) IN (SELECT * FROM UNNEST(")
+            .bind(genders)
+            .push("::text[], ")
+            .bind(preferences)
+            .push("::text[]))")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use Gender::{Female, Male, Other};
+    use SexualPreference::{Bisexual, Heterosexual, Homosexual};
+
+    fn orientation(gender: Gender, preference: SexualPreference) -> Orientation {
+        Orientation { gender, preference }
+    }
+
+    fn all_orientations() -> Vec<Orientation> {
+        Gender::iter()
+            .flat_map(|gender| {
+                SexualPreference::iter().map(move |preference| orientation(gender, preference))
+            })
+            .collect()
+    }
+
+    #[test]
+    fn defaults_a_missing_preference_to_bisexual() {
+        assert_eq!(Orientation::new(Male, None), orientation(Male, Bisexual));
+        assert_eq!(Orientation::new(Female, Some(Homosexual)), orientation(Female, Homosexual));
+    }
+
+    #[test]
+    fn matches_the_interest_table() {
+        // (gender, preference) -> interested in (male, female, other)
+        let table = [
+            (Male, Heterosexual, [false, true, false]),
+            (Male, Homosexual, [true, false, false]),
+            (Male, Bisexual, [true, true, true]),
+            (Female, Heterosexual, [true, false, false]),
+            (Female, Homosexual, [false, true, false]),
+            (Female, Bisexual, [true, true, true]),
+            (Other, Heterosexual, [false, false, false]),
+            (Other, Homosexual, [false, false, false]),
+            (Other, Bisexual, [true, true, true]),
+        ];
+        assert_eq!(table.len(), all_orientations().len());
+
+        for (gender, preference, expected) in table {
+            let viewer = orientation(gender, preference);
+            let actual = [Male, Female, Other].map(|other| viewer.is_interested_in(other));
+            assert_eq!(actual, expected, "{} {}", gender, preference);
+        }
+    }
+
+    #[test]
+    fn compatibility_is_symmetric() {
+        for a in all_orientations() {
+            for b in all_orientations() {
+                assert_eq!(a.is_compatible_with(&b), b.is_compatible_with(&a));
+            }
+        }
+    }
+
+    #[test]
+    fn compatibility_requires_mutual_interest() {
+        for a in all_orientations() {
+            for b in all_orientations() {
+                assert_eq!(
+                    a.is_compatible_with(&b),
+                    a.is_interested_in(b.gender) && b.is_interested_in(a.gender),
+                    "{:?} {:?}",
+                    a,
+                    b
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn matches_expected_pairs() {
+        let compatible = |a, b| orientation(a, Heterosexual).is_compatible_with(&b);
+        assert!(compatible(Male, orientation(Female, Heterosexual)));
+        assert!(compatible(Male, orientation(Female, Bisexual)));
+        assert!(!compatible(Male, orientation(Female, Homosexual)));
+        assert!(!compatible(Male, orientation(Male, Bisexual)));
+        assert!(!compatible(Male, orientation(Other, Bisexual)));
+
+        let other = orientation(Other, Bisexual);
+        assert!(other.is_compatible_with(&orientation(Male, Bisexual)));
+        assert!(other.is_compatible_with(&orientation(Other, Bisexual)));
+        assert!(!other.is_compatible_with(&orientation(Female, Heterosexual)));
+        assert!(!other.is_compatible_with(&orientation(Female, Homosexual)));
+
+        let gay = orientation(Male, Homosexual);
+        assert!(gay.is_compatible_with(&orientation(Male, Homosexual)));
+        assert!(gay.is_compatible_with(&orientation(Male, Bisexual)));
+        assert!(!gay.is_compatible_with(&orientation(Male, Heterosexual)));
+    }
+
+    #[test]
+    fn lists_exactly_the_compatible_orientations() {
+        for viewer in all_orientations() {
+            let listed = viewer.compatible_orientations();
+            for other in all_orientations() {
+                assert_eq!(
+                    listed.contains(&other),
+                    viewer.is_compatible_with(&other),
+                    "{:?} {:?}",
+                    viewer,
+                    other
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn names_compatible_orientations_in_parallel() {
+        let (genders, preferences) = orientation(Male, Heterosexual).compatible_orientation_names();
+
+        assert_eq!(genders, vec!["female", "female"]);
+        assert_eq!(preferences, vec!["heterosexual", "bisexual"]);
+    }
+
+    #[test]
+    fn builds_an_sql_predicate() {
+        let predicate =
+            orientation(Female, Homosexual).sql_predicate("p.gender", "p.sexual_preference");
+
+        assert_eq!(
+            predicate.build().sql(),
+            "(p.gender::text, COALESCE(p.sexual_preference::text, 'bisexual')) \
+             IN (SELECT * FROM UNNEST($1::text[], $2::text[]))"
+        );
+    }
+}
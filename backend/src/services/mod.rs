@@ -1 +1,19 @@
+pub mod browse;
+pub mod chat;
+pub mod cursor;
+pub mod fame;
+pub mod geo;
+pub mod geoip;
+pub mod hub;
+pub mod image_editing;
+pub mod image_processing;
+pub mod jobs;
 pub mod jwt;
+pub mod matching;
+pub mod moderation;
+pub mod notifications;
+pub mod perceptual_hash;
+pub mod presence;
+pub mod signing;
+pub mod storage;
+pub mod suspension;
@@ -0,0 +1,64 @@
+use sqlx::PgPool;
+use tracing::{error, warn};
+use uuid::Uuid;
+
+use crate::database::moderation_repository::StatusChange;
+use crate::database::report_repository::FiledReport;
+use crate::database::{ModerationRepository, UserRepository};
+use crate::enums::{AccountStatus, ModerationActionKind};
+use crate::models::NewModerationAction;
+use crate::services::hub::Hub;
+
+/// Reports awaiting review that suspend an account until a moderator looks at them.
+pub const AUTO_SUSPEND_REPORTS: i64 = 5;
+
+/// Suspends the reported account pending review once its queue item has
+/// `AUTO_SUSPEND_REPORTS` reports, closing its realtime connections. The report is stored
+/// already, so failures are only logged.
+pub async fn suspend_if_reported_enough(
+    db: &PgPool,
+    hub: &Hub,
+    reported_id: Uuid,
+    report: &FiledReport,
+) {
+    if report.pending_reports < AUTO_SUSPEND_REPORTS {
+        return;
+    }
+    match UserRepository::new(db.clone())
+        .find_by_id(reported_id)
+        .await
+    {
+        Ok(Some(user)) if user.is_active() => {}
+        Ok(_) => return,
+        Err(e) => {
+            error!("Failed to load reported user {}: {:#}", reported_id, e);
+            return;
+        }
+    }
+
+    let action = NewModerationAction::new(None, reported_id, ModerationActionKind::Suspend)
+        .reason(Some(format!("Reported by {} users, pending review", report.pending_reports)))
+        .moderation_item(report.moderation_item_id);
+    let moderation_repo = ModerationRepository::new(db.clone());
+    match moderation_repo
+        .change_account_status(&action, &[AccountStatus::Active], AccountStatus::Suspended)
+        .await
+    {
+        // A moderator acted on the account meanwhile
+        Ok(StatusChange::NotFound | StatusChange::Conflict) => {}
+        Ok(StatusChange::Changed) => {
+            hub.disconnect_user(reported_id);
+            warn!(
+                "Suspended user {} after {} reports, pending review",
+                reported_id, report.pending_reports
+            );
+            if let Err(e) = moderation_repo
+                .mark_auto_suspended(report.moderation_item_id)
+                .await
+            {
+                error!("Failed to note automatic suspension: {:#}", e);
+            }
+        }
+        Err(e) => error!("Failed to suspend reported user {}: {:#}", reported_id, e),
+    }
+}
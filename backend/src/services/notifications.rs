@@ -0,0 +1,23 @@
+use sqlx::PgPool;
+use tracing::error;
+use uuid::Uuid;
+
+use crate::database::NotificationRepository;
+use crate::enums::NotificationKind;
+use crate::services::hub::Hub;
+use crate::websocket::protocol::ServerMessage;
+
+/// Stores a notification caused by `actor_id` and pushes it to the open sockets of `user_id`.
+/// Callers have already done what the notification is about, so failures are only logged.
+pub async fn notify(db: &PgPool, hub: &Hub, user_id: Uuid, actor_id: Uuid, kind: NotificationKind) {
+    match NotificationRepository::new(db.clone())
+        .create(user_id, Some(actor_id), kind)
+        .await
+    {
+        Ok(Some(notification)) => {
+            hub.send(user_id, &ServerMessage::Notification { notification });
+        }
+        Ok(None) => {}
+        Err(e) => error!("Failed to notify {} ({}): {:#}", user_id, kind, e),
+    }
+}
@@ -0,0 +1,125 @@
+use image::{imageops::FilterType, DynamicImage};
+
+/// Hashes at most this many bits apart are treated as the same photo.
+///
+/// Re-encoding, resizing and mild colour changes stay well below it, while unrelated
+/// photos land around 32. It must stay below `BAND_COUNT` for band lookups to be exhaustive.
+pub const MAX_DUPLICATE_DISTANCE: u32 = 6;
+
+const BAND_COUNT: usize = 8;
+const BAND_BITS: usize = 64 / BAND_COUNT;
+
+/// 64-bit difference hash: each bit records whether a pixel of the 9x8 greyscale
+/// thumbnail is brighter than its right-hand neighbour.
+pub fn dhash(image: &DynamicImage) -> u64 {
+    let small = image
+        .grayscale()
+        .resize_exact(9, 8, FilterType::Triangle)
+        .to_luma8();
+
+    let mut hash = 0u64;
+    for y in 0..8 {
+        for x in 0..8 {
+            hash <<= 1;
+            if small.get_pixel(x, y).0[0] > small.get_pixel(x + 1, y).0[0] {
+                hash |= 1;
+            }
+        }
+    }
+
+    hash
+}
+
+/// Splits the hash into bands tagged with their position, for an exact-match index.
+///
+/// Two hashes within `MAX_DUPLICATE_DISTANCE` bits differ in fewer bands than there are,
+/// so they always share at least one band and a lookup on any shared band finds every candidate.
+pub fn bands(hash: u64) -> Vec<i32> {
+    (0..BAND_COUNT)
+        .map(|band| {
+            let value = (hash >> (band * BAND_BITS)) & ((1 << BAND_BITS) - 1);
+            (band << BAND_BITS) as i32 | value as i32
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{Rgb, RgbImage};
+
+    fn distance(a: u64, b: u64) -> u32 {
+        (a ^ b).count_ones()
+    }
+
+    fn photo(width: u32, height: u32, seed: u32) -> DynamicImage {
+        let image = RgbImage::from_fn(width, height, |x, y| {
+            let fx = x as f32 / width as f32;
+            let fy = y as f32 / height as f32;
+            let wave = ((fx * (3 + seed) as f32 * std::f32::consts::TAU).sin()
+                + (fy * (5 + seed * 2) as f32).cos())
+                * 60.0
+                + 128.0;
+            Rgb([wave as u8, (fy * 255.0) as u8, (fx * 200.0) as u8])
+        });
+        DynamicImage::ImageRgb8(image)
+    }
+
+    #[test]
+    fn counts_differing_bits() {
+        assert_eq!(distance(0, 0), 0);
+        assert_eq!(distance(0b1011, 0b0001), 2);
+        assert_eq!(distance(0, u64::MAX), 64);
+    }
+
+    #[test]
+    fn matches_resized_and_recompressed_copies() {
+        let original = photo(800, 600, 1);
+        let hash = dhash(&original);
+
+        let resized = original.resize(200, 150, FilterType::Lanczos3);
+        assert!(distance(hash, dhash(&resized)) <= MAX_DUPLICATE_DISTANCE);
+
+        let mut jpeg = Vec::new();
+        original
+            .write_to(&mut std::io::Cursor::new(&mut jpeg), image::ImageFormat::Jpeg)
+            .unwrap();
+        let recompressed = image::load_from_memory(&jpeg).unwrap();
+        assert!(distance(hash, dhash(&recompressed)) <= MAX_DUPLICATE_DISTANCE);
+
+        let brighter = original.brighten(20);
+        assert!(distance(hash, dhash(&brighter)) <= MAX_DUPLICATE_DISTANCE);
+    }
+
+    #[test]
+    fn separates_different_photos() {
+        let a = dhash(&photo(800, 600, 1));
+        let b = dhash(&photo(800, 600, 4));
+
+        assert!(distance(a, b) > MAX_DUPLICATE_DISTANCE);
+        assert!(distance(a, dhash(&photo(800, 600, 1).fliph())) > MAX_DUPLICATE_DISTANCE);
+    }
+
+    #[test]
+    fn near_hashes_share_a_band() {
+        let hash = 0x0123_4567_89ab_cdef;
+        let near = hash ^ 0x0000_0101_0101_0101;
+
+        assert_eq!(distance(hash, near), MAX_DUPLICATE_DISTANCE);
+        let shared = bands(hash)
+            .iter()
+            .filter(|band| bands(near).contains(band))
+            .count();
+        assert!(shared >= 1);
+    }
+
+    #[test]
+    fn tags_bands_with_their_position() {
+        let bands = bands(0xff00_0000_0000_00ff);
+
+        assert_eq!(bands.len(), BAND_COUNT);
+        assert_eq!(bands[0], 0x0ff);
+        assert_eq!(bands[1], 0x100);
+        assert_eq!(bands[7], 0x7ff);
+    }
+}
@@ -0,0 +1,323 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sqlx::PgPool;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::broadcast;
+use tracing::error;
+use uuid::Uuid;
+
+use crate::database::PresenceRepository;
+
+/// How long a user without open sockets stays online after their last request.
+pub const IDLE_TIMEOUT: chrono::Duration = chrono::Duration::minutes(2);
+
+/// How often users who went idle are announced and last-seen times are written out.
+const SWEEP_INTERVAL: Duration = Duration::from_secs(30);
+
+const CHANGES_CAPACITY: usize = 1024;
+
+/// A user coming online or going offline.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PresenceChange {
+    pub user_id: Uuid,
+    pub online: bool,
+    pub last_seen_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PresenceStatus {
+    pub online: bool,
+    pub last_seen_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone)]
+struct Entry {
+    sockets: usize,
+    last_seen_at: DateTime<Utc>,
+    // Whether the last announcement said online, so each transition is published once
+    announced_online: bool,
+    flushed: bool,
+}
+
+impl Entry {
+    fn is_online(&self, now: DateTime<Utc>) -> bool {
+        self.sockets > 0 || now - self.last_seen_at < IDLE_TIMEOUT
+    }
+}
+
+/// Presence of every recently active user. Time is passed in so the rules can be tested.
+#[derive(Debug, Default)]
+struct PresenceMap {
+    entries: HashMap<Uuid, Entry>,
+}
+
+impl PresenceMap {
+    fn touch(&mut self, user_id: Uuid, now: DateTime<Utc>) -> Option<PresenceChange> {
+        let entry = self.entries.entry(user_id).or_insert(Entry {
+            sockets: 0,
+            last_seen_at: now,
+            announced_online: false,
+            flushed: false,
+        });
+        entry.last_seen_at = entry.last_seen_at.max(now);
+        entry.flushed = false;
+
+        Self::announce(user_id, entry, now)
+    }
+
+    fn connect(&mut self, user_id: Uuid, now: DateTime<Utc>) -> Option<PresenceChange> {
+        let change = self.touch(user_id, now);
+        if let Some(entry) = self.entries.get_mut(&user_id) {
+            entry.sockets += 1;
+        }
+        change
+    }
+
+    /// Going offline is left to `sweep`, so reloading a page does not flicker.
+    fn disconnect(&mut self, user_id: Uuid, now: DateTime<Utc>) {
+        self.touch(user_id, now);
+        if let Some(entry) = self.entries.get_mut(&user_id) {
+            entry.sockets = entry.sockets.saturating_sub(1);
+        }
+    }
+
+    fn status(&self, user_id: Uuid, now: DateTime<Utc>) -> Option<PresenceStatus> {
+        self.entries.get(&user_id).map(|entry| PresenceStatus {
+            online: entry.is_online(now),
+            last_seen_at: entry.last_seen_at,
+        })
+    }
+
+    /// Announces users who went idle and returns the last-seen times not yet written out.
+    /// Offline users whose time was written out are forgotten.
+    fn sweep(&mut self, now: DateTime<Utc>) -> (Vec<PresenceChange>, Vec<(Uuid, DateTime<Utc>)>) {
+        let mut changes = Vec::new();
+        let mut unflushed = Vec::new();
+
+        for (&user_id, entry) in self.entries.iter_mut() {
+            changes.extend(Self::announce(user_id, entry, now));
+            if !entry.flushed {
+                unflushed.push((user_id, entry.last_seen_at));
+                entry.flushed = true;
+            }
+        }
+        self.entries
+            .retain(|_, entry| entry.is_online(now) || !entry.flushed);
+
+        (changes, unflushed)
+    }
+
+    /// Puts back last-seen times that could not be written out, unless newer ones arrived.
+    fn unflush(&mut self, times: &[(Uuid, DateTime<Utc>)]) {
+        for &(user_id, last_seen_at) in times {
+            let entry = self.entries.entry(user_id).or_insert(Entry {
+                sockets: 0,
+                last_seen_at,
+                announced_online: false,
+                flushed: false,
+            });
+            entry.flushed = false;
+        }
+    }
+
+    fn announce(user_id: Uuid, entry: &mut Entry, now: DateTime<Utc>) -> Option<PresenceChange> {
+        let online = entry.is_online(now);
+        if online == entry.announced_online {
+            return None;
+        }
+        entry.announced_online = online;
+
+        Some(PresenceChange {
+            user_id,
+            online,
+            last_seen_at: entry.last_seen_at,
+        })
+    }
+}
+
+/// Tracks who is online from their sockets and requests, and publishes changes.
+///
+/// Last-seen times live in memory and are written to the database periodically, so
+/// request activity costs no queries.
+#[derive(Clone)]
+pub struct Presence {
+    map: Arc<Mutex<PresenceMap>>,
+    changes: broadcast::Sender<PresenceChange>,
+}
+
+impl Presence {
+    /// Spawns the task announcing idle users and writing out last-seen times.
+    pub fn start(db: PgPool) -> Self {
+        let (changes, _) = broadcast::channel(CHANGES_CAPACITY);
+        let presence = Self {
+            map: Arc::new(Mutex::new(PresenceMap::default())),
+            changes,
+        };
+
+        let sweeper = presence.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(SWEEP_INTERVAL);
+            loop {
+                interval.tick().await;
+                sweeper.sweep(&db).await;
+            }
+        });
+
+        presence
+    }
+
+    /// Records activity such as an authenticated request or a socket heartbeat.
+    pub fn touch(&self, user_id: Uuid) {
+        let change = self.lock().touch(user_id, Utc::now());
+        self.publish(change);
+    }
+
+    pub fn connect(&self, user_id: Uuid) {
+        let change = self.lock().connect(user_id, Utc::now());
+        self.publish(change);
+    }
+
+    pub fn disconnect(&self, user_id: Uuid) {
+        self.lock().disconnect(user_id, Utc::now());
+    }
+
+    /// `None` for users not seen since the server started; the database knows more.
+    pub fn status(&self, user_id: Uuid) -> Option<PresenceStatus> {
+        self.lock().status(user_id, Utc::now())
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<PresenceChange> {
+        self.changes.subscribe()
+    }
+
+    async fn sweep(&self, db: &PgPool) {
+        let (changes, unflushed) = self.lock().sweep(Utc::now());
+        for change in changes {
+            self.publish(Some(change));
+        }
+
+        if unflushed.is_empty() {
+            return;
+        }
+        if let Err(e) = PresenceRepository::new(db.clone())
+            .save_last_seen(&unflushed)
+            .await
+        {
+            error!("Failed to save last seen times: {:#}", e);
+            self.lock().unflush(&unflushed);
+        }
+    }
+
+    fn publish(&self, change: Option<PresenceChange>) {
+        if let Some(change) = change {
+            // Sending only fails without subscribers, when nobody needs to know
+            let _ = self.changes.send(change);
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, PresenceMap> {
+        self.map
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(seconds: i64) -> DateTime<Utc> {
+        DateTime::from_timestamp(1_800_000_000 + seconds, 0).unwrap()
+    }
+
+    fn user() -> Uuid {
+        Uuid::from_u128(1)
+    }
+
+    #[test]
+    fn announces_coming_online_once() {
+        let mut map = PresenceMap::default();
+
+        let change = map.touch(user(), at(0)).unwrap();
+        assert!(change.online);
+        assert_eq!(change.last_seen_at, at(0));
+        assert_eq!(map.touch(user(), at(10)), None);
+        assert_eq!(map.connect(user(), at(20)), None);
+    }
+
+    #[test]
+    fn stays_online_while_a_socket_is_open() {
+        let mut map = PresenceMap::default();
+        map.connect(user(), at(0));
+
+        let (changes, _) = map.sweep(at(3600));
+        assert!(changes.is_empty());
+        assert!(map.status(user(), at(3600)).unwrap().online);
+    }
+
+    #[test]
+    fn goes_offline_once_idle_after_the_last_socket_closes() {
+        let mut map = PresenceMap::default();
+        map.connect(user(), at(0));
+        map.connect(user(), at(0));
+        map.disconnect(user(), at(10));
+        map.disconnect(user(), at(20));
+
+        assert!(map.status(user(), at(30)).unwrap().online);
+        assert!(map.sweep(at(30)).0.is_empty());
+
+        let idle = at(20) + IDLE_TIMEOUT;
+        let (changes, _) = map.sweep(idle);
+        assert_eq!(
+            changes,
+            vec![PresenceChange {
+                user_id: user(),
+                online: false,
+                last_seen_at: at(20),
+            }]
+        );
+    }
+
+    #[test]
+    fn flushes_each_last_seen_time_once() {
+        let mut map = PresenceMap::default();
+        map.touch(user(), at(0));
+
+        assert_eq!(map.sweep(at(1)).1, vec![(user(), at(0))]);
+        assert!(map.sweep(at(2)).1.is_empty());
+
+        map.touch(user(), at(3));
+        assert_eq!(map.sweep(at(4)).1, vec![(user(), at(3))]);
+    }
+
+    #[test]
+    fn forgets_offline_users_once_flushed() {
+        let mut map = PresenceMap::default();
+        map.touch(user(), at(0));
+
+        let idle = at(0) + IDLE_TIMEOUT;
+        map.sweep(idle);
+        assert_eq!(map.status(user(), idle), None);
+    }
+
+    #[test]
+    fn retries_failed_flushes() {
+        let mut map = PresenceMap::default();
+        map.touch(user(), at(0));
+        let (_, unflushed) = map.sweep(at(1));
+
+        map.unflush(&unflushed);
+        assert_eq!(map.sweep(at(2)).1, vec![(user(), at(0))]);
+    }
+
+    #[test]
+    fn never_moves_last_seen_backwards() {
+        let mut map = PresenceMap::default();
+        map.touch(user(), at(10));
+        map.touch(user(), at(5));
+
+        assert_eq!(map.status(user(), at(11)).unwrap().last_seen_at, at(10));
+    }
+}
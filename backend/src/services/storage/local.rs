@@ -0,0 +1,139 @@
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use std::path::PathBuf;
+
+use super::{is_valid_key, MediaSigner, Storage};
+
+/// Stores pictures below a directory on the local disk and serves them through the `/media` route.
+pub struct LocalStorage {
+    root: PathBuf,
+    base_url: String,
+    signer: MediaSigner,
+}
+
+impl LocalStorage {
+    pub fn new(root: &str, public_url: &str, signer: MediaSigner) -> Self {
+        Self {
+            root: PathBuf::from(root),
+            base_url: format!("{}/media", public_url.trim_end_matches('/')),
+            signer,
+        }
+    }
+
+    fn path_for(&self, key: &str) -> Result<PathBuf> {
+        if !is_valid_key(key) {
+            anyhow::bail!("Invalid storage key: {}", key);
+        }
+        Ok(self.root.join(key))
+    }
+}
+
+#[async_trait]
+impl Storage for LocalStorage {
+    async fn put(&self, key: &str, data: Vec<u8>, _content_type: &str) -> Result<()> {
+        let path = self.path_for(key)?;
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+        tokio::fs::write(&path, data)
+            .await
+            .with_context(|| format!("Failed to write {}", path.display()))
+    }
+
+    async fn get(&self, key: &str) -> Result<Vec<u8>> {
+        let path = self.path_for(key)?;
+        tokio::fs::read(&path)
+            .await
+            .with_context(|| format!("Failed to read {}", path.display()))
+    }
+
+    async fn delete(&self, key: &str) -> Result<()> {
+        let path = self.path_for(key)?;
+        match tokio::fs::remove_file(&path).await {
+            Ok(()) => Ok(()),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+            Err(e) => Err(e).with_context(|| format!("Failed to delete {}", path.display())),
+        }
+    }
+
+    fn signed_url(&self, key: &str, expires_at: DateTime<Utc>) -> Result<String> {
+        if !is_valid_key(key) {
+            anyhow::bail!("Invalid storage key: {}", key);
+        }
+
+        let expires = expires_at.timestamp();
+        Ok(format!(
+            "{}/{}?expires={}&signature={}",
+            self.base_url,
+            key,
+            expires,
+            self.signer.sign(key, expires)
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    fn storage(root: &std::path::Path) -> LocalStorage {
+        LocalStorage::new(
+            root.to_str().unwrap(),
+            "http://localhost:3000/",
+            MediaSigner::new("a-secret-that-is-long-enough-for-tests"),
+        )
+    }
+
+    #[tokio::test]
+    async fn stores_and_deletes_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let storage = storage(dir.path());
+
+        storage
+            .put("pictures/a/b/card.jpg", b"data".to_vec(), "image/jpeg")
+            .await
+            .unwrap();
+        assert_eq!(storage.get("pictures/a/b/card.jpg").await.unwrap(), b"data");
+
+        storage.delete("pictures/a/b/card.jpg").await.unwrap();
+        assert!(storage.get("pictures/a/b/card.jpg").await.is_err());
+
+        // Deleting twice is not an error
+        storage.delete("pictures/a/b/card.jpg").await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn refuses_keys_outside_the_root() {
+        let dir = tempfile::tempdir().unwrap();
+        let storage = storage(dir.path());
+
+        assert!(storage
+            .put("../escape.jpg", b"data".to_vec(), "image/jpeg")
+            .await
+            .is_err());
+        assert!(storage.get("/etc/passwd").await.is_err());
+    }
+
+    #[test]
+    fn signs_media_urls() {
+        let dir = tempfile::tempdir().unwrap();
+        let storage = storage(dir.path());
+        let expires_at = Utc::now() + Duration::minutes(5);
+
+        let url = storage
+            .signed_url("pictures/a/b/card.jpg", expires_at)
+            .unwrap();
+
+        let prefix = format!(
+            "http://localhost:3000/media/pictures/a/b/card.jpg?expires={}&signature=",
+            expires_at.timestamp()
+        );
+        assert!(url.starts_with(&prefix));
+        let signature = &url[prefix.len()..];
+        assert!(storage
+            .signer
+            .verify("pictures/a/b/card.jpg", expires_at.timestamp(), signature));
+    }
+}
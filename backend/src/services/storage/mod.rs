@@ -0,0 +1,164 @@
+use anyhow::Result;
+use async_trait::async_trait;
+use chrono::{DateTime, Duration, Utc};
+use std::sync::Arc;
+
+use crate::config::Config;
+use crate::enums::StorageBackend;
+use crate::services::signing::Signer;
+
+pub mod local;
+pub mod s3;
+
+pub use local::LocalStorage;
+pub use s3::S3Storage;
+
+/// How long a URL handed out for a private picture stays valid.
+pub const SIGNED_URL_LIFETIME_MINUTES: i64 = 60;
+
+/// Backend-agnostic blob storage for pictures, addressed by slash-separated keys.
+#[async_trait]
+pub trait Storage: Send + Sync {
+    async fn put(&self, key: &str, data: Vec<u8>, content_type: &str) -> Result<()>;
+
+    async fn get(&self, key: &str) -> Result<Vec<u8>>;
+
+    async fn delete(&self, key: &str) -> Result<()>;
+
+    /// A URL granting read access to `key` until `expires_at`, which cannot be derived
+    /// from the key alone.
+    fn signed_url(&self, key: &str, expires_at: DateTime<Utc>) -> Result<String>;
+}
+
+pub type SharedStorage = Arc<dyn Storage>;
+
+pub fn create_storage(config: &Config, signer: MediaSigner) -> Result<SharedStorage> {
+    let storage: SharedStorage = match config.storage_backend {
+        StorageBackend::Local => {
+            Arc::new(LocalStorage::new(&config.media_dir, &config.public_url, signer))
+        }
+        StorageBackend::S3 => {
+            let s3_config = config
+                .s3
+                .as_ref()
+                .ok_or_else(|| anyhow::anyhow!("S3 storage selected but not configured"))?;
+            Arc::new(S3Storage::new(s3_config)?)
+        }
+    };
+
+    Ok(storage)
+}
+
+pub fn signed_url_expiry() -> DateTime<Utc> {
+    Utc::now() + Duration::minutes(SIGNED_URL_LIFETIME_MINUTES)
+}
+
+pub fn content_type_for(key: &str) -> &'static str {
+    match key.rsplit('.').next() {
+        Some("jpg") | Some("jpeg") => "image/jpeg",
+        Some("webp") => "image/webp",
+        Some("png") => "image/png",
+        _ => "application/octet-stream",
+    }
+}
+
+/// Rejects keys that could escape the storage root or address something other than a file.
+pub fn is_valid_key(key: &str) -> bool {
+    !key.is_empty()
+        && key.split('/').all(|segment| {
+            !segment.is_empty()
+                && segment != "."
+                && segment != ".."
+                && segment
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+        })
+}
+
+/// Signs `/media` URLs so that only links handed out by the API can be used to fetch a picture.
+#[derive(Clone)]
+pub struct MediaSigner {
+    signer: Signer,
+}
+
+impl MediaSigner {
+    pub fn new(secret: &str) -> Self {
+        Self {
+            signer: Signer::new(secret, "media"),
+        }
+    }
+
+    pub fn sign(&self, key: &str, expires: i64) -> String {
+        hex::encode(self.signer.sign(&Self::payload(key, expires)))
+    }
+
+    pub fn verify(&self, key: &str, expires: i64, signature: &str) -> bool {
+        if expires < Utc::now().timestamp() {
+            return false;
+        }
+
+        match hex::decode(signature) {
+            Ok(signature) => self.signer.verify(&Self::payload(key, expires), &signature),
+            Err(_) => false,
+        }
+    }
+
+    fn payload(key: &str, expires: i64) -> Vec<u8> {
+        format!("{}\n{}", key, expires).into_bytes()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn verifies_own_signatures() {
+        let signer = MediaSigner::new("a-secret-that-is-long-enough-for-tests");
+        let expires = Utc::now().timestamp() + 60;
+        let signature = signer.sign("pictures/a/b/card.webp", expires);
+
+        assert!(signer.verify("pictures/a/b/card.webp", expires, &signature));
+    }
+
+    #[test]
+    fn rejects_tampered_urls() {
+        let signer = MediaSigner::new("a-secret-that-is-long-enough-for-tests");
+        let expires = Utc::now().timestamp() + 60;
+        let signature = signer.sign("pictures/a/b/card.webp", expires);
+
+        assert!(!signer.verify("pictures/a/c/card.webp", expires, &signature));
+        assert!(!signer.verify("pictures/a/b/card.webp", expires + 1, &signature));
+        assert!(!signer.verify("pictures/a/b/card.webp", expires, "not-hex"));
+
+        let other = MediaSigner::new("another-secret-that-is-long-enough");
+        assert!(!other.verify("pictures/a/b/card.webp", expires, &signature));
+    }
+
+    #[test]
+    fn rejects_expired_urls() {
+        let signer = MediaSigner::new("a-secret-that-is-long-enough-for-tests");
+        let expires = Utc::now().timestamp() - 1;
+        let signature = signer.sign("pictures/a/b/card.webp", expires);
+
+        assert!(!signer.verify("pictures/a/b/card.webp", expires, &signature));
+    }
+
+    #[test]
+    fn validates_keys() {
+        assert!(is_valid_key("pictures/1f0c/2a9b/thumbnail.jpg"));
+        assert!(is_valid_key("uploads/1f0c"));
+        assert!(!is_valid_key(""));
+        assert!(!is_valid_key("/etc/passwd"));
+        assert!(!is_valid_key("pictures/../../etc/passwd"));
+        assert!(!is_valid_key("pictures//card.jpg"));
+        assert!(!is_valid_key("pictures/%2e%2e/card.jpg"));
+    }
+
+    #[test]
+    fn derives_content_type_from_extension() {
+        assert_eq!(content_type_for("a/b/full.jpg"), "image/jpeg");
+        assert_eq!(content_type_for("a/b/full.webp"), "image/webp");
+        assert_eq!(content_type_for("uploads/1f0c"), "application/octet-stream");
+    }
+}
@@ -0,0 +1,51 @@
+use anyhow::Result;
+use sqlx::PgPool;
+use std::time::Duration;
+use tracing::{error, info};
+
+use crate::database::ModerationRepository;
+
+/// How often suspensions that ended are lifted.
+pub const EXPIRY_INTERVAL: Duration = Duration::from_secs(60);
+
+const BATCH_SIZE: i64 = 100;
+
+/// Spawns the periodic reinstatement of accounts whose suspension ended.
+pub fn start_expiry(db: PgPool) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(EXPIRY_INTERVAL);
+        loop {
+            interval.tick().await;
+            match lift_expired(&db).await {
+                Ok(0) => {}
+                Ok(lifted) => info!("Lifted {} expired suspensions", lifted),
+                Err(e) => error!("Failed to lift expired suspensions: {:#}", e),
+            }
+        }
+    });
+}
+
+/// Reinstates every account whose suspension ended. Returns how many were.
+async fn lift_expired(db: &PgPool) -> Result<usize> {
+    let moderation_repo = ModerationRepository::new(db.clone());
+    let mut lifted = 0;
+
+    loop {
+        let user_ids = moderation_repo.find_expired_suspensions(BATCH_SIZE).await?;
+        if user_ids.is_empty() {
+            return Ok(lifted);
+        }
+
+        let mut lifted_in_batch = 0;
+        for user_id in user_ids {
+            if moderation_repo.lift_expired_suspension(user_id).await? {
+                lifted_in_batch += 1;
+            }
+        }
+        // Accounts found but not lifted were changed meanwhile and would be found again
+        if lifted_in_batch == 0 {
+            return Ok(lifted);
+        }
+        lifted += lifted_in_batch;
+    }
+}
@@ -0,0 +1,123 @@
+use serde::Serialize;
+
+/// Separates packets in a long-polling payload.
+pub const RECORD_SEPARATOR: char = '\u{1e}';
+
+/// Engine.IO v4 packets, in their text encoding. Binary packets are not supported.
+#[derive(Debug, Clone, PartialEq)]
+pub enum EnginePacket {
+    /// The handshake, as JSON.
+    Open(String),
+    Close,
+    /// `probe` while a transport upgrade is being tested.
+    Ping(String),
+    Pong(String),
+    /// A Socket.IO packet.
+    Message(String),
+    Upgrade,
+    /// Ends a pending poll once the client moved to a WebSocket.
+    Noop,
+}
+
+impl EnginePacket {
+    pub fn encode(&self) -> String {
+        match self {
+            EnginePacket::Open(data) => format!("0{}", data),
+            EnginePacket::Close => "1".to_string(),
+            EnginePacket::Ping(data) => format!("2{}", data),
+            EnginePacket::Pong(data) => format!("3{}", data),
+            EnginePacket::Message(data) => format!("4{}", data),
+            EnginePacket::Upgrade => "5".to_string(),
+            EnginePacket::Noop => "6".to_string(),
+        }
+    }
+
+    pub fn decode(text: &str) -> Option<Self> {
+        let mut chars = text.chars();
+        let kind = chars.next()?;
+        let data = chars.as_str().to_string();
+        Some(match kind {
+            '0' => EnginePacket::Open(data),
+            '1' => EnginePacket::Close,
+            '2' => EnginePacket::Ping(data),
+            '3' => EnginePacket::Pong(data),
+            '4' => EnginePacket::Message(data),
+            '5' => EnginePacket::Upgrade,
+            '6' => EnginePacket::Noop,
+            _ => return None,
+        })
+    }
+
+    /// Length of the encoded packet in bytes.
+    pub fn encoded_len(&self) -> usize {
+        match self {
+            EnginePacket::Open(data)
+            | EnginePacket::Ping(data)
+            | EnginePacket::Pong(data)
+            | EnginePacket::Message(data) => data.len() + 1,
+            _ => 1,
+        }
+    }
+}
+
+/// Joins packets into the body of a poll response.
+pub fn encode_payload(packets: &[EnginePacket]) -> String {
+    packets
+        .iter()
+        .map(EnginePacket::encode)
+        .collect::<Vec<_>>()
+        .join(&RECORD_SEPARATOR.to_string())
+}
+
+/// Splits the body of a poll request. `None` if any packet is invalid or binary.
+pub fn decode_payload(body: &str) -> Option<Vec<EnginePacket>> {
+    body.split(RECORD_SEPARATOR)
+        .map(EnginePacket::decode)
+        .collect()
+}
+
+/// The data of the `Open` packet.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Handshake {
+    pub sid: String,
+    pub upgrades: Vec<&'static str>,
+    pub ping_interval: u64,
+    pub ping_timeout: u64,
+    pub max_payload: usize,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trips_packets() {
+        for packet in [
+            EnginePacket::Open("{}".to_string()),
+            EnginePacket::Close,
+            EnginePacket::Ping("probe".to_string()),
+            EnginePacket::Pong(String::new()),
+            EnginePacket::Message("2[\"hi\"]".to_string()),
+            EnginePacket::Upgrade,
+            EnginePacket::Noop,
+        ] {
+            assert_eq!(EnginePacket::decode(&packet.encode()), Some(packet));
+        }
+        assert_eq!(EnginePacket::decode(""), None);
+        assert_eq!(EnginePacket::decode("bAQID"), None);
+    }
+
+    #[test]
+    fn splits_polling_payloads() {
+        let packets = vec![
+            EnginePacket::Message("0".to_string()),
+            EnginePacket::Ping(String::new()),
+        ];
+        let body = encode_payload(&packets);
+
+        assert_eq!(body, "40\u{1e}2");
+        assert_eq!(decode_payload(&body), Some(packets));
+        assert_eq!(decode_payload("40\u{1e}bAQID"), None);
+    }
+}
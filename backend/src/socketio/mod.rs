@@ -0,0 +1,458 @@
+use axum::{
+    extract::{
+        ws::{Message, WebSocket, WebSocketUpgrade},
+        Query, State,
+    },
+    http::{header, StatusCode},
+    response::{IntoResponse, Json, Response},
+    Extension,
+};
+use serde::Deserialize;
+use serde_json::json;
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::Ordering;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::broadcast::error::RecvError;
+use uuid::Uuid;
+
+use crate::middleware::auth::AuthUser;
+use crate::services::presence::Presence;
+use crate::websocket::{self, protocol::ServerMessage};
+use crate::AppState;
+
+pub mod engine;
+pub mod packet;
+mod session;
+
+use engine::{decode_payload, encode_payload, EnginePacket};
+use packet::{Packet, MAIN_NAMESPACE};
+use session::Session;
+
+/// How often the server pings the client.
+const PING_INTERVAL: Duration = Duration::from_secs(25);
+
+/// Sessions whose client does not answer a ping within this are closed.
+const PING_TIMEOUT: Duration = Duration::from_secs(20);
+
+/// Largest poll request body or WebSocket message accepted, in bytes.
+const MAX_PAYLOAD: usize = 1_000_000;
+
+/// Open Engine.IO sessions and the Socket.IO rooms they joined.
+#[derive(Debug, Clone, Default)]
+pub struct SocketIo {
+    registry: Arc<Mutex<Registry>>,
+}
+
+#[derive(Default)]
+struct Registry {
+    sessions: HashMap<String, Arc<Session>>,
+    rooms: HashMap<String, HashSet<String>>,
+}
+
+impl std::fmt::Debug for Registry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Registry")
+            .field("sessions", &self.sessions.len())
+            .field("rooms", &self.rooms.len())
+            .finish()
+    }
+}
+
+impl SocketIo {
+    /// Creates the registry and starts pushing presence changes to the rooms following them.
+    pub fn start(presence: &Presence) -> Self {
+        let socket_io = Self::default();
+        let mut changes = presence.subscribe();
+
+        let rooms = socket_io.clone();
+        tokio::spawn(async move {
+            loop {
+                match changes.recv().await {
+                    Ok(change) => {
+                        let message = ServerMessage::Presence {
+                            user_id: change.user_id,
+                            online: change.online,
+                            last_seen_at: Some(change.last_seen_at),
+                        };
+                        if let Some(packet) = event_for(&message) {
+                            rooms.emit_to(&presence_room(change.user_id), &packet);
+                        }
+                    }
+                    // Missed changes are corrected by the next one for the same user
+                    Err(RecvError::Lagged(_)) => {}
+                    Err(RecvError::Closed) => break,
+                }
+            }
+        });
+
+        socket_io
+    }
+
+    /// Sends a packet to every session in the room.
+    pub fn emit_to(&self, room: &str, packet: &Packet) {
+        let sessions: Vec<Arc<Session>> = {
+            let registry = self.lock();
+            registry
+                .rooms
+                .get(room)
+                .into_iter()
+                .flatten()
+                .filter_map(|sid| registry.sessions.get(sid).cloned())
+                .collect()
+        };
+        let text = packet.encode();
+        for session in sessions {
+            session.push(EnginePacket::Message(text.clone()));
+        }
+    }
+
+    fn insert(&self, session: Arc<Session>) {
+        self.lock().sessions.insert(session.sid.clone(), session);
+    }
+
+    /// The session with this id, if it belongs to the user.
+    fn find(&self, sid: &str, user_id: Uuid) -> Option<Arc<Session>> {
+        self.lock()
+            .sessions
+            .get(sid)
+            .filter(|session| session.user_id == user_id)
+            .cloned()
+    }
+
+    fn remove(&self, sid: &str) {
+        let mut registry = self.lock();
+        registry.sessions.remove(sid);
+        leave_rooms(&mut registry.rooms, sid);
+    }
+
+    fn join(&self, sid: &str, room: &str) {
+        self.lock()
+            .rooms
+            .entry(room.to_string())
+            .or_default()
+            .insert(sid.to_string());
+    }
+
+    fn leave(&self, sid: &str, room: &str) {
+        let mut registry = self.lock();
+        if let Some(members) = registry.rooms.get_mut(room) {
+            members.remove(sid);
+            if members.is_empty() {
+                registry.rooms.remove(room);
+            }
+        }
+    }
+
+    fn leave_all(&self, sid: &str) {
+        leave_rooms(&mut self.lock().rooms, sid);
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Registry> {
+        self.registry
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+fn leave_rooms(rooms: &mut HashMap<String, HashSet<String>>, sid: &str) {
+    rooms.retain(|_, members| {
+        members.remove(sid);
+        !members.is_empty()
+    });
+}
+
+const PRESENCE_ROOM_PREFIX: &str = "presence:";
+
+/// The room of sessions following a user's presence.
+fn presence_room(user_id: Uuid) -> String {
+    format!("{}{}", PRESENCE_ROOM_PREFIX, user_id)
+}
+
+/// The user whose presence a room follows. Presence rooms are the only ones clients can join.
+fn presence_room_user(room: &str) -> Option<Uuid> {
+    Uuid::parse_str(room.strip_prefix(PRESENCE_ROOM_PREFIX)?).ok()
+}
+
+/// A hub message as a Socket.IO event named after its `type`, with the other fields as data.
+fn event_for(message: &ServerMessage) -> Option<Packet> {
+    let (name, data) = message.event_parts()?;
+    Some(Packet::event(MAIN_NAMESPACE, &name, data))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct EngineQuery {
+    #[serde(rename = "EIO")]
+    pub eio: Option<String>,
+    pub transport: Option<String>,
+    pub sid: Option<String>,
+}
+
+/// Engine.IO error responses, with the codes clients expect.
+#[derive(Debug, Clone, Copy)]
+enum EngineError {
+    TransportUnknown,
+    SessionIdUnknown,
+    BadRequest,
+    UnsupportedProtocolVersion,
+}
+
+impl IntoResponse for EngineError {
+    fn into_response(self) -> Response {
+        let (code, message) = match self {
+            EngineError::TransportUnknown => (0, "Transport unknown"),
+            EngineError::SessionIdUnknown => (1, "Session ID unknown"),
+            EngineError::BadRequest => (3, "Bad request"),
+            EngineError::UnsupportedProtocolVersion => (5, "Unsupported protocol version"),
+        };
+
+        (
+            StatusCode::BAD_REQUEST,
+            Json(json!({
+                "code": code,
+                "message": message
+            })),
+        )
+            .into_response()
+    }
+}
+
+/// Opens a session, answers a poll or upgrades to a WebSocket.
+pub async fn handle_get(
+    State(state): State<AppState>,
+    Extension(auth_user): Extension<AuthUser>,
+    Query(query): Query<EngineQuery>,
+    ws: Option<WebSocketUpgrade>,
+) -> Response {
+    if query.eio.as_deref() != Some("4") {
+        return EngineError::UnsupportedProtocolVersion.into_response();
+    }
+    let user_id = auth_user.user.id;
+
+    match (query.transport.as_deref(), query.sid) {
+        (Some("polling"), None) => {
+            let (_, open) = Session::open(&state, user_id, vec!["websocket"]);
+            text(open.encode())
+        }
+        (Some("polling"), Some(sid)) => match state.socket_io.find(&sid, user_id) {
+            Some(session) => poll(session).await,
+            None => EngineError::SessionIdUnknown.into_response(),
+        },
+        (Some("websocket"), sid) => {
+            let Some(ws) = ws else {
+                return EngineError::BadRequest.into_response();
+            };
+            let session = match sid {
+                Some(sid) => match state.socket_io.find(&sid, user_id) {
+                    Some(session) if !session.upgraded.load(Ordering::SeqCst) => Some(session),
+                    _ => return EngineError::SessionIdUnknown.into_response(),
+                },
+                None => None,
+            };
+            ws.max_message_size(MAX_PAYLOAD)
+                .on_upgrade(move |socket| serve_websocket(socket, state, user_id, session))
+        }
+        _ => EngineError::TransportUnknown.into_response(),
+    }
+}
+
+/// Takes the packets of a poll request.
+pub async fn handle_post(
+    State(state): State<AppState>,
+    Extension(auth_user): Extension<AuthUser>,
+    Query(query): Query<EngineQuery>,
+    body: String,
+) -> Response {
+    if query.eio.as_deref() != Some("4") {
+        return EngineError::UnsupportedProtocolVersion.into_response();
+    }
+    if query.transport.as_deref() != Some("polling") {
+        return EngineError::TransportUnknown.into_response();
+    }
+    let Some(session) = query
+        .sid
+        .and_then(|sid| state.socket_io.find(&sid, auth_user.user.id))
+    else {
+        return EngineError::SessionIdUnknown.into_response();
+    };
+
+    let packets = match decode_payload(&body) {
+        Some(packets) if body.len() <= MAX_PAYLOAD => packets,
+        _ => {
+            session.close();
+            return EngineError::BadRequest.into_response();
+        }
+    };
+    for packet in packets {
+        if session.incoming.send(packet).await.is_err() {
+            break;
+        }
+    }
+
+    text("ok".to_string())
+}
+
+/// Waits for packets for the client and returns all that are queued.
+async fn poll(session: Arc<Session>) -> Response {
+    if session.upgraded.load(Ordering::SeqCst) {
+        return EngineError::BadRequest.into_response();
+    }
+    // Clients poll one at a time; a second poll means something is broken
+    let Ok(mut receiver) = session.receiver.try_lock() else {
+        session.close();
+        return EngineError::BadRequest.into_response();
+    };
+
+    let first = tokio::select! {
+        biased;
+        packet = receiver.recv() => packet,
+        _ = session.closed() => None,
+    };
+    let Some(first) = first else {
+        return text(EnginePacket::Close.encode());
+    };
+
+    let mut size = first.encoded_len();
+    let mut packets = vec![first];
+    while size < MAX_PAYLOAD {
+        let Ok(packet) = receiver.try_recv() else {
+            break;
+        };
+        size += packet.encoded_len() + 1;
+        packets.push(packet);
+    }
+
+    text(encode_payload(&packets))
+}
+
+/// Carries a session over a WebSocket, either upgraded from polling or opened on it.
+async fn serve_websocket(
+    mut socket: WebSocket,
+    state: AppState,
+    user_id: Uuid,
+    session: Option<Arc<Session>>,
+) {
+    let session = match session {
+        Some(session) => {
+            if !upgrade(&mut socket, &session).await {
+                return;
+            }
+            session
+        }
+        None => {
+            let (session, open) = Session::open(&state, user_id, Vec::new());
+            if !websocket::write(&mut socket, Message::Text(open.encode())).await {
+                session.close();
+                return;
+            }
+            session
+        }
+    };
+
+    // A poll still in progress hands over the queue when it ends
+    let mut receiver = tokio::select! {
+        receiver = session.receiver.lock() => receiver,
+        _ = session.closed() => return,
+    };
+
+    loop {
+        tokio::select! {
+            frame = socket.recv() => {
+                let Some(Ok(frame)) = frame else { break };
+                match frame {
+                    Message::Text(text) => {
+                        let Some(packet) = EnginePacket::decode(&text) else { break };
+                        if session.incoming.send(packet).await.is_err() {
+                            break;
+                        }
+                    }
+                    // Binary attachments are not supported
+                    Message::Binary(_) | Message::Close(_) => break,
+                    _ => {}
+                }
+            }
+            packet = receiver.recv() => {
+                let Some(packet) = packet else { break };
+                if !websocket::write(&mut socket, Message::Text(packet.encode())).await {
+                    break;
+                }
+            }
+            _ = session.closed() => break,
+        }
+    }
+
+    session.close();
+}
+
+/// Moves a polling session to the WebSocket: the client probes it while polling goes on,
+/// then confirms. Returns whether the upgrade completed.
+async fn upgrade(socket: &mut WebSocket, session: &Session) -> bool {
+    let probe = async {
+        loop {
+            let Some(Ok(frame)) = socket.recv().await else {
+                return false;
+            };
+            let Message::Text(text) = frame else {
+                continue;
+            };
+            match EnginePacket::decode(&text) {
+                Some(EnginePacket::Ping(data)) if data == "probe" => {
+                    let pong = EnginePacket::Pong(data).encode();
+                    if !websocket::write(socket, Message::Text(pong)).await {
+                        return false;
+                    }
+                    // Ends the poll in progress so the client can pause polling
+                    session.push(EnginePacket::Noop);
+                }
+                Some(EnginePacket::Upgrade) => return true,
+                _ => return false,
+            }
+        }
+    };
+
+    let upgraded = matches!(tokio::time::timeout(PING_TIMEOUT, probe).await, Ok(true));
+    if upgraded {
+        session.upgraded.store(true, Ordering::SeqCst);
+    }
+    upgraded
+}
+
+fn text(body: String) -> Response {
+    ([(header::CONTENT_TYPE, "text/plain; charset=UTF-8")], body).into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_presence_rooms() {
+        let user_id = Uuid::new_v4();
+
+        assert_eq!(presence_room_user(&presence_room(user_id)), Some(user_id));
+        assert_eq!(presence_room_user(&user_id.to_string()), None);
+        assert_eq!(presence_room_user("presence:someone"), None);
+    }
+
+    #[test]
+    fn names_events_after_the_message_type() {
+        let packet = event_for(&ServerMessage::Presence {
+            user_id: Uuid::nil(),
+            online: true,
+            last_seen_at: None,
+        })
+        .unwrap();
+
+        assert_eq!(
+            packet.event_args().unwrap(),
+            (
+                "presence",
+                &[json!({
+                    "user_id": Uuid::nil(),
+                    "online": true,
+                    "last_seen_at": null
+                })][..]
+            )
+        );
+    }
+}
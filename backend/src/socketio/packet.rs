@@ -0,0 +1,223 @@
+use serde_json::{json, Value};
+
+/// The namespace every client connects to unless it names another.
+pub const MAIN_NAMESPACE: &str = "/";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PacketKind {
+    Connect,
+    Disconnect,
+    Event,
+    Ack,
+    ConnectError,
+    BinaryEvent,
+    BinaryAck,
+}
+
+impl PacketKind {
+    fn code(self) -> char {
+        match self {
+            PacketKind::Connect => '0',
+            PacketKind::Disconnect => '1',
+            PacketKind::Event => '2',
+            PacketKind::Ack => '3',
+            PacketKind::ConnectError => '4',
+            PacketKind::BinaryEvent => '5',
+            PacketKind::BinaryAck => '6',
+        }
+    }
+
+    fn from_code(code: char) -> Option<Self> {
+        Some(match code {
+            '0' => PacketKind::Connect,
+            '1' => PacketKind::Disconnect,
+            '2' => PacketKind::Event,
+            '3' => PacketKind::Ack,
+            '4' => PacketKind::ConnectError,
+            '5' => PacketKind::BinaryEvent,
+            '6' => PacketKind::BinaryAck,
+            _ => return None,
+        })
+    }
+}
+
+/// A Socket.IO v5 packet: `<kind>[<namespace>,][<ack id>][<JSON data>]`, carried in an
+/// Engine.IO message. Binary attachments are not supported.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Packet {
+    pub kind: PacketKind,
+    pub namespace: String,
+    pub id: Option<u64>,
+    pub data: Option<Value>,
+}
+
+impl Packet {
+    pub fn connect(namespace: &str, sid: &str) -> Self {
+        Self::new(PacketKind::Connect, namespace, None, Some(json!({ "sid": sid })))
+    }
+
+    pub fn connect_error(namespace: &str, message: &str) -> Self {
+        Self::new(PacketKind::ConnectError, namespace, None, Some(json!({ "message": message })))
+    }
+
+    pub fn event(namespace: &str, name: &str, data: Value) -> Self {
+        Self::new(PacketKind::Event, namespace, None, Some(json!([name, data])))
+    }
+
+    pub fn ack(namespace: &str, id: u64, data: Value) -> Self {
+        Self::new(PacketKind::Ack, namespace, Some(id), Some(json!([data])))
+    }
+
+    /// Asks the client to acknowledge an event with this id.
+    pub fn expecting_ack(mut self, id: u64) -> Self {
+        self.id = Some(id);
+        self
+    }
+
+    fn new(kind: PacketKind, namespace: &str, id: Option<u64>, data: Option<Value>) -> Self {
+        Self {
+            kind,
+            namespace: namespace.to_string(),
+            id,
+            data,
+        }
+    }
+
+    /// The name and arguments of an event.
+    pub fn event_args(&self) -> Option<(&str, &[Value])> {
+        if self.kind != PacketKind::Event {
+            return None;
+        }
+        match self.data.as_ref()? {
+            Value::Array(args) => {
+                let (name, args) = args.split_first()?;
+                Some((name.as_str()?, args))
+            }
+            _ => None,
+        }
+    }
+
+    pub fn encode(&self) -> String {
+        let mut text = self.kind.code().to_string();
+        if self.namespace != MAIN_NAMESPACE {
+            text.push_str(&self.namespace);
+            text.push(',');
+        }
+        if let Some(id) = self.id {
+            text.push_str(&id.to_string());
+        }
+        if let Some(data) = &self.data {
+            text.push_str(&data.to_string());
+        }
+        text
+    }
+
+    pub fn decode(text: &str) -> Option<Self> {
+        let mut chars = text.chars();
+        let kind = PacketKind::from_code(chars.next()?)?;
+        if matches!(kind, PacketKind::BinaryEvent | PacketKind::BinaryAck) {
+            return None;
+        }
+        let mut rest = chars.as_str();
+
+        let namespace = if rest.starts_with('/') {
+            let (namespace, after) = rest.split_once(',').unwrap_or((rest, ""));
+            rest = after;
+            namespace.to_string()
+        } else {
+            MAIN_NAMESPACE.to_string()
+        };
+
+        let digits = rest.len() - rest.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+        let id = if digits > 0 {
+            Some(rest[..digits].parse().ok()?)
+        } else {
+            None
+        };
+        rest = &rest[digits..];
+
+        let data = if rest.is_empty() {
+            None
+        } else {
+            Some(serde_json::from_str::<Value>(rest).ok()?)
+        };
+
+        let valid = match kind {
+            PacketKind::Connect => matches!(data, None | Some(Value::Object(_))),
+            PacketKind::Disconnect => data.is_none(),
+            PacketKind::Event => matches!(
+                &data,
+                Some(Value::Array(args)) if args.first().is_some_and(Value::is_string)
+            ),
+            PacketKind::Ack => id.is_some() && matches!(data, Some(Value::Array(_))),
+            PacketKind::ConnectError => data.is_some(),
+            PacketKind::BinaryEvent | PacketKind::BinaryAck => false,
+        };
+
+        valid.then_some(Self {
+            kind,
+            namespace,
+            id,
+            data,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encodes_packets() {
+        assert_eq!(Packet::connect("/", "abc").encode(), r#"0{"sid":"abc"}"#);
+        assert_eq!(
+            Packet::connect_error("/admin", "Invalid namespace").encode(),
+            r#"4/admin,{"message":"Invalid namespace"}"#
+        );
+        assert_eq!(
+            Packet::event("/", "presence", json!({"online": true})).encode(),
+            r#"2["presence",{"online":true}]"#
+        );
+        assert_eq!(Packet::ack("/", 12, json!({})).encode(), "312[{}]");
+        assert_eq!(
+            Packet::event("/", "chat_message", json!({}))
+                .expecting_ack(3)
+                .encode(),
+            r#"23["chat_message",{}]"#
+        );
+    }
+
+    #[test]
+    fn decodes_packets() {
+        let event = Packet::decode(r#"2/chat,7["send_message",{"body":"Hi"}]"#).unwrap();
+        assert_eq!(event.namespace, "/chat");
+        assert_eq!(event.id, Some(7));
+        let (name, args) = event.event_args().unwrap();
+        assert_eq!(name, "send_message");
+        assert_eq!(args, &[json!({"body": "Hi"})]);
+
+        let connect = Packet::decode("0").unwrap();
+        assert_eq!(connect.kind, PacketKind::Connect);
+        assert_eq!(connect.namespace, MAIN_NAMESPACE);
+        assert_eq!(Packet::decode("0/admin,").unwrap().namespace, "/admin");
+        assert_eq!(Packet::decode("1").unwrap().kind, PacketKind::Disconnect);
+    }
+
+    #[test]
+    fn rejects_malformed_packets() {
+        for text in [
+            "",
+            "9",
+            "2",
+            "2[]",
+            "2[1]",
+            "2{\"a\":1}",
+            "3[]",
+            "0[]",
+            "2[\"a\"",
+            "51-[\"a\",{}]",
+        ] {
+            assert_eq!(Packet::decode(text), None, "{}", text);
+        }
+    }
+}
@@ -0,0 +1,377 @@
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+use std::sync::atomic::AtomicBool;
+use std::sync::Arc;
+use tokio::sync::{mpsc, watch, Mutex};
+use tokio::time::Instant;
+use tracing::warn;
+use uuid::Uuid;
+
+use super::engine::{EnginePacket, Handshake};
+use super::packet::{Packet, PacketKind, MAIN_NAMESPACE};
+use super::{
+    event_for, presence_room, presence_room_user, MAX_PAYLOAD, PING_INTERVAL, PING_TIMEOUT,
+};
+use crate::models::Message;
+use crate::services::hub::{Push, OUTBOX_CAPACITY};
+use crate::websocket::connection::{Connection, INVALID_MESSAGE};
+use crate::websocket::protocol::ServerMessage;
+use crate::AppState;
+
+/// Packets from the client waiting to be handled. Transports wait for room here.
+const INCOMING_CAPACITY: usize = 64;
+
+/// One Engine.IO connection, whichever transport currently carries it.
+pub(super) struct Session {
+    pub sid: String,
+    pub user_id: Uuid,
+    outgoing: mpsc::Sender<EnginePacket>,
+    /// Held by the transport delivering packets to the client: a poll in progress or the
+    /// WebSocket after an upgrade.
+    pub receiver: Mutex<mpsc::Receiver<EnginePacket>>,
+    pub incoming: mpsc::Sender<EnginePacket>,
+    pub upgraded: AtomicBool,
+    closed: watch::Sender<bool>,
+}
+
+impl Session {
+    /// Starts a session for the user and returns it with the handshake to send.
+    pub fn open(
+        state: &AppState,
+        user_id: Uuid,
+        upgrades: Vec<&'static str>,
+    ) -> (Arc<Self>, EnginePacket) {
+        let (outgoing, receiver) = mpsc::channel(OUTBOX_CAPACITY);
+        let (incoming, incoming_receiver) = mpsc::channel(INCOMING_CAPACITY);
+        let session = Arc::new(Self {
+            sid: Uuid::new_v4().simple().to_string(),
+            user_id,
+            outgoing,
+            receiver: Mutex::new(receiver),
+            incoming,
+            upgraded: AtomicBool::new(false),
+            closed: watch::Sender::new(false),
+        });
+        state.socket_io.insert(session.clone());
+        tokio::spawn(run(state.clone(), session.clone(), incoming_receiver));
+
+        let handshake = Handshake {
+            sid: session.sid.clone(),
+            upgrades,
+            ping_interval: PING_INTERVAL.as_millis() as u64,
+            ping_timeout: PING_TIMEOUT.as_millis() as u64,
+            max_payload: MAX_PAYLOAD,
+        };
+        let open = EnginePacket::Open(json!(handshake).to_string());
+
+        (session, open)
+    }
+
+    /// Queues a packet for the client. A client too far behind is disconnected, like
+    /// on the raw WebSocket.
+    pub fn push(&self, packet: EnginePacket) -> bool {
+        match self.outgoing.try_send(packet) {
+            Ok(()) => true,
+            Err(mpsc::error::TrySendError::Full(_)) => {
+                warn!(
+                    "Closing Socket.IO session {} of {}, its queue is full",
+                    self.sid, self.user_id
+                );
+                self.close();
+                false
+            }
+            Err(mpsc::error::TrySendError::Closed(_)) => false,
+        }
+    }
+
+    pub fn close(&self) {
+        self.closed.send_replace(true);
+    }
+
+    /// Resolves once the session is closed.
+    pub async fn closed(&self) {
+        let mut closed = self.closed.subscribe();
+        // The sender lives as long as the session, so waiting cannot fail
+        let _ = closed.wait_for(|closed| *closed).await;
+    }
+}
+
+/// Handles what the client sends, forwards what the hub has for the user and keeps the
+/// heartbeat, until the session closes.
+async fn run(state: AppState, session: Arc<Session>, mut incoming: mpsc::Receiver<EnginePacket>) {
+    let (connection, mut outbox) = Connection::open(&state, session.user_id);
+
+    let mut socket = Socket {
+        state: state.clone(),
+        session: session.clone(),
+        connection,
+        id: None,
+        next_ack_id: 0,
+        awaiting_ack: BTreeMap::new(),
+    };
+    let mut heartbeat = tokio::time::interval_at(Instant::now() + PING_INTERVAL, PING_INTERVAL);
+    let mut pong_deadline: Option<Instant> = None;
+
+    loop {
+        tokio::select! {
+            _ = session.closed() => break,
+            packet = incoming.recv() => {
+                let Some(packet) = packet else { break };
+                socket.connection.touch();
+                let open = match packet {
+                    EnginePacket::Pong(_) => {
+                        pong_deadline = None;
+                        true
+                    }
+                    EnginePacket::Message(text) => socket.handle(&text).await,
+                    EnginePacket::Close => false,
+                    _ => true,
+                };
+                if !open {
+                    break;
+                }
+            }
+            queued = outbox.recv() => {
+                // The hub closes the outbox of sessions too slow to keep up, and of suspended users
+                let Some(queued) = queued else { break };
+                let open = match &*queued {
+                    Push::Message(message) => socket.emit_message(message),
+                    Push::Unwatch(other_id) => {
+                        socket.unwatch(*other_id);
+                        true
+                    }
+                };
+                if !open {
+                    break;
+                }
+            }
+            _ = heartbeat.tick() => {
+                if !session.push(EnginePacket::Ping(String::new())) {
+                    break;
+                }
+                pong_deadline.get_or_insert(Instant::now() + PING_TIMEOUT);
+            }
+            _ = tokio::time::sleep_until(pong_deadline.unwrap_or_else(Instant::now)),
+                if pong_deadline.is_some() => break,
+        }
+    }
+
+    session.close();
+    state.socket_io.remove(&session.sid);
+    socket.connection.close();
+}
+
+#[derive(Debug, Deserialize)]
+struct SendMessageArgs {
+    recipient_id: Uuid,
+    body: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct WatchPresenceArgs {
+    user_ids: Vec<Uuid>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RoomArgs {
+    room: String,
+}
+
+/// The Socket.IO socket of a session.
+///
+/// Only the main namespace exists. Everything sent concerns the signed-in user alone, so there
+/// is nothing for other namespaces to keep apart: connecting to one is refused with a
+/// CONNECT_ERROR, and packets for it are ignored like those for any namespace the client has
+/// not connected to.
+struct Socket {
+    state: AppState,
+    session: Arc<Session>,
+    connection: Connection,
+    /// Set while connected to the main namespace.
+    id: Option<String>,
+    next_ack_id: u64,
+    /// Chat messages emitted to the client until it acknowledges them, by acknowledgement id.
+    awaiting_ack: BTreeMap<u64, Message>,
+}
+
+impl Socket {
+    /// Returns whether the session should go on; malformed packets end it.
+    async fn handle(&mut self, text: &str) -> bool {
+        let Some(packet) = Packet::decode(text) else {
+            return false;
+        };
+        if packet.namespace != MAIN_NAMESPACE {
+            return match packet.kind {
+                PacketKind::Connect => {
+                    self.emit(Packet::connect_error(&packet.namespace, "Invalid namespace"))
+                }
+                _ => true,
+            };
+        }
+
+        match packet.kind {
+            PacketKind::Connect => self.connect().await,
+            PacketKind::Disconnect => {
+                self.disconnect();
+                true
+            }
+            PacketKind::Event | PacketKind::Ack if self.id.is_none() => true,
+            PacketKind::Event => self.event(&packet).await,
+            PacketKind::Ack => {
+                self.acknowledged(&packet);
+                true
+            }
+            // Only servers send connection errors; binary packets are refused when decoded
+            PacketKind::ConnectError | PacketKind::BinaryEvent | PacketKind::BinaryAck => false,
+        }
+    }
+
+    async fn connect(&mut self) -> bool {
+        let id = self
+            .id
+            .get_or_insert_with(|| Uuid::new_v4().simple().to_string())
+            .clone();
+        if !self.emit(Packet::connect(MAIN_NAMESPACE, &id)) {
+            return false;
+        }
+
+        let snapshot = self.connection.watch_connections().await;
+        self.join_watched();
+        snapshot.iter().all(|message| self.emit_message(message))
+    }
+
+    fn disconnect(&mut self) {
+        self.id = None;
+        self.awaiting_ack.clear();
+        self.connection.unwatch_all();
+        self.state.socket_io.leave_all(&self.session.sid);
+    }
+
+    /// Runs an event and answers it when the client asked for an acknowledgement.
+    async fn event(&mut self, packet: &Packet) -> bool {
+        let Some((name, args)) = packet.event_args() else {
+            return true;
+        };
+
+        let reply = match name {
+            "send_message" => match parse_args::<SendMessageArgs>(args) {
+                Some(args) => match self
+                    .connection
+                    .send_message(args.recipient_id, &args.body)
+                    .await
+                {
+                    Ok(message) => json!({ "message": message }),
+                    Err(error) => json!({ "error": error }),
+                },
+                None => json!({ "error": INVALID_MESSAGE }),
+            },
+            "watch_presence" => match parse_args::<WatchPresenceArgs>(args) {
+                Some(args) => {
+                    if !self.watch(args.user_ids).await {
+                        return false;
+                    }
+                    json!({})
+                }
+                None => json!({ "error": INVALID_MESSAGE }),
+            },
+            // Joining a presence room is watching that user, with the same block checks
+            "join" => {
+                match parse_args::<RoomArgs>(args).and_then(|args| presence_room_user(&args.room)) {
+                    Some(user_id) => {
+                        if !self.watch(vec![user_id]).await {
+                            return false;
+                        }
+                        json!({})
+                    }
+                    None => json!({ "error": "Unknown room" }),
+                }
+            }
+            "leave" => {
+                match parse_args::<RoomArgs>(args).and_then(|args| presence_room_user(&args.room)) {
+                    Some(user_id) => {
+                        self.unwatch(user_id);
+                        json!({})
+                    }
+                    None => json!({ "error": "Unknown room" }),
+                }
+            }
+            _ => json!({ "error": "Unknown event" }),
+        };
+
+        match packet.id {
+            Some(id) => self.emit(Packet::ack(MAIN_NAMESPACE, id, reply)),
+            None => true,
+        }
+    }
+
+    /// Confirms the delivery of the chat message the client acknowledged. Ids the server did
+    /// not hand out, or stopped waiting for, are ignored.
+    fn acknowledged(&mut self, packet: &Packet) {
+        if let Some(message) = packet.id.and_then(|id| self.awaiting_ack.remove(&id)) {
+            self.connection.confirm_delivery(&message);
+        }
+    }
+
+    /// Follows the presence of more users through their rooms and sends where they stand.
+    async fn watch(&mut self, user_ids: Vec<Uuid>) -> bool {
+        let snapshot = self.connection.watch(user_ids).await;
+        self.join_watched();
+        snapshot.iter().all(|message| self.emit_message(message))
+    }
+
+    fn join_watched(&self) {
+        for user_id in self.connection.watched() {
+            self.state
+                .socket_io
+                .join(&self.session.sid, &presence_room(user_id));
+        }
+    }
+
+    /// Stops following a user's presence, when the client leaves the room or after a block
+    /// either way. A blocked user is not told about deliveries either.
+    fn unwatch(&mut self, user_id: Uuid) {
+        self.connection.unwatch(user_id);
+        self.awaiting_ack
+            .retain(|_, message| message.sender_id != user_id);
+        self.state
+            .socket_io
+            .leave(&self.session.sid, &presence_room(user_id));
+    }
+
+    /// Sends a hub message as the event named after its type, while connected. Chat messages
+    /// from others ask for an acknowledgement, which confirms their delivery to the sender.
+    fn emit_message(&mut self, message: &ServerMessage) -> bool {
+        let (Some(_), Some(mut packet)) = (&self.id, event_for(message)) else {
+            return true;
+        };
+        if let ServerMessage::ChatMessage { message } = message {
+            if message.sender_id != self.connection.user_id {
+                packet = packet.expecting_ack(self.await_ack(message.clone()));
+            }
+        }
+        self.emit(packet)
+    }
+
+    /// Hands out the next acknowledgement id, forgetting the oldest message once more are
+    /// waiting than the client could be behind.
+    fn await_ack(&mut self, message: Message) -> u64 {
+        let id = self.next_ack_id;
+        self.next_ack_id += 1;
+        self.awaiting_ack.insert(id, message);
+        if self.awaiting_ack.len() > OUTBOX_CAPACITY {
+            self.awaiting_ack.pop_first();
+        }
+        id
+    }
+
+    fn emit(&self, packet: Packet) -> bool {
+        self.session.push(EnginePacket::Message(packet.encode()))
+    }
+}
+
+/// Reads the first argument of an event.
+fn parse_args<T: DeserializeOwned>(args: &[Value]) -> Option<T> {
+    serde_json::from_value(args.first()?.clone()).ok()
+}
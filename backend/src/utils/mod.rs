@@ -1 +1,80 @@
-// Utils module - currently empty but available for future utilities
+use axum::http::HeaderMap;
+use std::net::{IpAddr, SocketAddr};
+
+/// The client's address. `X-Forwarded-For` is only read when the connection comes from one of
+/// the `trusted_proxies`, and then from the right, skipping the hops those proxies added, so a
+/// client cannot pick its own address by sending the header.
+pub fn client_ip(headers: &HeaderMap, peer: SocketAddr, trusted_proxies: &[IpAddr]) -> IpAddr {
+    let mut client = peer.ip();
+    if !trusted_proxies.contains(&client) {
+        return client;
+    }
+
+    let hops = headers
+        .get_all("x-forwarded-for")
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .collect::<Vec<_>>();
+    for hop in hops.into_iter().rev() {
+        let Ok(hop) = hop.trim().parse::<IpAddr>() else {
+            // Nothing left of a malformed hop can be trusted
+            return peer.ip();
+        };
+        client = hop;
+        if !trusted_proxies.contains(&hop) {
+            break;
+        }
+    }
+
+    client
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn peer() -> SocketAddr {
+        "127.0.0.1:50000".parse().unwrap()
+    }
+
+    fn proxies() -> Vec<IpAddr> {
+        vec!["127.0.0.1".parse().unwrap(), "10.0.0.2".parse().unwrap()]
+    }
+
+    fn forwarded(value: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert("x-forwarded-for", value.parse().unwrap());
+        headers
+    }
+
+    #[test]
+    fn falls_back_to_the_peer_address() {
+        assert_eq!(client_ip(&HeaderMap::new(), peer(), &proxies()), peer().ip());
+    }
+
+    #[test]
+    fn ignores_forwarded_headers_from_untrusted_peers() {
+        let headers = forwarded("81.2.69.160");
+
+        assert_eq!(client_ip(&headers, peer(), &[]), peer().ip());
+    }
+
+    #[test]
+    fn takes_the_last_hop_not_added_by_a_trusted_proxy() {
+        // The client sent its own header, which the proxies appended to
+        let headers = forwarded("1.2.3.4, 81.2.69.160, 10.0.0.2");
+
+        assert_eq!(
+            client_ip(&headers, peer(), &proxies()),
+            "81.2.69.160".parse::<IpAddr>().unwrap()
+        );
+    }
+
+    #[test]
+    fn ignores_malformed_forwarded_headers() {
+        let headers = forwarded("81.2.69.160, unknown");
+
+        assert_eq!(client_ip(&headers, peer(), &proxies()), peer().ip());
+    }
+}
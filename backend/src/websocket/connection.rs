@@ -0,0 +1,173 @@
+use std::collections::HashSet;
+use tracing::error;
+use uuid::Uuid;
+
+use super::protocol::ServerMessage;
+use crate::database::{BlockRepository, LikeRepository, PresenceRepository};
+use crate::models::Message;
+use crate::services::chat::{self, ChatError};
+use crate::services::hub::{ConnectionId, Outbox};
+use crate::AppState;
+
+/// Most users a single connection can follow the presence of.
+const MAX_WATCHED_USERS: usize = 200;
+
+/// The answer to a request that cannot be read.
+pub(crate) const INVALID_MESSAGE: &str = "Invalid message";
+
+/// What one realtime connection of a user can do, whichever transport carries it. The raw
+/// WebSocket and Socket.IO only read requests and write answers in their own framing.
+pub(crate) struct Connection {
+    state: AppState,
+    pub user_id: Uuid,
+    pub id: ConnectionId,
+    watched: HashSet<Uuid>,
+}
+
+impl Connection {
+    /// Registers the connection with the hub and counts the user as online, returning it
+    /// with the pushes the hub queues for it.
+    pub fn open(state: &AppState, user_id: Uuid) -> (Self, Outbox) {
+        let (id, outbox) = state.hub.register(user_id);
+        state.presence.connect(user_id);
+
+        let connection = Self {
+            state: state.clone(),
+            user_id,
+            id,
+            watched: HashSet::new(),
+        };
+        (connection, outbox)
+    }
+
+    pub fn close(self) {
+        self.state.hub.unregister(self.user_id, self.id);
+        self.state.presence.disconnect(self.user_id);
+    }
+
+    /// Keeps the user online while the client is heard from.
+    pub fn touch(&self) {
+        self.state.presence.touch(self.user_id);
+    }
+
+    /// Follows the users the user is connected with, as every connection does from the start,
+    /// returning where they stand now.
+    pub async fn watch_connections(&mut self) -> Vec<ServerMessage> {
+        match LikeRepository::new(self.state.db.clone())
+            .find_connected_ids(self.user_id)
+            .await
+        {
+            Ok(connected_ids) => self.watch(connected_ids).await,
+            Err(e) => {
+                error!("Failed to load connections of {}: {:#}", self.user_id, e);
+                Vec::new()
+            }
+        }
+    }
+
+    /// Adds the `user_ids` the user has no block with to those whose presence is followed,
+    /// returning where the newly followed users stand now.
+    pub async fn watch(&mut self, user_ids: Vec<Uuid>) -> Vec<ServerMessage> {
+        let room = MAX_WATCHED_USERS.saturating_sub(self.watched.len());
+        let requested: Vec<Uuid> = user_ids
+            .into_iter()
+            .filter(|id| *id != self.user_id && !self.watched.contains(id))
+            .collect::<HashSet<_>>()
+            .into_iter()
+            .take(room)
+            .collect();
+        if requested.is_empty() {
+            return Vec::new();
+        }
+
+        let allowed = match BlockRepository::new(self.state.db.clone())
+            .filter_unblocked(self.user_id, &requested)
+            .await
+        {
+            Ok(allowed) => allowed,
+            Err(e) => {
+                error!("Failed to filter watched users: {:#}", e);
+                return Vec::new();
+            }
+        };
+
+        // Users not seen since the server started are offline; the database knows when they were last
+        let mut snapshot = Vec::with_capacity(allowed.len());
+        let mut unknown = Vec::new();
+        for id in allowed {
+            self.watched.insert(id);
+            match self.state.presence.status(id) {
+                Some(status) => snapshot.push(ServerMessage::Presence {
+                    user_id: id,
+                    online: status.online,
+                    last_seen_at: Some(status.last_seen_at),
+                }),
+                None => unknown.push(id),
+            }
+        }
+        if !unknown.is_empty() {
+            let last_seen = PresenceRepository::new(self.state.db.clone())
+                .find_last_seen_many(&unknown)
+                .await
+                .unwrap_or_else(|e| {
+                    error!("Failed to load last seen times: {:#}", e);
+                    Vec::new()
+                });
+            snapshot.extend(unknown.into_iter().map(|id| {
+                ServerMessage::Presence {
+                    user_id: id,
+                    online: false,
+                    last_seen_at: last_seen
+                        .iter()
+                        .find(|(seen_id, _)| *seen_id == id)
+                        .map(|(_, time)| *time),
+                }
+            }));
+        }
+
+        snapshot
+    }
+
+    /// Stops following a user's presence, when the client asks or after a block either way.
+    pub fn unwatch(&mut self, user_id: Uuid) {
+        self.watched.remove(&user_id);
+    }
+
+    pub fn unwatch_all(&mut self) {
+        self.watched.clear();
+    }
+
+    pub fn is_watching(&self, user_id: Uuid) -> bool {
+        self.watched.contains(&user_id)
+    }
+
+    pub fn watched(&self) -> impl Iterator<Item = Uuid> + '_ {
+        self.watched.iter().copied()
+    }
+
+    /// Sends a chat message from the user. The error is what the client is told.
+    pub async fn send_message(&self, recipient_id: Uuid, body: &str) -> Result<Message, String> {
+        chat::send_message(
+            &self.state.db,
+            &self.state.hub,
+            self.user_id,
+            recipient_id,
+            body,
+            Some(self.id),
+        )
+        .await
+        .map_err(|e| {
+            if let ChatError::Database(e) = &e {
+                error!("Failed to send message from {}: {:#}", self.user_id, e);
+            }
+            e.to_string()
+        })
+    }
+
+    /// Tells the sender that a message pushed to this connection reached the client.
+    pub fn confirm_delivery(&self, message: &Message) {
+        if message.sender_id != self.user_id {
+            chat::confirm_delivery(&self.state.hub, message);
+        }
+    }
+}
@@ -1,9 +1,188 @@
-use axum::response::Json;
-use serde_json::{json, Value};
-
-pub async fn handle_websocket() -> Json<Value> {
-    Json(json!({
-        "message": "WebSocket endpoint - to be implemented",
-        "status": "not_implemented"
-    }))
+use axum::{
+    extract::{
+        ws::{Message, WebSocket, WebSocketUpgrade},
+        State,
+    },
+    response::Response,
+    Extension,
+};
+use std::collections::VecDeque;
+use std::time::Duration;
+use tokio::sync::broadcast::error::RecvError;
+use tokio::time::Instant;
+use tracing::error;
+use uuid::Uuid;
+
+use crate::middleware::auth::AuthUser;
+use crate::models;
+use crate::services::hub::{Push, OUTBOX_CAPACITY};
+use crate::AppState;
+
+pub(crate) mod connection;
+pub mod protocol;
+
+use connection::{Connection, INVALID_MESSAGE};
+use protocol::{ClientMessage, ServerMessage};
+
+/// How often the server pings a quiet client.
+const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(25);
+
+/// Connections whose client sent nothing for this long are closed.
+const HEARTBEAT_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Writes taking longer than this close the connection, so a pushed message either arrives
+/// within it or the client reconnects and catches up.
+const SEND_TIMEOUT: Duration = Duration::from_secs(10);
+
+pub async fn handle_websocket(
+    ws: WebSocketUpgrade,
+    State(state): State<AppState>,
+    Extension(auth_user): Extension<AuthUser>,
+) -> Response {
+    ws.on_upgrade(move |socket| serve(socket, state, auth_user.user.id))
+}
+
+async fn serve(mut socket: WebSocket, state: AppState, user_id: Uuid) {
+    let (mut connection, mut outbox) = Connection::open(&state, user_id);
+    let mut changes = state.presence.subscribe();
+
+    let snapshot = connection.watch_connections().await;
+    send_all(&mut socket, &snapshot).await;
+
+    let mut heartbeat =
+        tokio::time::interval_at(Instant::now() + HEARTBEAT_INTERVAL, HEARTBEAT_INTERVAL);
+    let mut last_heard = Instant::now();
+    // Chat messages from others pushed to the client, until it acknowledges them
+    let mut awaiting_ack: VecDeque<models::Message> = VecDeque::new();
+
+    loop {
+        tokio::select! {
+            message = socket.recv() => {
+                let Some(Ok(message)) = message else { break };
+                last_heard = Instant::now();
+                connection.touch();
+
+                let reply = match message {
+                    Message::Text(text) => match serde_json::from_str::<ClientMessage>(&text) {
+                        Ok(ClientMessage::Ping) => Some(ServerMessage::Pong),
+                        Ok(ClientMessage::WatchPresence { user_ids }) => {
+                            let snapshot = connection.watch(user_ids).await;
+                            if !send_all(&mut socket, &snapshot).await {
+                                break;
+                            }
+                            None
+                        }
+                        Ok(ClientMessage::SendMessage { request_id, recipient_id, body }) => {
+                            Some(match connection.send_message(recipient_id, &body).await {
+                                Ok(message) => ServerMessage::Ack { request_id, message },
+                                Err(message) => ServerMessage::Error {
+                                    request_id: Some(request_id),
+                                    message,
+                                },
+                            })
+                        }
+                        Ok(ClientMessage::Ack { message_id }) => {
+                            if let Some(index) =
+                                awaiting_ack.iter().position(|message| message.id == message_id)
+                            {
+                                if let Some(message) = awaiting_ack.remove(index) {
+                                    connection.confirm_delivery(&message);
+                                }
+                            }
+                            None
+                        }
+                        Err(_) => Some(ServerMessage::Error {
+                            request_id: None,
+                            message: INVALID_MESSAGE.to_string(),
+                        }),
+                    },
+                    Message::Close(_) => break,
+                    // Protocol pings are answered by axum; pongs only prove the client is alive
+                    _ => None,
+                };
+                if let Some(reply) = reply {
+                    if !send(&mut socket, &reply).await {
+                        break;
+                    }
+                }
+            }
+            queued = outbox.recv() => {
+                // The hub closes the outbox of connections too slow to keep up, and of suspended users
+                let Some(queued) = queued else { break };
+                match &*queued {
+                    Push::Message(message) => {
+                        if !send(&mut socket, message).await {
+                            break;
+                        }
+                        if let ServerMessage::ChatMessage { message } = message {
+                            if message.sender_id != user_id {
+                                // More than the client could be behind are forgotten
+                                if awaiting_ack.len() == OUTBOX_CAPACITY {
+                                    awaiting_ack.pop_front();
+                                }
+                                awaiting_ack.push_back(message.clone());
+                            }
+                        }
+                    }
+                    // A blocked user is not told about deliveries either
+                    Push::Unwatch(other_id) => {
+                        connection.unwatch(*other_id);
+                        awaiting_ack.retain(|message| message.sender_id != *other_id);
+                    }
+                }
+            }
+            change = changes.recv() => match change {
+                Ok(change) if connection.is_watching(change.user_id) => {
+                    let message = ServerMessage::Presence {
+                        user_id: change.user_id,
+                        online: change.online,
+                        last_seen_at: Some(change.last_seen_at),
+                    };
+                    if !send(&mut socket, &message).await {
+                        break;
+                    }
+                }
+                // Missed changes are corrected by the next one for the same user
+                Ok(_) | Err(RecvError::Lagged(_)) => {}
+                Err(RecvError::Closed) => break,
+            },
+            _ = heartbeat.tick() => {
+                if last_heard.elapsed() > HEARTBEAT_TIMEOUT {
+                    break;
+                }
+                if !write(&mut socket, Message::Ping(Vec::new())).await {
+                    break;
+                }
+            }
+        }
+    }
+
+    connection.close();
+}
+
+/// Returns whether every message went out.
+async fn send_all(socket: &mut WebSocket, messages: &[ServerMessage]) -> bool {
+    for message in messages {
+        if !send(socket, message).await {
+            return false;
+        }
+    }
+    true
+}
+
+/// Returns whether the message went out; a failure means the connection is gone.
+async fn send(socket: &mut WebSocket, message: &ServerMessage) -> bool {
+    let text = match serde_json::to_string(message) {
+        Ok(text) => text,
+        Err(e) => {
+            error!("Failed to serialize websocket message: {}", e);
+            return true;
+        }
+    };
+    write(socket, Message::Text(text)).await
+}
+
+/// Returns whether the frame went out within `SEND_TIMEOUT`.
+pub(crate) async fn write(socket: &mut WebSocket, frame: Message) -> bool {
+    matches!(tokio::time::timeout(SEND_TIMEOUT, socket.send(frame)).await, Ok(Ok(())))
 }
@@ -0,0 +1,176 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::models::{Message, Notification};
+
+/// Messages clients send, as JSON objects tagged with `type`.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ClientMessage {
+    Ping,
+    /// Adds users to those whose presence is pushed, e.g. the profile being looked at.
+    WatchPresence {
+        user_ids: Vec<Uuid>,
+    },
+    /// Sends a chat message, answered with an `Ack` or an `Error` carrying `request_id`.
+    SendMessage {
+        request_id: String,
+        recipient_id: Uuid,
+        body: String,
+    },
+    /// The client received the `ChatMessage` with this id, which tells its sender.
+    Ack {
+        message_id: Uuid,
+    },
+}
+
+/// Messages the server sends, as JSON objects tagged with `type`.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ServerMessage {
+    Pong,
+    Presence {
+        user_id: Uuid,
+        online: bool,
+        last_seen_at: Option<DateTime<Utc>>,
+    },
+    /// A chat message to or from the user, sent from another connection.
+    ChatMessage {
+        message: Message,
+    },
+    /// A message the user sent reached one of the recipient's clients.
+    MessageDelivered {
+        message_id: Uuid,
+        conversation_id: Uuid,
+    },
+    Notification {
+        notification: Notification,
+    },
+    /// The message sent by the `SendMessage` request with this id was stored.
+    Ack {
+        request_id: String,
+        message: Message,
+    },
+    Error {
+        #[serde(skip_serializing_if = "Option::is_none")]
+        request_id: Option<String>,
+        message: String,
+    },
+}
+
+impl ServerMessage {
+    /// The `type` tag and the other fields, for transports that name events themselves.
+    pub fn event_parts(&self) -> Option<(String, serde_json::Value)> {
+        let Ok(serde_json::Value::Object(mut fields)) = serde_json::to_value(self) else {
+            return None;
+        };
+        match fields.remove("type") {
+            Some(serde_json::Value::String(name)) => Some((name, fields.into())),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn parses_client_messages() {
+        assert_eq!(
+            serde_json::from_value::<ClientMessage>(json!({"type": "ping"})).unwrap(),
+            ClientMessage::Ping
+        );
+        assert_eq!(
+            serde_json::from_value::<ClientMessage>(json!({
+                "type": "watch_presence",
+                "user_ids": [Uuid::nil()]
+            }))
+            .unwrap(),
+            ClientMessage::WatchPresence {
+                user_ids: vec![Uuid::nil()]
+            }
+        );
+        assert_eq!(
+            serde_json::from_value::<ClientMessage>(json!({
+                "type": "send_message",
+                "request_id": "1",
+                "recipient_id": Uuid::nil(),
+                "body": "Hi"
+            }))
+            .unwrap(),
+            ClientMessage::SendMessage {
+                request_id: "1".to_string(),
+                recipient_id: Uuid::nil(),
+                body: "Hi".to_string()
+            }
+        );
+        assert_eq!(
+            serde_json::from_value::<ClientMessage>(json!({
+                "type": "ack",
+                "message_id": Uuid::nil()
+            }))
+            .unwrap(),
+            ClientMessage::Ack {
+                message_id: Uuid::nil()
+            }
+        );
+        assert!(serde_json::from_value::<ClientMessage>(json!({"type": "shout"})).is_err());
+    }
+
+    #[test]
+    fn tags_server_messages() {
+        let message = ServerMessage::Presence {
+            user_id: Uuid::nil(),
+            online: true,
+            last_seen_at: None,
+        };
+
+        assert_eq!(
+            serde_json::to_value(&message).unwrap(),
+            json!({
+                "type": "presence",
+                "user_id": Uuid::nil(),
+                "online": true,
+                "last_seen_at": null
+            })
+        );
+        assert_eq!(serde_json::to_value(ServerMessage::Pong).unwrap(), json!({"type": "pong"}));
+        assert_eq!(
+            serde_json::to_value(ServerMessage::MessageDelivered {
+                message_id: Uuid::nil(),
+                conversation_id: Uuid::nil()
+            })
+            .unwrap(),
+            json!({
+                "type": "message_delivered",
+                "message_id": Uuid::nil(),
+                "conversation_id": Uuid::nil()
+            })
+        );
+        assert_eq!(
+            serde_json::to_value(ServerMessage::Error {
+                request_id: None,
+                message: "Invalid message".to_string()
+            })
+            .unwrap(),
+            json!({"type": "error", "message": "Invalid message"})
+        );
+    }
+
+    #[test]
+    fn splits_the_type_from_the_fields() {
+        let message = ServerMessage::Error {
+            request_id: Some("1".to_string()),
+            message: "Invalid message".to_string(),
+        };
+
+        assert_eq!(
+            message.event_parts(),
+            Some(("error".to_string(), json!({"request_id": "1", "message": "Invalid message"})))
+        );
+        assert_eq!(ServerMessage::Pong.event_parts(), Some(("pong".to_string(), json!({}))));
+    }
+}